@@ -1,9 +1,9 @@
 import { expect, test } from 'vitest'
 
-import nodeFetch from 'node-fetch';
+import nodeFetch, { Blob, FormData } from 'node-fetch';
 import makeFetchCookie from 'fetch-cookie';
 
-import { Api, generateId, FetchFn, UserId, ApiError, ApiResult } from "@";
+import { Api, generateId, FetchFn, UserId, ApiError, ApiResult, SlaPolicy, TicketTimelineItem, TicketFieldValue, InitiatedUpload, UploadId, UploadMetadata } from "@";
 import api from "../bindings/Api";
 
 const BASE_URL = "http://localhost:3000";
@@ -83,6 +83,24 @@ async function makeFakeUser(api: Api): Promise<UserId> {
     return userId;
 }
 
+async function postUploadContent(initiated: InitiatedUpload, metadata: UploadMetadata, content: string | Uint8Array) {
+    const form = new FormData();
+    for (const [name, value] of Object.entries(initiated.fields)) {
+        form.append(name, value);
+    }
+    form.append("file", new Blob([content], {type: metadata.content_type}));
+    const response = await nodeFetch(initiated.url, {method: "POST", body: form});
+    expect(response.ok).toBe(true);
+}
+
+async function uploadFile(api: Api, metadata: UploadMetadata, content: string | Uint8Array): Promise<UploadId> {
+    const initiated = unwrap(await api.initiateUpload(metadata));
+    await postUploadContent(initiated, metadata, content);
+
+    unwrap(await api.finalizeUpload(initiated.id));
+    return initiated.id;
+}
+
 test("get_me", async () => {
     const api = makeApi();
     const userId = await makeFakeUser(api);
@@ -98,6 +116,27 @@ test("get_me", async () => {
     expect(me.identities.telegram.photo_url).toBe(null);
 })
 
+test("delete_me", async () => {
+    const api = makeApi();
+    const userId = await makeFakeUser(api);
+
+    unwrap(await api.deleteMe());
+
+    // the profile is still available, but the personal data is gone
+    const profile = unwrap(await api.getUserProfile(userId));
+    expect(profile.id).toBe(userId);
+    expect(profile.name).toBe("Deleted user");
+
+    const anotherApi = makeApi();
+    await makeFakeUser(anotherApi);
+    const error = unwrapErr(await anotherApi.createTicket(generateId(), {
+        destination: { type: "User", id: userId },
+        title: "Are you there?",
+        body: "Hello?",
+    }));
+    expect(error.underlying_error).toBe("Bad request: a command referenced a related item that was not found");
+})
+
 async function make_groups(): Promise<{itDepartment: string, dormManager: string}> {
     const api0 = makeApi();
 
@@ -207,7 +246,9 @@ test("assign_ticket", async () => {
 
 test("group_ticket_list", async() => {
     const api = makeApi();
+    const memberApi = makeApi();
     const _userId = await makeFakeUser(api);
+    const memberId = await makeFakeUser(memberApi);
     const ticketId = generateId();
     const groupId = generateId();
 
@@ -222,6 +263,14 @@ test("group_ticket_list", async() => {
     const tickets = unwrap(await api.getGroupTickets(groupId)).payload;
     expect(tickets.length).toBe(1);
     expect(tickets[0].id).toBe(ticketId);
+
+    // plain members see the tickets of their group too
+    unwrapErr(await memberApi.getGroupTickets(groupId));
+    unwrap(await api.inviteGroupMember(groupId, memberId));
+    unwrap(await memberApi.acceptGroupInvitation(groupId));
+    expect(unwrap(await api.getGroup(groupId)).payload.roles[memberId]).toBe("Member");
+    const memberTickets = unwrap(await memberApi.getGroupTickets(groupId)).payload;
+    expect(memberTickets.map((ticket) => ticket.id)).toEqual([ticketId]);
 })
 
 test("user_groups", async() => {
@@ -348,14 +397,10 @@ test("search_users", async () => {
 test("edit_groups", async() => {
     const api = makeApi();
 
-    const userId = await makeFakeUser(api);
-    const anotherUserId = generateId();
+    const anotherApi = makeApi();
 
-    unwrap(await api.internalCreateUser(anotherUserId, {
-        type: "Telegram", id: 654321,
-        first_name: "Abra", last_name: "Cadabra",
-        username: "Abracadabra1337", photo_url: null,
-    }));
+    const userId = await makeFakeUser(api);
+    const anotherUserId = await makeFakeUser(anotherApi);
 
     const groupId = generateId();
 
@@ -366,7 +411,8 @@ test("edit_groups", async() => {
     expect(group.members.length).toBe(1);
     expect(group.members[0]).toBe(userId);
 
-    unwrap(await api.addGroupMember(groupId, anotherUserId));
+    unwrap(await api.inviteGroupMember(groupId, anotherUserId));
+    unwrap(await anotherApi.acceptGroupInvitation(groupId));
 
     const group2 = unwrap(await api.getGroup(groupId)).payload;
     expect(group2.title).toBe("Test group");
@@ -382,14 +428,63 @@ test("edit_groups", async() => {
     expect(group3.members[0]).toBe(userId);
     expect(group3.members[1]).toBe(anotherUserId);
 
-    // the new user removes the previous admin
-    // so sneaky!
+    expect(group3.roles[userId]).toBe("Owner");
+    expect(group3.roles[anotherUserId]).toBe("Member");
+
+    // the last owner cannot leave the group
+    const error = unwrapErr(await api.removeGroupMember(groupId, userId));
+    expect(error.underlying_error).toBe("The last owner of the group cannot be removed or demoted");
+
+    unwrap(await api.changeGroupMemberRole(groupId, anotherUserId, "Owner"));
     unwrap(await api.removeGroupMember(groupId, userId));
 
     const group4 = unwrap(await api.getGroup(groupId)).payload;
     expect(group4.title).toBe("New title");
     expect(group4.members.length).toBe(1);
     expect(group4.members[0]).toBe(anotherUserId);
+    expect(group4.roles[anotherUserId]).toBe("Owner");
+})
+
+test("group_roles", async() => {
+    const ownerApi = makeApi();
+    const memberApi = makeApi();
+
+    const ownerId = await makeFakeUser(ownerApi);
+    const memberId = await makeFakeUser(memberApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+
+    unwrap(await ownerApi.createGroup(groupId, {title: "Test group"}));
+    unwrap(await ownerApi.inviteGroupMember(groupId, memberId));
+    unwrap(await memberApi.acceptGroupInvitation(groupId));
+
+    // plain members cannot manage the group
+    unwrapErr(await memberApi.changeGroupTitle(groupId, "Hijacked"));
+    unwrapErr(await memberApi.removeGroupMember(groupId, ownerId));
+    unwrapErr(await memberApi.changeGroupMemberRole(groupId, memberId, "Admin"));
+
+    unwrap(await ownerApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Everything is broken",
+        body: "I can't do anything",
+    }));
+
+    // ...nor handle its tickets
+    unwrapErr(await memberApi.changeTicketStatus(ticketId, "InProgress"));
+
+    unwrap(await ownerApi.changeGroupMemberRole(groupId, memberId, "Agent"));
+    unwrap(await memberApi.changeTicketStatus(ticketId, "InProgress"));
+
+    // admins cannot touch owners
+    unwrap(await ownerApi.changeGroupMemberRole(groupId, memberId, "Admin"));
+    unwrapErr(await memberApi.changeGroupMemberRole(groupId, ownerId, "Member"));
+    unwrapErr(await memberApi.changeGroupMemberRole(groupId, memberId, "Owner"));
+    unwrap(await memberApi.changeGroupTitle(groupId, "New title"));
+
+    // but anyone can leave
+    unwrap(await memberApi.removeGroupMember(groupId, memberId));
+    const group = unwrap(await ownerApi.getGroup(groupId)).payload;
+    expect(group.members).toEqual([ownerId]);
 })
 
 test("nonexistent stuff errors", async () => {
@@ -400,6 +495,947 @@ test("nonexistent stuff errors", async () => {
     const nonExistentUserId = generateId();
 
     unwrap(await api.createGroup(groupId, {title: "Test group"}));
-    const error = unwrapErr(await api.addGroupMember(groupId, nonExistentUserId));
+    const error = unwrapErr(await api.inviteGroupMember(groupId, nonExistentUserId));
     expect(error.underlying_error).toBe("Bad request: a command referenced a related item that was not found");
-})
\ No newline at end of file
+})
+
+test("group_invitations", async () => {
+    const ownerApi = makeApi();
+    const userApi = makeApi();
+
+    const _ownerId = await makeFakeUser(ownerApi);
+    const userId = await makeFakeUser(userApi);
+    const groupId = generateId();
+
+    unwrap(await ownerApi.createGroup(groupId, {title: "Test group"}));
+    unwrap(await ownerApi.inviteGroupMember(groupId, userId));
+
+    // the invitation does not make the user a member by itself
+    const group = unwrap(await ownerApi.getGroup(groupId)).payload;
+    expect(group.members).not.toContain(userId);
+
+    const {payload: invitations} = unwrap(await ownerApi.getGroupInvitations(groupId));
+    expect(invitations.invitations).toEqual([userId]);
+    // the invited user cannot see the invitations of the group
+    unwrapErr(await userApi.getGroupInvitations(groupId));
+
+    const {groups, payload: myInvitations} = unwrap(await userApi.getMyInvitations());
+    expect(myInvitations.items).toEqual([groupId]);
+    expect(groups[groupId].title).toBe("Test group");
+
+    unwrap(await userApi.declineGroupInvitation(groupId));
+    expect(unwrap(await userApi.getMyInvitations()).payload.items.length).toBe(0);
+    unwrapErr(await userApi.acceptGroupInvitation(groupId));
+
+    // the group is not discoverable yet
+    unwrapErr(await userApi.requestToJoinGroup(groupId));
+
+    unwrap(await ownerApi.changeGroupDiscoverability(groupId, true));
+    unwrap(await userApi.requestToJoinGroup(groupId));
+    expect(unwrap(await ownerApi.getGroupInvitations(groupId)).payload.join_requests).toEqual([userId]);
+
+    unwrap(await ownerApi.approveGroupJoinRequest(groupId, userId));
+
+    const group2 = unwrap(await ownerApi.getGroup(groupId)).payload;
+    expect(group2.members).toContain(userId);
+    expect(group2.roles[userId]).toBe("Member");
+})
+
+test("group_hierarchy", async () => {
+    const api = makeApi();
+    const agentApi = makeApi();
+    const memberApi = makeApi();
+
+    const _userId = await makeFakeUser(api);
+    const agentId = await makeFakeUser(agentApi);
+    const memberId = await makeFakeUser(memberApi);
+    const itDepartment = generateId();
+    const network = generateId();
+    const wifi = generateId();
+    const ticketId = generateId();
+
+    unwrap(await api.createGroup(itDepartment, {title: "IT"}));
+    unwrap(await api.createGroup(network, {title: "Network", parent: itDepartment}));
+    unwrap(await api.createGroup(wifi, {title: "Wi-Fi"}));
+    unwrap(await api.changeGroupParent(wifi, network));
+
+    const {groups, payload: tree} = unwrap(await api.getGroupTree(itDepartment));
+    expect(tree.ancestors).toEqual([]);
+    expect(tree.children[itDepartment]).toEqual([network]);
+    expect(tree.children[network]).toEqual([wifi]);
+    expect(groups[wifi].title).toBe("Wi-Fi");
+
+    expect(unwrap(await api.getGroupTree(wifi)).payload.ancestors).toEqual([itDepartment, network]);
+
+    // a group cannot be moved under its own sub-group
+    const error = unwrapErr(await api.changeGroupParent(itDepartment, wifi));
+    expect(error.underlying_error).toBe("A group cannot be placed under itself or one of its sub-groups");
+    unwrapErr(await api.changeGroupParent(wifi, wifi));
+
+    // agents of a sub-group can handle the tickets of the parent group
+    unwrap(await api.inviteGroupMember(network, agentId));
+    unwrap(await agentApi.acceptGroupInvitation(network));
+    unwrap(await api.changeGroupMemberRole(network, agentId, "Agent"));
+
+    unwrap(await api.createTicket(ticketId, {
+        destination: { type: "Group", id: itDepartment },
+        title: "Everything is broken",
+        body: "I can't do anything",
+    }));
+
+    const tickets = unwrap(await agentApi.getGroupTickets(itDepartment)).payload;
+    expect(tickets.map((ticket) => ticket.id)).toEqual([ticketId]);
+    unwrap(await agentApi.changeTicketStatus(ticketId, "InProgress"));
+
+    // plain members of a sub-group do not
+    unwrap(await api.inviteGroupMember(wifi, memberId));
+    unwrap(await memberApi.acceptGroupInvitation(wifi));
+    unwrapErr(await memberApi.getGroupTickets(itDepartment));
+    unwrapErr(await memberApi.changeTicketStatus(ticketId, "Done"));
+})
+
+test("ticket_auto_assignment", async () => {
+    const api = makeApi();
+    const agentApi = makeApi();
+    const requesterApi = makeApi();
+
+    const ownerId = await makeFakeUser(api);
+    const agentId = await makeFakeUser(agentApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+
+    unwrap(await api.createGroup(groupId, {title: "Helpdesk"}));
+    unwrap(await api.inviteGroupMember(groupId, agentId));
+    unwrap(await agentApi.acceptGroupInvitation(groupId));
+    unwrap(await api.changeGroupMemberRole(groupId, agentId, "Agent"));
+
+    // only admins can change the strategy
+    unwrapErr(await agentApi.changeGroupAssignmentStrategy(groupId, "RoundRobin"));
+    unwrap(await api.changeGroupAssignmentStrategy(groupId, "RoundRobin"));
+    expect(unwrap(await api.getGroup(groupId)).payload.assignment_strategy).toBe("RoundRobin");
+
+    const createTicket = async () => {
+        const ticketId = generateId();
+        unwrap(await requesterApi.createTicket(ticketId, {
+            destination: { type: "Group", id: groupId },
+            title: "Printer is out of paper",
+            body: "Again",
+        }));
+        return ticketId;
+    };
+    const assigneeOf = async (ticketId: string) =>
+        unwrap(await api.getTicket(ticketId)).payload.assignee;
+
+    const first = await createTicket();
+    const second = await createTicket();
+    const third = await createTicket();
+    expect(await assigneeOf(first)).toBe(ownerId);
+    expect(await assigneeOf(second)).toBe(agentId);
+    expect(await assigneeOf(third)).toBe(ownerId);
+
+    // the owner has two open tickets, the agent only one
+    unwrap(await api.changeGroupAssignmentStrategy(groupId, "LeastOpen"));
+    expect(await assigneeOf(await createTicket())).toBe(agentId);
+
+    // closed tickets do not count
+    unwrap(await api.changeTicketStatus(first, "Fixed"));
+    expect(await assigneeOf(await createTicket())).toBe(ownerId);
+
+    unwrap(await api.changeGroupAssignmentStrategy(groupId, "Manual"));
+    expect(await assigneeOf(await createTicket())).toBe(null);
+})
+
+test("group_workflows", async () => {
+    const api = makeApi();
+    const requesterApi = makeApi();
+
+    const _ownerId = await makeFakeUser(api);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+
+    unwrap(await api.createGroup(groupId, {title: "Maintenance"}));
+
+    // the initial status must be one of the statuses
+    unwrapErr(await api.changeGroupWorkflow(groupId, {
+        statuses: [{id: "New", label: "New", category: "Open"}],
+        initial: "Fixed",
+        transitions: {},
+    }));
+
+    unwrap(await api.changeGroupWorkflow(groupId, {
+        statuses: [
+            {id: "New", label: "New", category: "Open"},
+            {id: "AwaitingParts", label: "Awaiting parts", category: "Open"},
+            {id: "Done", label: "Done", category: "Closed"},
+        ],
+        initial: "New",
+        transitions: {
+            "New": ["AwaitingParts"],
+            "AwaitingParts": ["Done"],
+        },
+    }));
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Broken chair",
+        body: "One of its legs fell off",
+    }));
+
+    let ticket = unwrap(await api.getTicket(ticketId)).payload;
+    expect(ticket.status).toBe("New");
+    expect(ticket.status_label).toBe("New");
+    expect(ticket.status_category).toBe("Open");
+
+    unwrapErr(await api.changeTicketStatus(ticketId, "Fixed"));
+    const error = unwrapErr(await api.changeTicketStatus(ticketId, "Done"));
+    expect(error.underlying_error).toBe("The ticket workflow does not allow changing the status from `New` to `Done`");
+
+    unwrap(await api.changeTicketStatus(ticketId, "AwaitingParts"));
+    unwrap(await api.changeTicketStatus(ticketId, "Done"));
+
+    ticket = unwrap(await api.getTicket(ticketId)).payload;
+    expect(ticket.status_label).toBe("Done");
+    expect(ticket.status_category).toBe("Closed");
+})
+
+test("ticket_priority_and_sla", async () => {
+    const api = makeApi();
+    const requesterApi = makeApi();
+
+    const _ownerId = await makeFakeUser(api);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+
+    unwrap(await api.createGroup(groupId, {title: "Facilities"}));
+    unwrap(await api.changeGroupSlaPolicy(groupId, {
+        targets: {
+            "Urgent": {first_response_minutes: 60, resolution_minutes: 24 * 60},
+        } as SlaPolicy["targets"],
+    }));
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Water leak",
+        body: "The ceiling is dripping",
+    }));
+
+    const listedTicket = async () =>
+        unwrap(await api.getGroupTickets(groupId)).payload.find((ticket) => ticket.id === ticketId)!;
+
+    // there are no targets for the normal priority
+    expect((await listedTicket()).priority).toBe("Normal");
+    expect((await listedTicket()).sla).toBe("None");
+
+    // only the destination side can prioritize the tickets
+    unwrapErr(await requesterApi.changeTicketPriority(ticketId, "Urgent"));
+    unwrap(await api.changeTicketPriority(ticketId, "Urgent"));
+    expect((await listedTicket()).sla).toBe("OnTrack");
+
+    // an explicit due date overrides the resolution target
+    const yesterday = new Date(Date.now() - 24 * 60 * 60 * 1000).toISOString();
+    unwrap(await api.setTicketDueDate(ticketId, yesterday));
+    expect((await listedTicket()).sla).toBe("Breached");
+
+    unwrap(await api.sendTicketMessage(ticketId, {body: "On our way"}));
+    unwrap(await api.changeTicketStatus(ticketId, "Fixed"));
+    expect((await listedTicket()).sla).toBe("None");
+
+    const ticket = unwrap(await api.getTicket(ticketId)).payload;
+    expect(ticket.priority).toBe("Urgent");
+    expect(ticket.due_date).not.toBeNull();
+    expect(ticket.timeline.map((item) => item.content.type)).toContain("PriorityChange");
+    expect(ticket.timeline.map((item) => item.content.type)).toContain("DueDateChange");
+})
+
+test("ticket_labels", async () => {
+    const api = makeApi();
+    const requesterApi = makeApi();
+
+    const _ownerId = await makeFakeUser(api);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+    const title = `Projector ${generateId()}`;
+
+    unwrap(await api.createGroup(groupId, {title: "Classroom equipment"}));
+    unwrap(await api.createGroupLabel(groupId, "hardware", "#ff0000"));
+    unwrapErr(await api.createGroupLabel(groupId, "hardware", "#00ff00"));
+    expect(unwrap(await api.getGroup(groupId)).payload.labels).toEqual([{name: "hardware", color: "#ff0000"}]);
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title,
+        body: "It does not turn on",
+    }));
+
+    // only the destination side can label the tickets, and only with labels from the catalog
+    unwrapErr(await requesterApi.addTicketLabel(ticketId, "hardware"));
+    unwrapErr(await api.addTicketLabel(ticketId, "software"));
+    unwrap(await api.addTicketLabel(ticketId, "hardware"));
+
+    expect(unwrap(await api.getTicket(ticketId)).payload.labels).toEqual([{name: "hardware", color: "#ff0000"}]);
+    const listed = unwrap(await api.getGroupTickets(groupId)).payload;
+    expect(listed[0].labels.map((label) => label.name)).toEqual(["hardware"]);
+
+    const hits = unwrap(await api.searchTickets(title, "hardware")).top_hits;
+    expect(hits.map((hit) => hit.value.id)).toEqual([ticketId]);
+    expect(unwrap(await api.searchTickets(title, "software")).top_hits).toEqual([]);
+
+    unwrap(await api.removeTicketLabel(ticketId, "hardware"));
+    expect(unwrap(await api.getTicket(ticketId)).payload.labels).toEqual([]);
+})
+
+test("ticket_rerouting", async () => {
+    const firstApi = makeApi();
+    const secondApi = makeApi();
+    const requesterApi = makeApi();
+
+    const _firstId = await makeFakeUser(firstApi);
+    const _secondId = await makeFakeUser(secondApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const firstGroupId = generateId();
+    const secondGroupId = generateId();
+    const ticketId = generateId();
+
+    unwrap(await firstApi.createGroup(firstGroupId, {title: "Network"}));
+    unwrap(await secondApi.createGroup(secondGroupId, {title: "Classroom equipment"}));
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: firstGroupId },
+        title: "Projector",
+        body: "It does not turn on",
+    }));
+
+    // the owner can re-route the ticket while it is pending
+    unwrap(await requesterApi.changeTicketDestination(ticketId, { type: "Group", id: secondGroupId }));
+
+    unwrapErr(await firstApi.changeTicketPriority(ticketId, "High"));
+    unwrap(await secondApi.changeTicketPriority(ticketId, "High"));
+    const ticket = unwrap(await secondApi.getTicket(ticketId)).payload;
+    expect(ticket.destination).toEqual({ type: "Group", id: secondGroupId });
+    expect(ticket.assignee).toEqual(null);
+    expect(ticket.timeline.map((item) => item.content.type)).toContain("DestinationChange");
+
+    expect(unwrap(await firstApi.getGroupTickets(firstGroupId)).payload).toEqual([]);
+    const listed = unwrap(await secondApi.getGroupTickets(secondGroupId)).payload;
+    expect(listed.map((item) => item.id)).toEqual([ticketId]);
+
+    // once work has started only the destination side can re-route it
+    unwrap(await secondApi.changeTicketStatus(ticketId, "InProgress"));
+    unwrapErr(await requesterApi.changeTicketDestination(ticketId, { type: "Group", id: firstGroupId }));
+})
+
+test("ticket_watchers", async () => {
+    const agentApi = makeApi();
+    const managerApi = makeApi();
+    const requesterApi = makeApi();
+    const outsiderApi = makeApi();
+
+    const agentId = await makeFakeUser(agentApi);
+    const managerId = await makeFakeUser(managerApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const _outsiderId = await makeFakeUser(outsiderApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+
+    unwrap(await agentApi.createGroup(groupId, {title: "Classrooms"}));
+    unwrap(await agentApi.inviteGroupMember(groupId, managerId));
+    unwrap(await managerApi.acceptGroupInvitation(groupId));
+    unwrap(await agentApi.changeGroupMemberRole(groupId, managerId, "Agent"));
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Projector",
+        body: "It does not turn on",
+    }));
+
+    // only the owner and the destination side can follow the ticket
+    unwrapErr(await outsiderApi.watchTicket(ticketId));
+    expect(unwrap(await outsiderApi.getWatchedTickets()).payload).toEqual([]);
+    // writing to the ticket does not get them around it
+    unwrap(await outsiderApi.sendTicketMessage(ticketId, {body: "Same here"}));
+    expect(unwrap(await outsiderApi.getWatchedTickets()).payload).toEqual([]);
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.watchers).toEqual([]);
+
+    unwrap(await managerApi.watchTicket(ticketId));
+    // watching twice is a no-op
+    unwrap(await managerApi.watchTicket(ticketId));
+    expect(unwrap(await managerApi.getWatchedTickets()).payload.map((item) => item.id)).toEqual([ticketId]);
+
+    // message authors follow the ticket automatically
+    unwrap(await agentApi.sendTicketMessage(ticketId, {body: "On my way"}));
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.watchers).toEqual([managerId, agentId]);
+    expect(unwrap(await agentApi.getWatchedTickets()).payload.map((item) => item.id)).toEqual([ticketId]);
+
+    unwrap(await managerApi.unwatchTicket(ticketId));
+    expect(unwrap(await managerApi.getWatchedTickets()).payload).toEqual([]);
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.watchers).toEqual([agentId]);
+})
+
+test("ticket_internal_notes", async () => {
+    const agentApi = makeApi();
+    const requesterApi = makeApi();
+    const anonymousApi = makeApi();
+
+    const agentId = await makeFakeUser(agentApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const _anonymousId = await makeFakeUser(anonymousApi);
+    const ticketId = generateId();
+    const title = `Projector ${generateId()}`;
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "User", id: agentId },
+        title,
+        body: "It does not turn on",
+    }));
+
+    // only the handling side can write internal notes
+    unwrapErr(await requesterApi.sendTicketInternalNote(ticketId, {body: "Please hurry"}));
+    unwrap(await agentApi.sendTicketInternalNote(ticketId, {body: "Probably the lamp again"}));
+
+    const isNote = (item: TicketTimelineItem) => item.content.type === "InternalNote";
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.timeline.filter(isNote).length).toEqual(1);
+    expect(unwrap(await requesterApi.getTicket(ticketId)).payload.timeline.filter(isNote)).toEqual([]);
+    expect(unwrap(await anonymousApi.getTicket(ticketId)).payload.timeline.filter(isNote)).toEqual([]);
+
+    const hits = unwrap(await anonymousApi.searchTickets(title)).top_hits;
+    expect(hits.flatMap((hit) => hit.value.timeline.filter(isNote))).toEqual([]);
+    expect(hits.flatMap((hit) => hit.highlights.timeline.filter(isNote))).toEqual([]);
+    // the notes are not searchable either
+    const noteHits = unwrap(await anonymousApi.searchTickets("Probably the lamp again")).top_hits;
+    expect(noteHits.map((hit) => hit.value.id)).not.toContain(ticketId);
+})
+
+test("ticket_message_editing", async () => {
+    const agentApi = makeApi();
+    const requesterApi = makeApi();
+
+    const _agentId = await makeFakeUser(agentApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+
+    unwrap(await agentApi.createGroup(groupId, {title: "Accounts"}));
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Can't log in",
+        body: "My password is hunter3",
+    }));
+
+    const messages = async () => unwrap(await agentApi.getTicket(ticketId)).payload.timeline
+        .flatMap((item) => item.content.type === "Message" ? [item.content] : []);
+    const messageId = (await messages())[0].id;
+
+    // only the author can edit the message
+    unwrapErr(await agentApi.editTicketMessage(ticketId, messageId, "Nothing to see here"));
+    unwrap(await requesterApi.editTicketMessage(ticketId, messageId, "My password is hunter2"));
+    let message = (await messages())[0];
+    expect(message.text).toEqual("My password is hunter2");
+    expect(message.revisions.map((revision) => revision.text)).toEqual(["My password is hunter3"]);
+
+    // admins of the destination group can redact it
+    unwrap(await agentApi.redactTicketMessage(ticketId, messageId));
+    message = (await messages())[0];
+    expect(message.redacted).toEqual(true);
+    expect(message.text).toEqual("");
+    expect(message.revisions).toEqual([]);
+
+    unwrapErr(await requesterApi.editTicketMessage(ticketId, messageId, "Oops"));
+    unwrapErr(await requesterApi.editTicketMessage(ticketId, generateId(), "Oops"));
+})
+
+test("ticket_relations", async () => {
+    const agentApi = makeApi();
+    const firstRequesterApi = makeApi();
+    const secondRequesterApi = makeApi();
+
+    const _agentId = await makeFakeUser(agentApi);
+    const firstRequesterId = await makeFakeUser(firstRequesterApi);
+    const secondRequesterId = await makeFakeUser(secondRequesterApi);
+    const groupId = generateId();
+    const primaryId = generateId();
+    const duplicateId = generateId();
+    const blockerId = generateId();
+
+    unwrap(await agentApi.createGroup(groupId, {title: "Network"}));
+    for (const [api, id] of [[firstRequesterApi, primaryId], [secondRequesterApi, duplicateId], [agentApi, blockerId]] as const) {
+        unwrap(await api.createTicket(id, {
+            destination: { type: "Group", id: groupId },
+            title: "No internet in the dorm",
+            body: "The network is down",
+        }));
+    }
+
+    // links are mirrored on the other ticket
+    unwrapErr(await firstRequesterApi.linkTicket(primaryId, blockerId, "BlockedBy"));
+    unwrapErr(await agentApi.linkTicket(primaryId, primaryId, "Related"));
+    unwrap(await agentApi.linkTicket(primaryId, blockerId, "BlockedBy"));
+    expect(unwrap(await agentApi.getTicket(blockerId)).payload.links).toEqual([{ticket: primaryId, kind: "Blocks"}]);
+    unwrap(await agentApi.unlinkTicket(blockerId, primaryId));
+    expect(unwrap(await agentApi.getTicket(primaryId)).payload.links).toEqual([]);
+
+    unwrap(await secondRequesterApi.watchTicket(duplicateId));
+    unwrap(await agentApi.mergeTicket(duplicateId, primaryId));
+    unwrapErr(await agentApi.mergeTicket(duplicateId, primaryId));
+
+    const duplicate = unwrap(await agentApi.getTicket(duplicateId)).payload;
+    expect(duplicate.merged_into).toEqual(primaryId);
+    expect(duplicate.status_category).toEqual("Closed");
+    expect(duplicate.links).toEqual([{ticket: primaryId, kind: "DuplicateOf"}]);
+
+    const primary = unwrap(await agentApi.getTicket(primaryId)).payload;
+    expect(primary.links).toEqual([{ticket: duplicateId, kind: "DuplicatedBy"}]);
+    expect(primary.watchers).toEqual([secondRequesterId]);
+    expect(primary.watchers).not.toContain(firstRequesterId);
+    expect(primary.timeline.map((item) => item.content.type)).toContain("DuplicateMerged");
+})
+
+test("ticket_forms", async () => {
+    const agentApi = makeApi();
+    const requesterApi = makeApi();
+
+    const _agentId = await makeFakeUser(agentApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+
+    unwrap(await agentApi.createGroup(groupId, {title: "Dormitory"}));
+    unwrapErr(await agentApi.changeGroupForm(groupId, {fields: [
+        {id: "room", label: "Room", required: true, kind: {type: "Number", min: 10, max: 1}},
+    ]}));
+    unwrap(await agentApi.changeGroupForm(groupId, {fields: [
+        {id: "room", label: "Room", required: true, kind: {type: "Number", min: 1, max: 500}},
+        {id: "kind", label: "Kind", required: false, kind: {type: "Select", options: ["Plumbing", "Electricity"], multiple: false}},
+    ]}));
+
+    const create = (fields: Record<string, TicketFieldValue>) => requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Leaking tap",
+        body: "The tap in the bathroom is leaking",
+        fields,
+    });
+
+    unwrapErr(await create({}));
+    unwrapErr(await create({room: {type: "Number", value: 1000}}));
+    unwrapErr(await create({room: {type: "Text", value: "42"}}));
+    unwrapErr(await create({room: {type: "Number", value: 42}, kind: {type: "Select", value: ["Heating"]}}));
+    unwrapErr(await create({room: {type: "Number", value: 42}, floor: {type: "Number", value: 4}}));
+    unwrap(await create({kind: {type: "Select", value: ["Plumbing"]}, room: {type: "Number", value: 42}}));
+
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.fields).toEqual([
+        {id: "room", label: "Room", value: {type: "Number", value: 42}},
+        {id: "kind", label: "Kind", value: {type: "Select", value: ["Plumbing"]}},
+    ]);
+})
+
+test("ticket_title_change", async () => {
+    const agentApi = makeApi();
+    const adminApi = makeApi();
+    const requesterApi = makeApi();
+
+    const agentId = await makeFakeUser(agentApi);
+    const _adminId = await makeFakeUser(adminApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+    const ticketId = generateId();
+    const newTitle = `Broken projector in room ${generateId()}`;
+
+    unwrap(await adminApi.createGroup(groupId, {title: "Classroom equipment"}));
+    unwrap(await adminApi.inviteGroupMember(groupId, agentId));
+    unwrap(await agentApi.acceptGroupInvitation(groupId));
+    unwrap(await adminApi.changeGroupMemberRole(groupId, agentId, "Agent"));
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Projector",
+        body: "It does not turn on",
+    }));
+
+    // agents can't rename the tickets, admins and the owner can
+    unwrapErr(await agentApi.changeTicketTitle(ticketId, "Nothing"));
+    unwrap(await adminApi.changeTicketTitle(ticketId, "Projector does not work"));
+    unwrap(await requesterApi.changeTicketTitle(ticketId, newTitle));
+
+    const ticket = unwrap(await agentApi.getTicket(ticketId)).payload;
+    expect(ticket.title).toEqual(newTitle);
+    expect(ticket.timeline.filter((item) => item.content.type === "TitleChange").length).toEqual(2);
+
+    const listed = unwrap(await agentApi.getGroupTickets(groupId)).payload;
+    expect(listed.map((item) => item.title)).toEqual([newTitle]);
+    const hits = unwrap(await agentApi.searchTickets(newTitle)).top_hits;
+    expect(hits.map((hit) => hit.value.id)).toEqual([ticketId]);
+})
+
+test("upload_expiry", async () => {
+    const api = makeApi();
+    await makeFakeUser(api);
+
+    const metadata = {filename: "broken_projector.png", content_type: "image/png", size: 1024};
+    const abandoned = unwrap(await api.initiateUpload(metadata));
+
+    // the file was never uploaded, but the upload is kept until the presigned post expires
+    unwrap(await api.internalAdvanceFakeClock("29m"));
+    expect(unwrapErr(await api.finalizeUpload(abandoned.id)).underlying_error).not.toContain("dropped");
+
+    unwrap(await api.internalAdvanceFakeClock("2m"));
+    expect(unwrapErr(await api.finalizeUpload(abandoned.id)).underlying_error).toContain("already dropped");
+})
+
+test("upload_thumbnails", async () => {
+    const api = makeApi();
+    const strangerApi = makeApi();
+    await makeFakeUser(api);
+    await makeFakeUser(strangerApi);
+
+    // a 1x1 transparent pixel
+    const png = new Uint8Array([
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+        0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+        0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4, 0x89, 0x00, 0x00, 0x00,
+        0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x64, 0x60, 0xf8, 0x5f,
+        0x0f, 0x00, 0x02, 0x87, 0x01, 0x80, 0xeb, 0x47, 0xba, 0x92, 0x00, 0x00,
+        0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ]);
+    const image = await uploadFile(api, {filename: "broken_lamp.png", content_type: "image/png", size: png.length}, png);
+    const text = "Lamp model: TL-5";
+    const textUpload = await uploadFile(api, {filename: "lamp.txt", content_type: "text/plain", size: text.length}, text);
+
+    // the thumbnail is made in background, so wait for it
+    let thumbnailUrl: string | null = null;
+    for (let attempt = 0; attempt < 50 && thumbnailUrl === null; attempt++) {
+        const result = await api.getUploadThumbnailLocation(image);
+        if (result.status === "Success") {
+            thumbnailUrl = result.payload;
+        } else {
+            await new Promise((resolve) => setTimeout(resolve, 100));
+        }
+    }
+    expect(thumbnailUrl).not.toBeNull();
+    const thumbnail = new Uint8Array(await (await nodeFetch(thumbnailUrl!)).arrayBuffer());
+    expect(Array.from(thumbnail.slice(0, 8))).toEqual([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]);
+
+    // only images get a thumbnail, and only those who can download the file can see it
+    expect(unwrapErr(await api.getUploadThumbnailLocation(textUpload)).underlying_error).toContain("not found");
+    unwrapErr(await strangerApi.getUploadThumbnailLocation(image));
+})
+
+test("upload_discard", async () => {
+    const api = makeApi();
+    await makeFakeUser(api);
+
+    const content = "Projector model: EB-X41";
+    const uploadId = await uploadFile(api, {filename: "projector.txt", content_type: "text/plain", size: content.length}, content);
+
+    const objectUrl = unwrap(await api.getUploadFileLocation(uploadId));
+    expect(await (await nodeFetch(objectUrl)).text()).toBe(content);
+
+    // the uploads a ticket refers to through its form are kept
+    const groupId = generateId();
+    const ticketId = generateId();
+    unwrap(await api.createGroup(groupId, {title: "Classrooms"}));
+    unwrap(await api.changeGroupForm(groupId, {fields: [
+        {id: "label", label: "Label photo", required: true, kind: {type: "Upload", max_files: 1}},
+    ]}));
+    const photoId = await uploadFile(api, {filename: "label.txt", content_type: "text/plain", size: content.length}, content);
+    unwrap(await api.createTicket(ticketId, {
+        destination: { type: "Group", id: groupId },
+        title: "Projector",
+        body: "It does not turn on",
+        fields: {label: {type: "Upload", value: [photoId]}},
+    }));
+
+    // nothing refers to the upload, so it is removed once the claim window passes
+    unwrap(await api.internalAdvanceFakeClock("25h"));
+    expect(unwrapErr(await api.finalizeUpload(uploadId)).underlying_error).toContain("already discarded");
+    expect((await nodeFetch(objectUrl)).status).toBe(404);
+
+    const photoUrl = unwrap(await api.getUploadFileLocation(photoId));
+    expect(await (await nodeFetch(photoUrl)).text()).toBe(content);
+})
+
+test("local_storage_signatures", async () => {
+    const api = makeApi();
+    await makeFakeUser(api);
+
+    const content = "Room 108, window handle";
+    const metadata = {filename: "handle.txt", content_type: "text/plain", size: content.length};
+    const initiated = unwrap(await api.initiateUpload(metadata));
+
+    // the fields are signed, so the file can't be put under another key
+    const form = new FormData();
+    for (const [name, value] of Object.entries(initiated.fields)) {
+        form.append(name, name === "key" ? value + ".exe" : value);
+    }
+    form.append("file", new Blob([content], {type: metadata.content_type}));
+    expect((await nodeFetch(initiated.url, {method: "POST", body: form})).status).toBe(403);
+
+    await postUploadContent(initiated, metadata, content);
+    unwrap(await api.finalizeUpload(initiated.id));
+
+    const objectUrl = new URL(unwrap(await api.getUploadFileLocation(initiated.id)));
+    const response = await nodeFetch(objectUrl);
+    expect(response.headers.get("content-disposition")).toBe('attachment; filename="handle.txt"');
+    expect(await response.text()).toBe(content);
+
+    objectUrl.searchParams.set("filename", "other.txt");
+    expect((await nodeFetch(objectUrl)).status).toBe(403);
+})
+
+test("upload_verification", async () => {
+    const api = makeApi();
+    await makeFakeUser(api);
+
+    const png = new Uint8Array([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 0x0d]);
+    const metadata = {filename: "broken_chair.png", content_type: "image/png", size: png.length};
+    const initiated = unwrap(await api.initiateUpload(metadata));
+
+    // a file of another type and size is rejected and deleted
+    await postUploadContent(initiated, metadata, "#!/bin/sh\nrm -rf /\n");
+    const violations = unwrapErr(await api.finalizeUpload(initiated.id)).underlying_error;
+    expect(violations).toContain("SizeMismatch");
+    expect(violations).toContain("UnexpectedContent");
+
+    // the client can retry while the upload is pending
+    await postUploadContent(initiated, metadata, png);
+    unwrap(await api.finalizeUpload(initiated.id));
+})
+
+test("upload_quota", async () => {
+    const api = makeApi();
+    await makeFakeUser(api);
+
+    const {usage, quota} = unwrap(await api.getUploadUsage());
+    expect(Number(usage.files)).toBe(0);
+
+    // the pending uploads count towards the quota too
+    const metadata = {filename: "lecture.mp4", content_type: "video/mp4", size: 10 * 1024 * 1024};
+    const count = Number(quota.max_total_size) / metadata.size;
+    for (let i = 0; i < count; i++) {
+        unwrap(await api.initiateUpload(metadata));
+    }
+    const filled = unwrap(await api.getUploadUsage()).usage;
+    expect(Number(filled.files)).toBe(count);
+    expect(Number(filled.total_size)).toBe(Number(quota.max_total_size));
+    expect(unwrapErr(await api.initiateUpload(metadata)).underlying_error).toContain("QuotaExceeded");
+
+    // the abandoned uploads free the space
+    unwrap(await api.internalAdvanceFakeClock("31m"));
+    expect(Number(unwrap(await api.getUploadUsage()).usage.files)).toBe(0);
+    unwrap(await api.initiateUpload(metadata));
+
+    // concurrent initiations can't overrun the quota together
+    const results = await Promise.all(Array.from({length: count}, () => api.initiateUpload(metadata)));
+    expect(results.filter((result) => result.status === "Success").length).toBe(count - 1);
+    expect(Number(unwrap(await api.getUploadUsage()).usage.total_size)).toBe(Number(quota.max_total_size));
+})
+
+test("ticket_attachments", async () => {
+    const requesterApi = makeApi();
+    const agentApi = makeApi();
+
+    const _requesterId = await makeFakeUser(requesterApi);
+    const agentId = await makeFakeUser(agentApi);
+    const ticketId = generateId();
+
+    const content = "Error 0x80070005";
+    const metadata = {filename: "error.txt", content_type: "text/plain", size: content.length};
+    const screenshot = await uploadFile(requesterApi, metadata, content);
+    const log = await uploadFile(requesterApi, metadata, content);
+    const pending = unwrap(await requesterApi.initiateUpload(metadata)).id;
+    const agentUpload = await uploadFile(agentApi, metadata, content);
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "User", id: agentId },
+        title: "Can't install updates",
+        body: "The error is attached",
+        attachments: [screenshot],
+    }));
+
+    // only the finalized uploads of the sender can be attached
+    unwrapErr(await requesterApi.sendTicketMessage(ticketId, {body: "Not uploaded yet", attachments: [pending]}));
+    unwrapErr(await requesterApi.sendTicketMessage(ticketId, {body: "Not mine", attachments: [agentUpload]}));
+    unwrap(await requesterApi.sendTicketMessage(ticketId, {body: "And the log", attachments: [log]}));
+
+    const ticket = unwrap(await agentApi.getTicket(ticketId));
+    const attachments = ticket.payload.timeline.flatMap((item) =>
+        item.content.type === "Message" ? item.content.attachments : []);
+    expect(attachments).toEqual([screenshot, log]);
+    expect(ticket.uploads[screenshot].filename).toBe("error.txt");
+    expect(ticket.uploads[log].size).toBe(content.length);
+
+    // the attached uploads are kept after the claim window
+    unwrap(await agentApi.internalAdvanceFakeClock("25h"));
+    expect(Object.keys(unwrap(await agentApi.getTicket(ticketId)).uploads)).toEqual([screenshot, log]);
+    expect(unwrapErr(await agentApi.finalizeUpload(agentUpload)).underlying_error).toContain("already discarded");
+})
+
+test("upload_download_authorization", async () => {
+    const requesterApi = makeApi();
+    const agentApi = makeApi();
+    const strangerApi = makeApi();
+
+    const _requesterId = await makeFakeUser(requesterApi);
+    const agentId = await makeFakeUser(agentApi);
+    const _strangerId = await makeFakeUser(strangerApi);
+    const ticketId = generateId();
+
+    const content = "Serial number: 4C0-1234";
+    const metadata = {filename: "label.txt", content_type: "text/plain", size: content.length};
+    const attached = await uploadFile(requesterApi, metadata, content);
+    const unattached = await uploadFile(requesterApi, metadata, content);
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "User", id: agentId },
+        title: "Broken printer",
+        body: "The label is attached",
+        attachments: [attached],
+    }));
+
+    // the owner can always download their uploads
+    expect(await (await nodeFetch(unwrap(await requesterApi.getUploadFileLocation(unattached)))).text()).toBe(content);
+    // the readers of a ticket can download its attachments, and nothing else
+    expect(await (await nodeFetch(unwrap(await agentApi.getUploadFileLocation(attached)))).text()).toBe(content);
+    unwrapErr(await agentApi.getUploadFileLocation(unattached));
+    unwrapErr(await strangerApi.getUploadFileLocation(attached));
+
+    // redacting a message takes its attachments away
+    const redacted = await uploadFile(requesterApi, metadata, content);
+    unwrap(await requesterApi.sendTicketMessage(ticketId, {body: "And a second photo", attachments: [redacted]}));
+    unwrap(await agentApi.getUploadFileLocation(redacted));
+    const messages = async () => unwrap(await agentApi.getTicket(ticketId)).payload.timeline
+        .flatMap((item) => item.content.type === "Message" ? [item.content] : []);
+    const message = (await messages()).find((message) => message.attachments.includes(redacted))!;
+    unwrap(await requesterApi.redactTicketMessage(ticketId, message.id));
+    expect((await messages()).find((other) => other.id === message.id)!.attachments).toEqual([]);
+    unwrapErr(await agentApi.getUploadFileLocation(redacted));
+    unwrap(await agentApi.getUploadFileLocation(attached));
+
+    // no ticket refers to the redacted upload anymore, so it is removed
+    const redactedUrl = unwrap(await requesterApi.getUploadFileLocation(redacted));
+    unwrap(await requesterApi.internalAdvanceFakeClock("0s"));
+    expect(unwrapErr(await requesterApi.finalizeUpload(redacted)).underlying_error).toContain("already discarded");
+    expect((await nodeFetch(redactedUrl)).status).toBe(404);
+    unwrap(await agentApi.getUploadFileLocation(attached));
+
+    // the uploads that do not exist are reported as errors too
+    expect(unwrapErr(await requesterApi.getUploadFileLocation(generateId())).underlying_error).toContain("not found");
+})
+
+test("ticket_inactivity", async () => {
+    const api = makeApi();
+    const requesterApi = makeApi();
+
+    const _ownerId = await makeFakeUser(api);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const groupId = generateId();
+    const abandonedId = generateId();
+    const answeredId = generateId();
+
+    unwrap(await api.createGroup(groupId, {title: "Dormitory"}));
+    unwrap(await api.changeGroupWorkflow(groupId, {
+        statuses: [
+            {id: "New", label: "New", category: "Open"},
+            {id: "WaitingForReply", label: "Waiting for reply", category: "Open"},
+            {id: "Solved", label: "Solved", category: "Closed"},
+            {id: "Abandoned", label: "Abandoned", category: "Closed"},
+        ],
+        initial: "New",
+        transitions: {
+            "New": ["WaitingForReply", "Solved"],
+            "WaitingForReply": ["New", "Solved"],
+        },
+    }));
+
+    // the warning must come first, and the tickets must be closed with a closed status
+    unwrapErr(await api.changeGroupInactivityPolicy(groupId, {
+        waiting_statuses: ["WaitingForReply"], warn_after_days: 7, close_after_days: 3, closed_status: "Abandoned",
+    }));
+    unwrapErr(await api.changeGroupInactivityPolicy(groupId, {
+        waiting_statuses: ["WaitingForReply"], warn_after_days: 3, close_after_days: 7, closed_status: "New",
+    }));
+    unwrap(await api.changeGroupInactivityPolicy(groupId, {
+        waiting_statuses: ["WaitingForReply"], warn_after_days: 3, close_after_days: 7, closed_status: "Abandoned",
+    }));
+    expect(unwrap(await api.getGroup(groupId)).payload.inactivity_policy?.closed_status).toBe("Abandoned");
+
+    for (const ticketId of [abandonedId, answeredId]) {
+        unwrap(await requesterApi.createTicket(ticketId, {
+            destination: { type: "Group", id: groupId },
+            title: "Noisy neighbours",
+            body: "They play the drums at night",
+        }));
+        unwrap(await api.changeTicketStatus(ticketId, "WaitingForReply"));
+    }
+
+    const timelineTypes = async (ticketId: string) =>
+        unwrap(await api.getTicket(ticketId)).payload.timeline.map((item) => item.content.type);
+
+    // a reply from the owner starts the timer over
+    unwrap(await api.internalAdvanceFakeClock("2d"));
+    unwrap(await requesterApi.sendTicketMessage(answeredId, {body: "Still happening"}));
+
+    unwrap(await api.internalAdvanceFakeClock("2d"));
+    expect(await timelineTypes(abandonedId)).toContain("InactivityWarning");
+    expect(await timelineTypes(answeredId)).not.toContain("InactivityWarning");
+
+    unwrap(await api.internalAdvanceFakeClock("4d"));
+    const abandoned = unwrap(await api.getTicket(abandonedId)).payload;
+    expect(abandoned.status).toBe("Abandoned");
+    expect(abandoned.status_category).toBe("Closed");
+    expect(await timelineTypes(abandonedId)).toContain("ClosedForInactivity");
+
+    const answered = unwrap(await api.getTicket(answeredId)).payload;
+    expect(answered.status).toBe("WaitingForReply");
+    expect(await timelineTypes(answeredId)).toContain("InactivityWarning");
+})
+
+test("ticket_snooze", async () => {
+    const agentApi = makeApi();
+    const requesterApi = makeApi();
+
+    const agentId = await makeFakeUser(agentApi);
+    const _requesterId = await makeFakeUser(requesterApi);
+    const ticketId = generateId();
+
+    unwrap(await requesterApi.createTicket(ticketId, {
+        destination: { type: "User", id: agentId },
+        title: "Broken chair",
+        body: "One of the legs is loose",
+    }));
+
+    const assignedIds = async (includeSnoozed?: boolean) =>
+        unwrap(await agentApi.getAssignedTickets(includeSnoozed)).payload.map((item) => item.id);
+    const addDays = (date: string, days: number) =>
+        new Date(new Date(date).getTime() + days * 24 * 60 * 60 * 1000).toISOString();
+
+    // the scheduled wake-up follows the fake clock, which the other tests might have moved
+    const now = unwrap(await agentApi.internalAdvanceFakeClock("0s")).payload;
+
+    unwrapErr(await agentApi.snoozeTicket(ticketId, addDays(now, -1)));
+    unwrapErr(await requesterApi.snoozeTicket(ticketId, addDays(now, 3)));
+    unwrap(await agentApi.snoozeTicket(ticketId, addDays(now, 3)));
+
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.snoozed_until).not.toBe(null);
+    expect(await assignedIds()).not.toContain(ticketId);
+    expect(await assignedIds(true)).toContain(ticketId);
+    // snoozing is an internal matter
+    const ownerTimeline = unwrap(await requesterApi.getTicket(ticketId)).payload.timeline;
+    expect(ownerTimeline.map((item) => item.content.type)).not.toContain("Snoozed");
+
+    unwrap(await agentApi.internalAdvanceFakeClock("4d"));
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.snoozed_until).toBe(null);
+    expect(await assignedIds()).toContain(ticketId);
+
+    // a reply from the owner ends the snooze early
+    unwrap(await agentApi.snoozeTicket(ticketId, addDays(now, 30)));
+    expect(await assignedIds()).not.toContain(ticketId);
+    unwrap(await requesterApi.sendTicketMessage(ticketId, {body: "Now the back is loose too"}));
+    expect(unwrap(await agentApi.getTicket(ticketId)).payload.snoozed_until).toBe(null);
+    expect(await assignedIds()).toContain(ticketId);
+
+    unwrap(await agentApi.snoozeTicket(ticketId, addDays(now, 30)));
+    unwrap(await agentApi.unsnoozeTicket(ticketId));
+    expect(await assignedIds()).toContain(ticketId);
+})
@@ -7,22 +7,40 @@ import type {
     UserProfileView,
     ExternalUserProfile,
     TicketId,
+    TicketMessageId,
     CreateTicket,
     SendTicketMessage,
+    SendInternalNote,
     TicketView,
     TicketListingViewExpandedItem,
     TicketStatus,
+    TicketWorkflow,
+    TicketPriority,
+    TicketDestination,
+    TicketRelation,
+    SlaPolicy,
+    InactivityPolicy,
+    TicketForm,
     TelegramLoginData,
     CreateGroup,
     UpdateGroup,
     GroupView,
     GroupId,
+    GroupRole,
+    AssignmentStrategy,
+    GroupInvitationsView,
+    GroupTreeView,
+    UserInvitationsView,
+    InviteGroupMember,
     UpdateTicket,
     WithUsers,
+    WithGroups,
     WithGroupsAndUsers,
+    WithGroupsUsersAndUploads,
     InitiatedUpload,
     SearchResults,
     UploadMetadata,
+    UploadUsage,
     UploadId
 } from "../";
 
@@ -61,6 +79,13 @@ export class Api {
         return await res.json();
     }
 
+    async #delete(url: string): Promise<ApiResult<null>> {
+        const res = await this.fetch(url, {
+            method: 'DELETE',
+        });
+        return await res.json();
+    }
+
     async #get<T extends object>(url: string): Promise<ApiResult<T>> {
         const res = await this.fetch(url);
         return await res.json();
@@ -78,6 +103,17 @@ export class Api {
         return await res.json();
     }
 
+    async internalAdvanceFakeClock(by: string): Promise<ApiResult<string>> {
+        const res = await this.fetch(`/api/fake-clock/advance`, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({by}),
+        });
+        return await res.json();
+    }
+
     async telegramLogin(data: TelegramLoginData): Promise<ApiResult<null>> {
         return await this.#sendCommand(`/api/login/telegram`, data);
     }
@@ -86,6 +122,14 @@ export class Api {
         return await this.#get('/api/users/me');
     }
 
+    async deleteMe(): Promise<ApiResult<null>> {
+        return await this.#delete('/api/users/me');
+    }
+
+    async getMyInvitations(): Promise<ApiResult<WithGroups<UserInvitationsView>>> {
+        return await this.#get('/api/users/me/invitations');
+    }
+
     async getUserProfile(id: UserId): Promise<ApiResult<UserProfileView>> {
         return await this.#get(`/api/users/${id}/profile`);
     }
@@ -98,6 +142,10 @@ export class Api {
         return await this.#sendCreateCommand(`/api/groups/${id}`, creation);
     }
 
+    async getGroupTree(id: GroupId): Promise<ApiResult<WithGroups<GroupTreeView>>> {
+        return await this.#get(`/api/groups/${id}/tree`);
+    }
+
     async getGroupTickets(id: GroupId): Promise<ApiResult<WithGroupsAndUsers<TicketListingViewExpandedItem[]>>> {
         return await this.#get(`/api/groups/${id}/tickets`);
     }
@@ -106,9 +154,41 @@ export class Api {
         return await this.#get(`/api/groups/${id}`);
     }
 
-    async addGroupMember(id: GroupId, new_member: UserId): Promise<ApiResult<null>> {
-        let command: UpdateGroup = {type: "AddMember", new_member};
-        return await this.#sendCommand(`/api/groups/${id}`, command);
+    async getGroupInvitations(id: GroupId): Promise<ApiResult<WithUsers<GroupInvitationsView>>> {
+        return await this.#get(`/api/groups/${id}/invitations`);
+    }
+
+    async inviteGroupMember(id: GroupId, invitee: UserId): Promise<ApiResult<null>> {
+        let command: InviteGroupMember = {invitee};
+        return await this.#sendCommand(`/api/groups/${id}/invitations`, command);
+    }
+
+    async revokeGroupInvitation(id: GroupId, invitee: UserId): Promise<ApiResult<null>> {
+        return await this.#delete(`/api/groups/${id}/invitations/${invitee}`);
+    }
+
+    async acceptGroupInvitation(id: GroupId): Promise<ApiResult<null>> {
+        return await this.#sendCommand(`/api/groups/${id}/invitations/accept`, {});
+    }
+
+    async declineGroupInvitation(id: GroupId): Promise<ApiResult<null>> {
+        return await this.#sendCommand(`/api/groups/${id}/invitations/decline`, {});
+    }
+
+    async requestToJoinGroup(id: GroupId): Promise<ApiResult<null>> {
+        return await this.#sendCommand(`/api/groups/${id}/join-requests`, {});
+    }
+
+    async withdrawGroupJoinRequest(id: GroupId): Promise<ApiResult<null>> {
+        return await this.#delete(`/api/groups/${id}/join-requests`);
+    }
+
+    async approveGroupJoinRequest(id: GroupId, requester: UserId): Promise<ApiResult<null>> {
+        return await this.#sendCommand(`/api/groups/${id}/join-requests/${requester}/approve`, {});
+    }
+
+    async rejectGroupJoinRequest(id: GroupId, requester: UserId): Promise<ApiResult<null>> {
+        return await this.#sendCommand(`/api/groups/${id}/join-requests/${requester}/reject`, {});
     }
 
     async removeGroupMember(id: GroupId, removed_member: UserId): Promise<ApiResult<null>> {
@@ -121,11 +201,61 @@ export class Api {
         return await this.#sendCommand(`/api/groups/${id}`, command);
     }
 
+    async changeGroupMemberRole(id: GroupId, member: UserId, new_role: GroupRole): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeMemberRole", member, new_role};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async changeGroupDiscoverability(id: GroupId, discoverable: boolean): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeDiscoverability", discoverable};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async changeGroupParent(id: GroupId, new_parent: GroupId | null): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeParent", new_parent};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async changeGroupAssignmentStrategy(id: GroupId, strategy: AssignmentStrategy): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeAssignmentStrategy", strategy};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async changeGroupWorkflow(id: GroupId, workflow: TicketWorkflow): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeWorkflow", workflow};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async changeGroupSlaPolicy(id: GroupId, policy: SlaPolicy): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeSlaPolicy", policy};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async changeGroupForm(id: GroupId, form: TicketForm): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeForm", form};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async changeGroupInactivityPolicy(id: GroupId, policy: InactivityPolicy | null): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "ChangeInactivityPolicy", policy};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async createGroupLabel(id: GroupId, name: string, color: string): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "CreateLabel", name, color};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
+    async deleteGroupLabel(id: GroupId, name: string): Promise<ApiResult<null>> {
+        let command: UpdateGroup = {type: "DeleteLabel", name};
+        return await this.#sendCommand(`/api/groups/${id}`, command);
+    }
+
     async createTicket(id: TicketId, creation: CreateTicket): Promise<ApiResult<null>> {
         return await this.#sendCreateCommand(`/api/tickets/${id}`, creation);
     }
 
-    async getTicket(id: TicketId): Promise<ApiResult<WithGroupsAndUsers<TicketView>>> {
+    async getTicket(id: TicketId): Promise<ApiResult<WithGroupsUsersAndUploads<TicketView>>> {
         return await this.#get(`/api/tickets/${id}`);
     }
 
@@ -133,8 +263,13 @@ export class Api {
         return await this.#get(`/api/tickets/owned`);
     }
 
-    async getAssignedTickets(): Promise<ApiResult<WithGroupsAndUsers<TicketListingViewExpandedItem[]>>> {
-        return await this.#get(`/api/tickets/assigned`);
+    async getWatchedTickets(): Promise<ApiResult<WithGroupsAndUsers<TicketListingViewExpandedItem[]>>> {
+        return await this.#get(`/api/tickets/watched`);
+    }
+
+    async getAssignedTickets(includeSnoozed?: boolean): Promise<ApiResult<WithGroupsAndUsers<TicketListingViewExpandedItem[]>>> {
+        const snoozedFilter = includeSnoozed ? '?include_snoozed=true' : '';
+        return await this.#get(`/api/tickets/assigned${snoozedFilter}`);
     }
 
     async sendTicketMessage(id: TicketId, message: SendTicketMessage): Promise<ApiResult<null>> {
@@ -142,6 +277,51 @@ export class Api {
         return await this.#sendCommand(`/api/tickets/${id}`, command);
     }
 
+    async sendTicketInternalNote(id: TicketId, note: SendInternalNote): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "SendInternalNote", ...note};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async editTicketMessage(id: TicketId, message: TicketMessageId, new_text: string): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "EditMessage", message, new_text};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async redactTicketMessage(id: TicketId, message: TicketMessageId): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "RedactMessage", message};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async linkTicket(id: TicketId, other: TicketId, kind: TicketRelation): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "LinkTicket", other, kind};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async unlinkTicket(id: TicketId, other: TicketId): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "UnlinkTicket", other};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async mergeTicket(id: TicketId, primary: TicketId): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "MergeInto", primary};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async changeTicketTitle(id: TicketId, new_title: string): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "ChangeTitle", new_title};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async snoozeTicket(id: TicketId, until: string): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "Snooze", until};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async unsnoozeTicket(id: TicketId): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "Unsnooze"};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
     async changeTicketStatus(id: TicketId, new_status: TicketStatus): Promise<ApiResult<null>> {
         let command: UpdateTicket = {type: "ChangeStatus", new_status};
         return await this.#sendCommand(`/api/tickets/${id}`, command);
@@ -152,8 +332,44 @@ export class Api {
         return await this.#sendCommand(`/api/tickets/${id}`, command);
     }
 
-    async searchTickets(q: string): Promise<ApiResult<SearchResults<TicketView>>> {
-        return await this.#get(`/api/search/tickets?q=${q}`);
+    async changeTicketPriority(id: TicketId, new_priority: TicketPriority): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "ChangePriority", new_priority};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async setTicketDueDate(id: TicketId, due_date: string | null): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "SetDueDate", due_date};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async addTicketLabel(id: TicketId, name: string): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "AddLabel", name};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async removeTicketLabel(id: TicketId, name: string): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "RemoveLabel", name};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async changeTicketDestination(id: TicketId, new_destination: TicketDestination): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "ChangeDestination", new_destination};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async watchTicket(id: TicketId): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "Watch"};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async unwatchTicket(id: TicketId): Promise<ApiResult<null>> {
+        let command: UpdateTicket = {type: "Unwatch"};
+        return await this.#sendCommand(`/api/tickets/${id}`, command);
+    }
+
+    async searchTickets(q: string, label?: string): Promise<ApiResult<SearchResults<TicketView>>> {
+        const labelFilter = label !== undefined ? `&label=${encodeURIComponent(label)}` : '';
+        return await this.#get(`/api/search/tickets?q=${q}${labelFilter}`);
     }
 
     async searchUsers(q: string): Promise<ApiResult<SearchResults<UserView>>> {
@@ -182,9 +398,35 @@ export class Api {
         return await res.json();
     }
 
+    async getUploadUsage(): Promise<ApiResult<UploadUsage>> {
+        return await this.#get(`/api/upload/usage`);
+    }
+
     getUploadFileUrl(id: UploadId): string {
         return `/api/upload/${id}/file`;
     }
+
+    async getUploadFileLocation(id: UploadId): Promise<ApiResult<string>> {
+        return await this.#getRedirectLocation(this.getUploadFileUrl(id));
+    }
+
+    getUploadThumbnailUrl(id: UploadId): string {
+        return `/api/upload/${id}/thumbnail`;
+    }
+
+    async getUploadThumbnailLocation(id: UploadId): Promise<ApiResult<string>> {
+        return await this.#getRedirectLocation(this.getUploadThumbnailUrl(id));
+    }
+
+    async #getRedirectLocation(url: string): Promise<ApiResult<string>> {
+        const res = await this.fetch(url, {
+            redirect: 'manual',
+        });
+        if (res.status >= 300 && res.status < 400) {
+            return {status: 'Success', payload: res.headers.get('location')!};
+        }
+        return await res.json();
+    }
 }
 
 export default Api;
\ No newline at end of file
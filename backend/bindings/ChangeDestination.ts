@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketDestination } from "./TicketDestination";
+
+export interface ChangeDestination { new_destination: TicketDestination, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupRole } from "./GroupRole";
+import type { UserId } from "./UserId";
+
+export interface ChangeGroupMemberRole { member: UserId, new_role: GroupRole, }
\ No newline at end of file
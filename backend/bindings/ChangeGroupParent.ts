@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupId } from "./GroupId";
+
+export interface ChangeGroupParent { new_parent: GroupId | null, }
\ No newline at end of file
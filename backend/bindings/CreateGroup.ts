@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupId } from "./GroupId";
 
-export interface CreateGroup { title: string, }
\ No newline at end of file
+export interface CreateGroup { title: string, parent?: GroupId, }
\ No newline at end of file
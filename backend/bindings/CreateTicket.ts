@@ -1,4 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { TicketDestination } from "./TicketDestination";
+import type { TicketFieldValue } from "./TicketFieldValue";
+import type { UploadId } from "./UploadId";
 
-export interface CreateTicket { destination: TicketDestination, title: string, body: string, }
\ No newline at end of file
+export interface CreateTicket { destination: TicketDestination, title: string, body: string, fields?: Record<string, TicketFieldValue>, attachments?: Array<UploadId>, }
\ No newline at end of file
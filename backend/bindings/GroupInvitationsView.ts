@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupId } from "./GroupId";
+import type { UserId } from "./UserId";
+
+export interface GroupInvitationsView { id: GroupId, invitations: Array<UserId>, join_requests: Array<UserId>, }
\ No newline at end of file
@@ -1,5 +1,12 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AssignmentStrategy } from "./AssignmentStrategy";
 import type { GroupId } from "./GroupId";
+import type { GroupRole } from "./GroupRole";
+import type { InactivityPolicy } from "./InactivityPolicy";
+import type { SlaPolicy } from "./SlaPolicy";
+import type { TicketForm } from "./TicketForm";
+import type { TicketLabel } from "./TicketLabel";
+import type { TicketWorkflow } from "./TicketWorkflow";
 import type { UserId } from "./UserId";
 
-export interface GroupView { id: GroupId, title: string, members: Array<UserId>, }
\ No newline at end of file
+export interface GroupView { id: GroupId, title: string, parent: GroupId | null, members: Array<UserId>, roles: Record<UserId, GroupRole>, discoverable: boolean, assignment_strategy: AssignmentStrategy, workflow: TicketWorkflow, sla_policy: SlaPolicy, labels: Array<TicketLabel>, form: TicketForm, inactivity_policy: InactivityPolicy | null, }
\ No newline at end of file
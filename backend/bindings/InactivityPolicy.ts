@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketStatus } from "./TicketStatus";
+
+export interface InactivityPolicy { waiting_statuses: Array<TicketStatus>, warn_after_days: number, close_after_days: number, closed_status: TicketStatus, }
\ No newline at end of file
@@ -1,4 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { UserId } from "./UserId";
 
-export interface AddGroupMember { new_member: UserId, }
\ No newline at end of file
+export interface InviteGroupMember { invitee: UserId, }
\ No newline at end of file
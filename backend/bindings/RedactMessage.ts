@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketMessageId } from "./TicketMessageId";
+
+export interface RedactMessage { message: TicketMessageId, }
\ No newline at end of file
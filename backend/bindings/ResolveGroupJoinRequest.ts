@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { UserId } from "./UserId";
+
+export interface ResolveGroupJoinRequest { requester: UserId, }
\ No newline at end of file
@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { UploadId } from "./UploadId";
 
-export interface SendTicketMessage { body: string, }
\ No newline at end of file
+export interface SendTicketMessage { body: string, attachments?: Array<UploadId>, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SlaTargets } from "./SlaTargets";
+import type { TicketPriority } from "./TicketPriority";
+
+export interface SlaPolicy { targets: Record<TicketPriority, SlaTargets>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface SlaTargets { first_response_minutes: number | null, resolution_minutes: number | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketFieldValue } from "./TicketFieldValue";
+
+export interface TicketField { id: string, label: string, value: TicketFieldValue, }
\ No newline at end of file
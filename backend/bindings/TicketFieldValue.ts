@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { UploadId } from "./UploadId";
+
+export type TicketFieldValue = { "type": "Text", "value": string } | { "type": "Number", "value": number } | { "type": "Select", "value": Array<string> } | { "type": "Date", "value": string } | { "type": "Upload", "value": Array<UploadId> };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketFormFieldKind } from "./TicketFormFieldKind";
+
+export interface TicketFormField { id: string, label: string, required: boolean, kind: TicketFormFieldKind, }
\ No newline at end of file
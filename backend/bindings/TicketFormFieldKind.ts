@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type TicketFormFieldKind = { "type": "Text", min_length: number | null, max_length: number | null, } | { "type": "Number", min: number | null, max: number | null, } | { "type": "Select", options: Array<string>, multiple: boolean, } | { "type": "Date", allow_past: boolean, } | { "type": "Upload", max_files: number | null, };
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketId } from "./TicketId";
+import type { TicketRelation } from "./TicketRelation";
+
+export interface TicketLink { ticket: TicketId, kind: TicketRelation, }
\ No newline at end of file
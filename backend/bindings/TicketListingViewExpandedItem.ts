@@ -1,7 +1,11 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SlaStatus } from "./SlaStatus";
+import type { StatusCategory } from "./StatusCategory";
 import type { TicketDestination } from "./TicketDestination";
 import type { TicketId } from "./TicketId";
+import type { TicketLabel } from "./TicketLabel";
+import type { TicketPriority } from "./TicketPriority";
 import type { TicketStatus } from "./TicketStatus";
 import type { UserId } from "./UserId";
 
-export interface TicketListingViewExpandedItem { id: TicketId, destination: TicketDestination, owner: UserId, assignee: UserId | null, title: string, status: TicketStatus, latest_update: string, }
\ No newline at end of file
+export interface TicketListingViewExpandedItem { id: TicketId, destination: TicketDestination, owner: UserId, assignee: UserId | null, title: string, status: TicketStatus, status_label: string, status_category: StatusCategory, priority: TicketPriority, due_date: string | null, labels: Array<TicketLabel>, sla: SlaStatus, snoozed_until: string | null, latest_update: string, }
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type TicketStatus = "Pending" | "InProgress" | "Declined" | "Fixed";
\ No newline at end of file
+export type TicketStatus = string;
\ No newline at end of file
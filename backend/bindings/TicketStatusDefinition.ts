@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StatusCategory } from "./StatusCategory";
+import type { TicketStatus } from "./TicketStatus";
+
+export interface TicketStatusDefinition { id: TicketStatus, label: string, category: StatusCategory, }
\ No newline at end of file
@@ -1,5 +1,9 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketDestination } from "./TicketDestination";
+import type { TicketId } from "./TicketId";
+import type { TicketPriority } from "./TicketPriority";
 import type { TicketStatus } from "./TicketStatus";
+import type { TicketTimelineMessage } from "./TicketTimelineMessage";
 import type { UserId } from "./UserId";
 
-export type TicketTimelineItemContent = { "type": "Message", from: UserId, text: string, } | { "type": "StatusChange", old: TicketStatus, new: TicketStatus, } | { "type": "AssigneeChange", old: UserId | null, new: UserId | null, };
\ No newline at end of file
+export type TicketTimelineItemContent = { "type": "Message" } & TicketTimelineMessage | { "type": "StatusChange", old: TicketStatus, new: TicketStatus, } | { "type": "AssigneeChange", old: UserId | null, new: UserId | null, } | { "type": "PriorityChange", old: TicketPriority, new: TicketPriority, } | { "type": "DueDateChange", old: string | null, new: string | null, } | { "type": "DestinationChange", old: TicketDestination, new: TicketDestination, } | { "type": "InternalNote" } & TicketTimelineMessage | { "type": "MergedInto", ticket: TicketId, } | { "type": "DuplicateMerged", ticket: TicketId, } | { "type": "TitleChange", old: string, new: string, } | { "type": "InactivityWarning", close_at: string, } | { "type": "ClosedForInactivity" } | { "type": "Snoozed", until: string, performer: UserId, } | { "type": "WokenUp" };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketMessageId } from "./TicketMessageId";
+import type { TicketMessageRevision } from "./TicketMessageRevision";
+import type { UploadId } from "./UploadId";
+import type { UserId } from "./UserId";
+
+export interface TicketTimelineMessage { id: TicketMessageId, from: UserId, text: string, attachments: Array<UploadId>, revisions: Array<TicketMessageRevision>, redacted: boolean, }
\ No newline at end of file
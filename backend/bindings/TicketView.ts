@@ -1,8 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StatusCategory } from "./StatusCategory";
 import type { TicketDestination } from "./TicketDestination";
+import type { TicketField } from "./TicketField";
 import type { TicketId } from "./TicketId";
+import type { TicketLabel } from "./TicketLabel";
+import type { TicketLink } from "./TicketLink";
+import type { TicketPriority } from "./TicketPriority";
 import type { TicketStatus } from "./TicketStatus";
 import type { TicketTimelineItem } from "./TicketTimelineItem";
 import type { UserId } from "./UserId";
 
-export interface TicketView { id: TicketId, destination: TicketDestination, owner: UserId, assignee: UserId | null, title: string, status: TicketStatus, timeline: Array<TicketTimelineItem>, latest_update: string, }
\ No newline at end of file
+export interface TicketView { id: TicketId, destination: TicketDestination, owner: UserId, assignee: UserId | null, title: string, status: TicketStatus, status_label: string, status_category: StatusCategory, priority: TicketPriority, due_date: string | null, labels: Array<TicketLabel>, fields: Array<TicketField>, watchers: Array<UserId>, links: Array<TicketLink>, merged_into: TicketId | null, snoozed_until: string | null, timeline: Array<TicketTimelineItem>, latest_update: string, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TicketStatus } from "./TicketStatus";
+import type { TicketStatusDefinition } from "./TicketStatusDefinition";
+
+export interface TicketWorkflow { statuses: Array<TicketStatusDefinition>, initial: TicketStatus, transitions: Record<TicketStatus, Array<TicketStatus>>, }
\ No newline at end of file
@@ -1,6 +1,18 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
-import type { AddGroupMember } from "./AddGroupMember";
+import type { ChangeGroupAssignmentStrategy } from "./ChangeGroupAssignmentStrategy";
+import type { ChangeGroupDiscoverability } from "./ChangeGroupDiscoverability";
+import type { ChangeGroupForm } from "./ChangeGroupForm";
+import type { ChangeGroupInactivityPolicy } from "./ChangeGroupInactivityPolicy";
+import type { ChangeGroupMemberRole } from "./ChangeGroupMemberRole";
+import type { ChangeGroupParent } from "./ChangeGroupParent";
+import type { ChangeGroupSlaPolicy } from "./ChangeGroupSlaPolicy";
 import type { ChangeGroupTitle } from "./ChangeGroupTitle";
+import type { ChangeGroupWorkflow } from "./ChangeGroupWorkflow";
+import type { CreateGroupLabel } from "./CreateGroupLabel";
+import type { DeleteGroupLabel } from "./DeleteGroupLabel";
+import type { InviteGroupMember } from "./InviteGroupMember";
 import type { RemoveGroupMember } from "./RemoveGroupMember";
+import type { ResolveGroupJoinRequest } from "./ResolveGroupJoinRequest";
+import type { RevokeGroupInvitation } from "./RevokeGroupInvitation";
 
-export type UpdateGroup = { "type": "AddMember" } & AddGroupMember | { "type": "RemoveMember" } & RemoveGroupMember | { "type": "ChangeTitle" } & ChangeGroupTitle;
\ No newline at end of file
+export type UpdateGroup = { "type": "InviteMember" } & InviteGroupMember | { "type": "RevokeInvitation" } & RevokeGroupInvitation | { "type": "AcceptInvitation" } | { "type": "DeclineInvitation" } | { "type": "RequestToJoin" } | { "type": "WithdrawJoinRequest" } | { "type": "ApproveJoinRequest" } & ResolveGroupJoinRequest | { "type": "RejectJoinRequest" } & ResolveGroupJoinRequest | { "type": "RemoveMember" } & RemoveGroupMember | { "type": "ChangeTitle" } & ChangeGroupTitle | { "type": "ChangeMemberRole" } & ChangeGroupMemberRole | { "type": "ChangeDiscoverability" } & ChangeGroupDiscoverability | { "type": "ChangeParent" } & ChangeGroupParent | { "type": "ChangeAssignmentStrategy" } & ChangeGroupAssignmentStrategy | { "type": "ChangeWorkflow" } & ChangeGroupWorkflow | { "type": "ChangeSlaPolicy" } & ChangeGroupSlaPolicy | { "type": "CreateLabel" } & CreateGroupLabel | { "type": "DeleteLabel" } & DeleteGroupLabel | { "type": "ChangeForm" } & ChangeGroupForm | { "type": "ChangeInactivityPolicy" } & ChangeGroupInactivityPolicy;
\ No newline at end of file
@@ -1,6 +1,19 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AddTicketLabel } from "./AddTicketLabel";
 import type { ChangeAssignee } from "./ChangeAssignee";
+import type { ChangeDestination } from "./ChangeDestination";
+import type { ChangePriority } from "./ChangePriority";
 import type { ChangeStatus } from "./ChangeStatus";
+import type { ChangeTicketTitle } from "./ChangeTicketTitle";
+import type { EditMessage } from "./EditMessage";
+import type { LinkTicket } from "./LinkTicket";
+import type { MergeInto } from "./MergeInto";
+import type { RedactMessage } from "./RedactMessage";
+import type { RemoveTicketLabel } from "./RemoveTicketLabel";
+import type { SendInternalNote } from "./SendInternalNote";
 import type { SendTicketMessage } from "./SendTicketMessage";
+import type { SetDueDate } from "./SetDueDate";
+import type { SnoozeTicket } from "./SnoozeTicket";
+import type { UnlinkTicket } from "./UnlinkTicket";
 
-export type UpdateTicket = { "type": "SendTicketMessage" } & SendTicketMessage | { "type": "ChangeStatus" } & ChangeStatus | { "type": "ChangeAssignee" } & ChangeAssignee;
\ No newline at end of file
+export type UpdateTicket = { "type": "SendTicketMessage" } & SendTicketMessage | { "type": "ChangeStatus" } & ChangeStatus | { "type": "ChangeAssignee" } & ChangeAssignee | { "type": "ChangePriority" } & ChangePriority | { "type": "SetDueDate" } & SetDueDate | { "type": "AddLabel" } & AddTicketLabel | { "type": "RemoveLabel" } & RemoveTicketLabel | { "type": "ChangeDestination" } & ChangeDestination | { "type": "Watch" } | { "type": "Unwatch" } | { "type": "SendInternalNote" } & SendInternalNote | { "type": "EditMessage" } & EditMessage | { "type": "RedactMessage" } & RedactMessage | { "type": "LinkTicket" } & LinkTicket | { "type": "UnlinkTicket" } & UnlinkTicket | { "type": "MergeInto" } & MergeInto | { "type": "ChangeTitle" } & ChangeTicketTitle | { "type": "Snooze" } & SnoozeTicket | { "type": "Unsnooze" };
\ No newline at end of file
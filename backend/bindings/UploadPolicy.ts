@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { UploadQuota } from "./UploadQuota";
 
-export interface UploadPolicy { allowed_file_extensions: Array<string>, allowed_content_types: Array<string>, max_size: bigint, }
\ No newline at end of file
+export interface UploadPolicy { allowed_file_extensions: Array<string>, allowed_content_types: Array<string>, max_size: bigint, quota: UploadQuota, }
\ No newline at end of file
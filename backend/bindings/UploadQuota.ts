@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface UploadQuota { max_total_size: bigint, max_files: bigint, }
\ No newline at end of file
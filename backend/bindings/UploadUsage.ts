@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { UploadQuota } from "./UploadQuota";
+import type { UploadUsageView } from "./UploadUsageView";
+
+export interface UploadUsage { usage: UploadUsageView, quota: UploadQuota, }
\ No newline at end of file
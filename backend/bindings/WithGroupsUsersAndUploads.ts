@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupId } from "./GroupId";
+import type { GroupProfileView } from "./GroupProfileView";
+import type { UploadId } from "./UploadId";
+import type { UploadMetadata } from "./UploadMetadata";
+import type { UserId } from "./UserId";
+import type { UserProfileView } from "./UserProfileView";
+
+export interface WithGroupsUsersAndUploads<T> { groups: Record<GroupId, GroupProfileView>, users: Record<UserId, UserProfileView>, uploads: Record<UploadId, UploadMetadata>, payload: T, }
\ No newline at end of file
@@ -149,7 +149,13 @@ impl<A: LifecycleAggregate> Aggregate for LifecycleAggregateState<A> {
                 .handle_delete(delete, service)
                 .await
                 .map_err(LifecycleError::AggregateError)
-                .map(|_| vec![LifecycleEvent::Deleted]),
+                .map(|events| {
+                    events
+                        .into_iter()
+                        .map(LifecycleEvent::Updated)
+                        .chain(std::iter::once(LifecycleEvent::Deleted))
+                        .collect()
+                }),
         }
     }
 
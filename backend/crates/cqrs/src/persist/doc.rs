@@ -45,6 +45,10 @@ impl ViewRepository<MyView> for MyViewRepository {
     ) -> Result<(), PersistenceError> {
         todo!()
     }
+
+    async fn delete_view(&self, _view_id: &str) -> Result<(), PersistenceError> {
+        todo!()
+    }
 }
 
 pub struct MyEventIterator;
@@ -21,6 +21,9 @@ where
     /// Updates the view instance and context, used by the `GenericQuery` to update
     /// views with committed events.
     async fn update_view(&self, view: V, context: ViewContext) -> Result<(), PersistenceError>;
+
+    /// Removes the view instance, if it exists.
+    async fn delete_view(&self, view_id: &str) -> Result<(), PersistenceError>;
 }
 
 /// A data structure maintaining context when updating views.
@@ -11,6 +11,7 @@ export type { TelegramLoginData } from './bindings/TelegramLoginData';
 export type { WithGroups } from './bindings/WithGroups';
 export type { WithUsers } from './bindings/WithUsers';
 export type { WithGroupsAndUsers } from './bindings/WithGroupsAndUsers';
+export type { WithGroupsUsersAndUploads } from './bindings/WithGroupsUsersAndUploads';
 
 export type { UserId } from './bindings/UserId';
 export type { UserIdentities } from './bindings/UserIdentities';
@@ -27,27 +28,79 @@ export type { GroupView } from './bindings/GroupView';
 export type { GroupProfileView } from './bindings/GroupProfileView';
 export type { UpdateGroup } from './bindings/UpdateGroup';
 export type { CreateGroup } from './bindings/CreateGroup';
-export type { AddGroupMember } from './bindings/AddGroupMember';
 export type { RemoveGroupMember } from './bindings/RemoveGroupMember';
 export type { ChangeGroupTitle } from './bindings/ChangeGroupTitle';
+export type { GroupRole } from './bindings/GroupRole';
+export type { ChangeGroupMemberRole } from './bindings/ChangeGroupMemberRole';
+export type { ChangeGroupDiscoverability } from './bindings/ChangeGroupDiscoverability';
+export type { ChangeGroupParent } from './bindings/ChangeGroupParent';
+export type { AssignmentStrategy } from './bindings/AssignmentStrategy';
+export type { ChangeGroupAssignmentStrategy } from './bindings/ChangeGroupAssignmentStrategy';
+export type { ChangeGroupWorkflow } from './bindings/ChangeGroupWorkflow';
+export type { ChangeGroupSlaPolicy } from './bindings/ChangeGroupSlaPolicy';
+export type { ChangeGroupForm } from './bindings/ChangeGroupForm';
+export type { ChangeGroupInactivityPolicy } from './bindings/ChangeGroupInactivityPolicy';
+export type { CreateGroupLabel } from './bindings/CreateGroupLabel';
+export type { DeleteGroupLabel } from './bindings/DeleteGroupLabel';
+export type { GroupTreeView } from './bindings/GroupTreeView';
+export type { InviteGroupMember } from './bindings/InviteGroupMember';
+export type { RevokeGroupInvitation } from './bindings/RevokeGroupInvitation';
+export type { ResolveGroupJoinRequest } from './bindings/ResolveGroupJoinRequest';
+export type { GroupInvitationsView } from './bindings/GroupInvitationsView';
+export type { UserInvitationsView } from './bindings/UserInvitationsView';
 
 export type { TicketId } from './bindings/TicketId';
 export type { TicketStatus } from './bindings/TicketStatus';
+export type { StatusCategory } from './bindings/StatusCategory';
+export type { TicketStatusDefinition } from './bindings/TicketStatusDefinition';
+export type { TicketWorkflow } from './bindings/TicketWorkflow';
+export type { TicketPriority } from './bindings/TicketPriority';
+export type { SlaTargets } from './bindings/SlaTargets';
+export type { SlaPolicy } from './bindings/SlaPolicy';
+export type { InactivityPolicy } from './bindings/InactivityPolicy';
+export type { SlaStatus } from './bindings/SlaStatus';
+export type { TicketLabel } from './bindings/TicketLabel';
+export type { TicketForm } from './bindings/TicketForm';
+export type { TicketFormField } from './bindings/TicketFormField';
+export type { TicketFormFieldKind } from './bindings/TicketFormFieldKind';
+export type { TicketFieldValue } from './bindings/TicketFieldValue';
+export type { TicketField } from './bindings/TicketField';
 export type { TicketDestination } from './bindings/TicketDestination';
 export type { TicketTimelineItem } from './bindings/TicketTimelineItem';
+export type { TicketTimelineMessage } from './bindings/TicketTimelineMessage';
+export type { TicketMessageRevision } from './bindings/TicketMessageRevision';
+export type { TicketMessageId } from './bindings/TicketMessageId';
 export type { TicketTimelineItemContent } from './bindings/TicketTimelineItemContent';
 export type { TicketView } from './bindings/TicketView';
 export type { TicketListingViewExpandedItem } from './bindings/TicketListingViewExpandedItem';
 export type { CreateTicket } from './bindings/CreateTicket';
 export type { UpdateTicket } from './bindings/UpdateTicket';
 export type { SendTicketMessage } from './bindings/SendTicketMessage';
+export type { SendInternalNote } from './bindings/SendInternalNote';
+export type { EditMessage } from './bindings/EditMessage';
+export type { RedactMessage } from './bindings/RedactMessage';
+export type { LinkTicket } from './bindings/LinkTicket';
+export type { UnlinkTicket } from './bindings/UnlinkTicket';
+export type { MergeInto } from './bindings/MergeInto';
+export type { ChangeTicketTitle } from './bindings/ChangeTicketTitle';
+export type { SnoozeTicket } from './bindings/SnoozeTicket';
+export type { TicketRelation } from './bindings/TicketRelation';
+export type { TicketLink } from './bindings/TicketLink';
 export type { ChangeStatus } from './bindings/ChangeStatus';
 export type { ChangeAssignee } from './bindings/ChangeAssignee';
+export type { ChangePriority } from './bindings/ChangePriority';
+export type { SetDueDate } from './bindings/SetDueDate';
+export type { AddTicketLabel } from './bindings/AddTicketLabel';
+export type { RemoveTicketLabel } from './bindings/RemoveTicketLabel';
+export type { ChangeDestination } from './bindings/ChangeDestination';
 
 export type { UploadId } from './bindings/UploadId';
 export type { UploadPolicy } from './bindings/UploadPolicy';
 export type { InitiatedUpload } from './bindings/InitiatedUpload';
 export type { UploadMetadata } from './bindings/UploadMetadata';
+export type { UploadQuota } from './bindings/UploadQuota';
+export type { UploadUsageView } from './bindings/UploadUsageView';
+export type { UploadUsage } from './bindings/UploadUsage';
 
 export type { SearchResults } from './bindings/SearchResults';
 export type { SearchResultItem } from './bindings/SearchResultItem';
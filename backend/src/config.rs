@@ -3,6 +3,7 @@ use custom_debug::Debug;
 use serde::Deserialize;
 use snafu::{ResultExt, Whatever};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 use url::Url;
 
@@ -13,6 +14,8 @@ pub struct Config {
     pub auth: Auth,
     pub upload: Upload,
     pub storage: Storage,
+    #[serde(default)]
+    pub scheduler: Scheduler,
 }
 
 impl Config {
@@ -74,6 +77,12 @@ pub struct Routes {
     pub expose_internal: bool,
 }
 
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Scheduler {
+    /// Use a clock that can be moved forward through an internal route
+    pub fake_clock: bool,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Server {
     pub endpoint: SocketAddr,
@@ -82,7 +91,16 @@ pub struct Server {
 #[derive(Deserialize, Clone, Debug)]
 pub struct Upload {
     pub policy: UploadPolicy,
-    pub s3: S3Config,
+    pub storage: UploadStorage,
+}
+
+/// Where the uploaded files are kept
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type")]
+pub enum UploadStorage {
+    S3(S3Config),
+    /// A directory served by the backend itself, so that the development and the tests do not need an S3 server
+    Local(LocalStorageConfig),
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -94,6 +112,14 @@ pub struct S3Config {
     pub secret_key: String,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct LocalStorageConfig {
+    pub root: PathBuf,
+    /// The URL the clients reach the backend at, the signed URLs point there
+    #[debug(format = "{}")]
+    pub public_url: Url,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Storage {
     // meilisearch is mis-used as view repository
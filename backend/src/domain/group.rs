@@ -1,4 +1,6 @@
 use crate::auth::Authenticated;
+use crate::domain::ticket::{InactivityPolicy, SlaPolicy, TicketForm, TicketLabel, TicketWorkflow};
+use crate::domain::upload::UploadId;
 use crate::domain::user::UserId;
 use crate::error::ApiError;
 use crate::related_data::CollectIds;
@@ -7,12 +9,12 @@ use async_trait::async_trait;
 use axum::http::StatusCode;
 use cqrs_es::lifecycle::{
     CreateEnvelope, LifecycleAggregate, LifecycleAggregateState, LifecycleEnvelope, LifecycleEvent,
-    LifecycleView, UpdateEnvelope,
+    LifecycleView, LifecycleViewState, UpdateEnvelope,
 };
-use cqrs_es::persist::ViewRepository;
+use cqrs_es::persist::{PersistenceError, ViewRepository};
 use cqrs_es::{AnyId, Id};
 use cqrs_es::{DomainEvent, Query, View};
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use std::sync::Arc;
@@ -42,18 +44,155 @@ impl CollectIds<GroupId> for GroupId {
     }
 }
 
+impl CollectIds<UploadId> for GroupId {
+    fn collect_ids(&self, _: &mut IndexSet<UploadId>) {}
+}
+
+/// Role of a member in a group. Roles are ordered by the privileges they grant, each role includes the privileges of the previous ones.
+#[derive(
+    Default,
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    TS,
+    Serialize,
+    Deserialize,
+    CollectIds,
+)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub enum GroupRole {
+    /// Can view the group and the tickets addressed to it
+    #[default]
+    Member,
+    /// Can handle the tickets addressed to the group
+    Agent,
+    /// Can manage the members and the settings of the group
+    Admin,
+    /// Can manage everything, including other owners
+    Owner,
+}
+
+/// How the tickets addressed to a group are assigned to its agents
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub enum AssignmentStrategy {
+    /// The agents pick up the tickets themselves
+    #[default]
+    Manual,
+    /// The tickets are assigned to each agent in turn
+    RoundRobin,
+    /// The tickets are assigned to the agent with the least open tickets
+    LeastOpen,
+}
+
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
 #[collect_ids(UserId, GroupId)]
 pub struct CreateGroup {
     pub title: String,
+    #[ts(optional)]
+    pub parent: Option<GroupId>,
+}
+
+/// Group creation, as handled by the aggregate.
+///
+/// Unlike the other aggregates, a group needs to know its own id to keep the hierarchy acyclic.
+#[derive(Debug)]
+pub struct NewGroup {
+    pub id: GroupId,
+    pub creation: CreateGroup,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct InviteGroupMember {
+    pub invitee: UserId,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct RevokeGroupInvitation {
+    pub invitee: UserId,
 }
 
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
 #[collect_ids(UserId, GroupId)]
-pub struct AddGroupMember {
-    pub new_member: UserId,
+pub struct ResolveGroupJoinRequest {
+    pub requester: UserId,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupParent {
+    pub new_parent: Option<GroupId>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupAssignmentStrategy {
+    pub strategy: AssignmentStrategy,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupWorkflow {
+    pub workflow: TicketWorkflow,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupForm {
+    pub form: TicketForm,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupSlaPolicy {
+    pub policy: SlaPolicy,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupInactivityPolicy {
+    pub policy: Option<InactivityPolicy>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct CreateGroupLabel {
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct DeleteGroupLabel {
+    pub name: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupDiscoverability {
+    pub discoverable: bool,
 }
 
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
@@ -70,19 +209,46 @@ pub struct ChangeGroupTitle {
     pub new_title: String,
 }
 
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeGroupMemberRole {
+    pub member: UserId,
+    pub new_role: GroupRole,
+}
+
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
 #[serde(tag = "type")]
 #[collect_ids(UserId, GroupId)]
 pub enum UpdateGroup {
-    AddMember(AddGroupMember),
+    InviteMember(InviteGroupMember),
+    RevokeInvitation(RevokeGroupInvitation),
+    AcceptInvitation,
+    DeclineInvitation,
+    RequestToJoin,
+    WithdrawJoinRequest,
+    ApproveJoinRequest(ResolveGroupJoinRequest),
+    RejectJoinRequest(ResolveGroupJoinRequest),
     RemoveMember(RemoveGroupMember),
     ChangeTitle(ChangeGroupTitle),
+    ChangeMemberRole(ChangeGroupMemberRole),
+    ChangeDiscoverability(ChangeGroupDiscoverability),
+    ChangeParent(ChangeGroupParent),
+    ChangeAssignmentStrategy(ChangeGroupAssignmentStrategy),
+    ChangeWorkflow(ChangeGroupWorkflow),
+    ChangeSlaPolicy(ChangeGroupSlaPolicy),
+    CreateLabel(CreateGroupLabel),
+    DeleteLabel(DeleteGroupLabel),
+    ChangeForm(ChangeGroupForm),
+    ChangeInactivityPolicy(ChangeGroupInactivityPolicy),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GroupCreated {
+    id: GroupId,
     title: String,
+    parent: Option<GroupId>,
 }
 
 impl DomainEvent for GroupCreated {
@@ -110,6 +276,77 @@ pub enum GroupUpdated {
         old_title: String,
         new_title: String,
     },
+    MemberRoleChanged {
+        performer: UserId,
+        member: UserId,
+        old_role: GroupRole,
+        new_role: GroupRole,
+    },
+    InvitationSent {
+        performer: UserId,
+        invitee: UserId,
+    },
+    InvitationRevoked {
+        performer: UserId,
+        invitee: UserId,
+    },
+    InvitationAccepted {
+        invitee: UserId,
+    },
+    InvitationDeclined {
+        invitee: UserId,
+    },
+    JoinRequested {
+        requester: UserId,
+    },
+    JoinRequestWithdrawn {
+        requester: UserId,
+    },
+    JoinRequestApproved {
+        performer: UserId,
+        requester: UserId,
+    },
+    JoinRequestRejected {
+        performer: UserId,
+        requester: UserId,
+    },
+    DiscoverabilityChanged {
+        performer: UserId,
+        discoverable: bool,
+    },
+    ParentChanged {
+        performer: UserId,
+        old_parent: Option<GroupId>,
+        new_parent: Option<GroupId>,
+    },
+    AssignmentStrategyChanged {
+        performer: UserId,
+        strategy: AssignmentStrategy,
+    },
+    WorkflowChanged {
+        performer: UserId,
+        workflow: TicketWorkflow,
+    },
+    SlaPolicyChanged {
+        performer: UserId,
+        policy: SlaPolicy,
+    },
+    FormChanged {
+        performer: UserId,
+        form: TicketForm,
+    },
+    InactivityPolicyChanged {
+        performer: UserId,
+        policy: Option<InactivityPolicy>,
+    },
+    LabelCreated {
+        performer: UserId,
+        label: TicketLabel,
+    },
+    LabelDeleted {
+        performer: UserId,
+        name: String,
+    },
 }
 
 impl DomainEvent for GroupUpdated {
@@ -118,6 +355,26 @@ impl DomainEvent for GroupUpdated {
             GroupUpdated::MemberAdded { .. } => "MemberAdded".to_string(),
             GroupUpdated::MemberRemoved { .. } => "MemberRemoved".to_string(),
             GroupUpdated::TitleChanged { .. } => "TitleChanged".to_string(),
+            GroupUpdated::MemberRoleChanged { .. } => "MemberRoleChanged".to_string(),
+            GroupUpdated::InvitationSent { .. } => "InvitationSent".to_string(),
+            GroupUpdated::InvitationRevoked { .. } => "InvitationRevoked".to_string(),
+            GroupUpdated::InvitationAccepted { .. } => "InvitationAccepted".to_string(),
+            GroupUpdated::InvitationDeclined { .. } => "InvitationDeclined".to_string(),
+            GroupUpdated::JoinRequested { .. } => "JoinRequested".to_string(),
+            GroupUpdated::JoinRequestWithdrawn { .. } => "JoinRequestWithdrawn".to_string(),
+            GroupUpdated::JoinRequestApproved { .. } => "JoinRequestApproved".to_string(),
+            GroupUpdated::JoinRequestRejected { .. } => "JoinRequestRejected".to_string(),
+            GroupUpdated::DiscoverabilityChanged { .. } => "DiscoverabilityChanged".to_string(),
+            GroupUpdated::ParentChanged { .. } => "ParentChanged".to_string(),
+            GroupUpdated::AssignmentStrategyChanged { .. } => {
+                "AssignmentStrategyChanged".to_string()
+            }
+            GroupUpdated::WorkflowChanged { .. } => "WorkflowChanged".to_string(),
+            GroupUpdated::FormChanged { .. } => "FormChanged".to_string(),
+            GroupUpdated::InactivityPolicyChanged { .. } => "InactivityPolicyChanged".to_string(),
+            GroupUpdated::SlaPolicyChanged { .. } => "SlaPolicyChanged".to_string(),
+            GroupUpdated::LabelCreated { .. } => "LabelCreated".to_string(),
+            GroupUpdated::LabelDeleted { .. } => "LabelDeleted".to_string(),
         }
     }
 
@@ -126,23 +383,74 @@ impl DomainEvent for GroupUpdated {
     }
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Group {
+    pub id: GroupId,
     pub title: String,
-    pub members: IndexSet<UserId>,
+    pub parent: Option<GroupId>,
+    pub members: IndexMap<UserId, GroupRole>,
+    /// Whether users can request to join the group
+    pub discoverable: bool,
+    pub invitations: IndexSet<UserId>,
+    pub join_requests: IndexSet<UserId>,
+    pub assignment_strategy: AssignmentStrategy,
+    pub workflow: TicketWorkflow,
+    pub sla_policy: SlaPolicy,
+    pub labels: Vec<TicketLabel>,
+    pub form: TicketForm,
+    pub inactivity_policy: Option<InactivityPolicy>,
 }
 
 impl Group {
-    fn check_access(&self, user_id: UserId) -> Result<(), GroupError> {
-        if !self.members.contains(&user_id) {
-            return Err(GroupError::Forbidden);
+    /// Checks that the user is a member of the group with at least the specified role, returning their actual role
+    fn check_role(&self, user_id: UserId, required: GroupRole) -> Result<GroupRole, GroupError> {
+        match self.members.get(&user_id) {
+            Some(&role) if role >= required => Ok(role),
+            _ => Err(GroupError::Forbidden),
         }
-        Ok(())
+    }
+
+    fn is_last_owner(&self, user_id: UserId) -> bool {
+        self.members.get(&user_id) == Some(&GroupRole::Owner)
+            && self
+                .members
+                .values()
+                .filter(|&&role| role == GroupRole::Owner)
+                .count()
+                == 1
     }
 }
 
 pub type GroupAggregate = LifecycleAggregateState<Group>;
 
+pub struct GroupServices {
+    pub group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+    pub group_hierarchy_view_repository: Arc<dyn ViewRepository<GroupHierarchyView>>,
+}
+
+impl GroupServices {
+    /// Checks that the user is allowed to attach sub-groups to the specified group
+    async fn check_parent_access(
+        &self,
+        parent: GroupId,
+        user_id: UserId,
+    ) -> Result<(), GroupError> {
+        let parent = self
+            .group_view_repository
+            .load(&parent.0.to_string())
+            .await
+            .unwrap()
+            .and_then(LifecycleViewState::into_created)
+            .ok_or(GroupError::DoesNotExist)?;
+
+        if parent.role_of(user_id) >= Some(GroupRole::Admin) {
+            Ok(())
+        } else {
+            Err(GroupError::Forbidden)
+        }
+    }
+}
+
 #[derive(Snafu, Debug)]
 pub enum GroupError {
     /// Group already exists
@@ -151,6 +459,30 @@ pub enum GroupError {
     DoesNotExist,
     /// This user cannot perform this action
     Forbidden,
+    /// The user is not a member of this group
+    NotAMember,
+    /// The last owner of the group cannot be removed or demoted
+    LastOwner,
+    /// The user is already a member of this group
+    AlreadyMember,
+    /// The user has no pending invitation to this group
+    NotInvited,
+    /// The user has no pending request to join this group
+    NoJoinRequest,
+    /// This group does not accept join requests
+    NotDiscoverable,
+    /// A group cannot be placed under itself or one of its sub-groups
+    HierarchyCycle,
+    /// The workflow statuses must be unique, and the initial status and the transitions must refer to them
+    InvalidWorkflow,
+    /// The group already has a label with this name
+    LabelAlreadyExists,
+    /// The group has no label with this name
+    NoSuchLabel,
+    /// The form fields must have unique ids, select fields need options and the minimums must not exceed the maximums
+    InvalidForm,
+    /// The inactivity policy must refer to open waiting statuses and a closed status of the workflow, and must warn before closing
+    InvalidInactivityPolicy,
 }
 
 impl ApiError for GroupError {
@@ -159,6 +491,18 @@ impl ApiError for GroupError {
             GroupError::AlreadyExists => StatusCode::BAD_REQUEST,
             GroupError::DoesNotExist => StatusCode::NOT_FOUND,
             GroupError::Forbidden => StatusCode::FORBIDDEN,
+            GroupError::NotAMember => StatusCode::BAD_REQUEST,
+            GroupError::LastOwner => StatusCode::BAD_REQUEST,
+            GroupError::AlreadyMember => StatusCode::BAD_REQUEST,
+            GroupError::NotInvited => StatusCode::BAD_REQUEST,
+            GroupError::NoJoinRequest => StatusCode::BAD_REQUEST,
+            GroupError::NotDiscoverable => StatusCode::FORBIDDEN,
+            GroupError::HierarchyCycle => StatusCode::BAD_REQUEST,
+            GroupError::InvalidWorkflow => StatusCode::BAD_REQUEST,
+            GroupError::LabelAlreadyExists => StatusCode::BAD_REQUEST,
+            GroupError::NoSuchLabel => StatusCode::NOT_FOUND,
+            GroupError::InvalidForm => StatusCode::BAD_REQUEST,
+            GroupError::InvalidInactivityPolicy => StatusCode::BAD_REQUEST,
         }
     }
 }
@@ -166,13 +510,13 @@ impl ApiError for GroupError {
 #[async_trait]
 impl LifecycleAggregate for Group {
     type Id = GroupId;
-    type CreateCommand = Authenticated<CreateGroup>;
+    type CreateCommand = Authenticated<NewGroup>;
     type UpdateCommand = Authenticated<UpdateGroup>;
     type DeleteCommand = ();
     type CreateEvent = GroupCreated;
     type UpdateEvent = GroupUpdated;
     type Error = GroupError;
-    type Services = ();
+    type Services = GroupServices;
 
     fn aggregate_type() -> String {
         "Group".to_string()
@@ -181,40 +525,149 @@ impl LifecycleAggregate for Group {
     async fn handle_create(
         Authenticated {
             user_id: performer,
-            payload: CreateGroup { title },
+            payload:
+                NewGroup {
+                    id,
+                    creation: CreateGroup { title, parent },
+                },
         }: Self::CreateCommand,
-        _service: &Self::Services,
+        service: &Self::Services,
     ) -> Result<(Self::CreateEvent, Vec<Self::UpdateEvent>), Self::Error> {
+        if let Some(parent) = parent {
+            service.check_parent_access(parent, performer).await?;
+        }
+
         Ok((
-            GroupCreated { title },
-            vec![GroupUpdated::MemberAdded {
-                performer,
-                member: performer,
-            }],
+            GroupCreated { id, title, parent },
+            vec![
+                GroupUpdated::MemberAdded {
+                    performer,
+                    member: performer,
+                },
+                GroupUpdated::MemberRoleChanged {
+                    performer,
+                    member: performer,
+                    old_role: GroupRole::Member,
+                    new_role: GroupRole::Owner,
+                },
+            ],
         ))
     }
 
     async fn handle(
         &self,
         command: Self::UpdateCommand,
-        _service: &Self::Services,
+        service: &Self::Services,
     ) -> Result<Vec<Self::UpdateEvent>, Self::Error> {
         let mut events = Vec::new();
         let performer = command.user_id;
 
         match command.payload {
-            UpdateGroup::AddMember(AddGroupMember { new_member }) => {
-                self.check_access(performer)?;
-                if !self.members.contains(&new_member) {
+            UpdateGroup::InviteMember(InviteGroupMember { invitee }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if self.members.contains_key(&invitee) {
+                    return Err(GroupError::AlreadyMember);
+                }
+                if self.join_requests.contains(&invitee) {
+                    // the user has already asked to join, no need to ask for their consent again
+                    events.push(GroupUpdated::JoinRequestApproved {
+                        performer,
+                        requester: invitee,
+                    });
+                    events.push(GroupUpdated::MemberAdded {
+                        performer,
+                        member: invitee,
+                    });
+                } else if !self.invitations.contains(&invitee) {
+                    events.push(GroupUpdated::InvitationSent { performer, invitee });
+                }
+            }
+            UpdateGroup::RevokeInvitation(RevokeGroupInvitation { invitee }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if self.invitations.contains(&invitee) {
+                    events.push(GroupUpdated::InvitationRevoked { performer, invitee });
+                }
+            }
+            UpdateGroup::AcceptInvitation => {
+                if !self.invitations.contains(&performer) {
+                    return Err(GroupError::NotInvited);
+                }
+                events.push(GroupUpdated::InvitationAccepted { invitee: performer });
+                events.push(GroupUpdated::MemberAdded {
+                    performer,
+                    member: performer,
+                });
+            }
+            UpdateGroup::DeclineInvitation => {
+                if !self.invitations.contains(&performer) {
+                    return Err(GroupError::NotInvited);
+                }
+                events.push(GroupUpdated::InvitationDeclined { invitee: performer });
+            }
+            UpdateGroup::RequestToJoin => {
+                if self.members.contains_key(&performer) {
+                    return Err(GroupError::AlreadyMember);
+                }
+                if self.invitations.contains(&performer) {
+                    // the user was already invited, treat it as an acceptance
+                    events.push(GroupUpdated::InvitationAccepted { invitee: performer });
                     events.push(GroupUpdated::MemberAdded {
                         performer,
-                        member: new_member,
+                        member: performer,
                     });
+                } else if !self.discoverable {
+                    return Err(GroupError::NotDiscoverable);
+                } else if !self.join_requests.contains(&performer) {
+                    events.push(GroupUpdated::JoinRequested {
+                        requester: performer,
+                    });
+                }
+            }
+            UpdateGroup::WithdrawJoinRequest => {
+                if self.join_requests.contains(&performer) {
+                    events.push(GroupUpdated::JoinRequestWithdrawn {
+                        requester: performer,
+                    });
+                }
+            }
+            UpdateGroup::ApproveJoinRequest(ResolveGroupJoinRequest { requester }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if !self.join_requests.contains(&requester) {
+                    return Err(GroupError::NoJoinRequest);
+                }
+                events.push(GroupUpdated::JoinRequestApproved {
+                    performer,
+                    requester,
+                });
+                events.push(GroupUpdated::MemberAdded {
+                    performer,
+                    member: requester,
+                });
+            }
+            UpdateGroup::RejectJoinRequest(ResolveGroupJoinRequest { requester }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if !self.join_requests.contains(&requester) {
+                    return Err(GroupError::NoJoinRequest);
                 }
+                events.push(GroupUpdated::JoinRequestRejected {
+                    performer,
+                    requester,
+                });
             }
             UpdateGroup::RemoveMember(RemoveGroupMember { removed_member }) => {
-                self.check_access(performer)?;
-                if self.members.contains(&removed_member) {
+                if removed_member == performer {
+                    // any member can leave the group
+                    self.check_role(performer, GroupRole::Member)?;
+                } else {
+                    let performer_role = self.check_role(performer, GroupRole::Admin)?;
+                    if self.members.get(&removed_member) > Some(&performer_role) {
+                        return Err(GroupError::Forbidden);
+                    }
+                }
+                if self.is_last_owner(removed_member) {
+                    return Err(GroupError::LastOwner);
+                }
+                if self.members.contains_key(&removed_member) {
                     events.push(GroupUpdated::MemberRemoved {
                         performer,
                         member: removed_member,
@@ -222,7 +675,7 @@ impl LifecycleAggregate for Group {
                 }
             }
             UpdateGroup::ChangeTitle(ChangeGroupTitle { new_title }) => {
-                self.check_access(performer)?;
+                self.check_role(performer, GroupRole::Admin)?;
                 if self.title != new_title {
                     events.push(GroupUpdated::TitleChanged {
                         performer,
@@ -231,6 +684,122 @@ impl LifecycleAggregate for Group {
                     })
                 }
             }
+            UpdateGroup::ChangeMemberRole(ChangeGroupMemberRole { member, new_role }) => {
+                let performer_role = self.check_role(performer, GroupRole::Admin)?;
+                let old_role = *self.members.get(&member).ok_or(GroupError::NotAMember)?;
+                // nobody can grant or take away more privileges than they have
+                if old_role.max(new_role) > performer_role {
+                    return Err(GroupError::Forbidden);
+                }
+                if new_role != GroupRole::Owner && self.is_last_owner(member) {
+                    return Err(GroupError::LastOwner);
+                }
+                if old_role != new_role {
+                    events.push(GroupUpdated::MemberRoleChanged {
+                        performer,
+                        member,
+                        old_role,
+                        new_role,
+                    })
+                }
+            }
+            UpdateGroup::ChangeDiscoverability(ChangeGroupDiscoverability { discoverable }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if self.discoverable != discoverable {
+                    events.push(GroupUpdated::DiscoverabilityChanged {
+                        performer,
+                        discoverable,
+                    });
+                }
+            }
+            UpdateGroup::ChangeParent(ChangeGroupParent { new_parent }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if let Some(new_parent) = new_parent {
+                    service.check_parent_access(new_parent, performer).await?;
+
+                    let ancestors = load_group_ancestors(
+                        service.group_hierarchy_view_repository.as_ref(),
+                        new_parent,
+                    )
+                    .await
+                    .unwrap();
+                    if new_parent == self.id || ancestors.contains(&self.id) {
+                        return Err(GroupError::HierarchyCycle);
+                    }
+                }
+                if self.parent != new_parent {
+                    events.push(GroupUpdated::ParentChanged {
+                        performer,
+                        old_parent: self.parent,
+                        new_parent,
+                    });
+                }
+            }
+            UpdateGroup::ChangeAssignmentStrategy(ChangeGroupAssignmentStrategy { strategy }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if self.assignment_strategy != strategy {
+                    events.push(GroupUpdated::AssignmentStrategyChanged {
+                        performer,
+                        strategy,
+                    });
+                }
+            }
+            UpdateGroup::ChangeWorkflow(ChangeGroupWorkflow { workflow }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if !workflow.is_valid() {
+                    return Err(GroupError::InvalidWorkflow);
+                }
+                if self.workflow != workflow {
+                    events.push(GroupUpdated::WorkflowChanged {
+                        performer,
+                        workflow,
+                    });
+                }
+            }
+            UpdateGroup::ChangeForm(ChangeGroupForm { form }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if !form.is_valid() {
+                    return Err(GroupError::InvalidForm);
+                }
+                if self.form != form {
+                    events.push(GroupUpdated::FormChanged { performer, form });
+                }
+            }
+            UpdateGroup::ChangeInactivityPolicy(ChangeGroupInactivityPolicy { policy }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if policy
+                    .as_ref()
+                    .is_some_and(|policy| !policy.is_valid(&self.workflow))
+                {
+                    return Err(GroupError::InvalidInactivityPolicy);
+                }
+                if self.inactivity_policy != policy {
+                    events.push(GroupUpdated::InactivityPolicyChanged { performer, policy });
+                }
+            }
+            UpdateGroup::ChangeSlaPolicy(ChangeGroupSlaPolicy { policy }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if self.sla_policy != policy {
+                    events.push(GroupUpdated::SlaPolicyChanged { performer, policy });
+                }
+            }
+            UpdateGroup::CreateLabel(CreateGroupLabel { name, color }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if self.labels.iter().any(|label| label.name == name) {
+                    return Err(GroupError::LabelAlreadyExists);
+                }
+                events.push(GroupUpdated::LabelCreated {
+                    performer,
+                    label: TicketLabel { name, color },
+                });
+            }
+            UpdateGroup::DeleteLabel(DeleteGroupLabel { name }) => {
+                self.check_role(performer, GroupRole::Admin)?;
+                if !self.labels.iter().any(|label| label.name == name) {
+                    return Err(GroupError::NoSuchLabel);
+                }
+                events.push(GroupUpdated::LabelDeleted { performer, name });
+            }
         }
 
         Ok(events)
@@ -244,17 +813,28 @@ impl LifecycleAggregate for Group {
         todo!()
     }
 
-    fn apply_create(GroupCreated { title }: Self::CreateEvent) -> Self {
+    fn apply_create(GroupCreated { id, title, parent }: Self::CreateEvent) -> Self {
         Self {
+            id,
             title,
-            members: IndexSet::new(),
+            parent,
+            members: IndexMap::new(),
+            discoverable: false,
+            invitations: IndexSet::new(),
+            join_requests: IndexSet::new(),
+            assignment_strategy: AssignmentStrategy::Manual,
+            workflow: TicketWorkflow::default(),
+            sla_policy: SlaPolicy::default(),
+            labels: Vec::new(),
+            form: TicketForm::default(),
+            inactivity_policy: None,
         }
     }
 
     fn apply(&mut self, event: Self::UpdateEvent) {
         match event {
             GroupUpdated::MemberAdded { member, .. } => {
-                self.members.insert(member);
+                self.members.insert(member, GroupRole::Member);
             }
             GroupUpdated::MemberRemoved { member, .. } => {
                 self.members.shift_remove(&member);
@@ -262,6 +842,54 @@ impl LifecycleAggregate for Group {
             GroupUpdated::TitleChanged { new_title, .. } => {
                 self.title = new_title;
             }
+            GroupUpdated::MemberRoleChanged {
+                member, new_role, ..
+            } => {
+                self.members.insert(member, new_role);
+            }
+            GroupUpdated::InvitationSent { invitee, .. } => {
+                self.invitations.insert(invitee);
+            }
+            GroupUpdated::InvitationRevoked { invitee, .. }
+            | GroupUpdated::InvitationAccepted { invitee }
+            | GroupUpdated::InvitationDeclined { invitee } => {
+                self.invitations.shift_remove(&invitee);
+            }
+            GroupUpdated::JoinRequested { requester } => {
+                self.join_requests.insert(requester);
+            }
+            GroupUpdated::JoinRequestWithdrawn { requester }
+            | GroupUpdated::JoinRequestApproved { requester, .. }
+            | GroupUpdated::JoinRequestRejected { requester, .. } => {
+                self.join_requests.shift_remove(&requester);
+            }
+            GroupUpdated::DiscoverabilityChanged { discoverable, .. } => {
+                self.discoverable = discoverable;
+            }
+            GroupUpdated::ParentChanged { new_parent, .. } => {
+                self.parent = new_parent;
+            }
+            GroupUpdated::AssignmentStrategyChanged { strategy, .. } => {
+                self.assignment_strategy = strategy;
+            }
+            GroupUpdated::WorkflowChanged { workflow, .. } => {
+                self.workflow = workflow;
+            }
+            GroupUpdated::FormChanged { form, .. } => {
+                self.form = form;
+            }
+            GroupUpdated::InactivityPolicyChanged { policy, .. } => {
+                self.inactivity_policy = policy;
+            }
+            GroupUpdated::SlaPolicyChanged { policy, .. } => {
+                self.sla_policy = policy;
+            }
+            GroupUpdated::LabelCreated { label, .. } => {
+                self.labels.push(label);
+            }
+            GroupUpdated::LabelDeleted { name, .. } => {
+                self.labels.retain(|label| label.name != name);
+            }
         }
     }
 }
@@ -273,10 +901,33 @@ pub struct GroupView {
     #[collect_ids(skip)]
     pub id: GroupId,
     pub title: String,
+    pub parent: Option<GroupId>,
     pub members: IndexSet<UserId>,
+    /// Roles of the members, in the same order as `members`
+    #[collect_ids(skip)]
+    pub roles: IndexMap<UserId, GroupRole>,
+    pub discoverable: bool,
+    pub assignment_strategy: AssignmentStrategy,
+    pub workflow: TicketWorkflow,
+    pub sla_policy: SlaPolicy,
+    pub labels: Vec<TicketLabel>,
+    pub form: TicketForm,
+    pub inactivity_policy: Option<InactivityPolicy>,
 }
 
 impl GroupView {
+    pub fn role_of(&self, user_id: UserId) -> Option<GroupRole> {
+        self.roles.get(&user_id).copied()
+    }
+
+    /// Members that handle the tickets addressed to the group, in the order they joined
+    pub fn agents(&self) -> impl Iterator<Item = UserId> + '_ {
+        self.roles
+            .iter()
+            .filter(|(_, &role)| role >= GroupRole::Agent)
+            .map(|(&user_id, _)| user_id)
+    }
+
     pub fn profile(&self) -> GroupProfileView {
         GroupProfileView {
             id: self.id,
@@ -289,11 +940,20 @@ impl LifecycleView for GroupView {
     type Aggregate = Group;
 
     fn create(event: CreateEnvelope<'_, Self::Aggregate>) -> Self {
-        let GroupCreated { title } = event.payload;
+        let GroupCreated { title, parent, .. } = event.payload;
         Self {
             id: event.aggregate_id,
             title: title.clone(),
+            parent: *parent,
             members: IndexSet::new(),
+            roles: IndexMap::new(),
+            discoverable: false,
+            assignment_strategy: AssignmentStrategy::Manual,
+            workflow: TicketWorkflow::default(),
+            sla_policy: SlaPolicy::default(),
+            labels: Vec::new(),
+            form: TicketForm::default(),
+            inactivity_policy: None,
         }
     }
 
@@ -301,13 +961,112 @@ impl LifecycleView for GroupView {
         match *event.payload {
             GroupUpdated::MemberAdded { member, .. } => {
                 self.members.insert(member);
+                self.roles.insert(member, GroupRole::Member);
             }
             GroupUpdated::MemberRemoved { member, .. } => {
-                self.members.remove(&member);
+                self.members.shift_remove(&member);
+                self.roles.shift_remove(&member);
             }
             GroupUpdated::TitleChanged { ref new_title, .. } => {
                 self.title = new_title.clone();
             }
+            GroupUpdated::MemberRoleChanged {
+                member, new_role, ..
+            } => {
+                self.roles.insert(member, new_role);
+            }
+            GroupUpdated::DiscoverabilityChanged { discoverable, .. } => {
+                self.discoverable = discoverable;
+            }
+            GroupUpdated::ParentChanged { new_parent, .. } => {
+                self.parent = new_parent;
+            }
+            GroupUpdated::AssignmentStrategyChanged { strategy, .. } => {
+                self.assignment_strategy = strategy;
+            }
+            GroupUpdated::WorkflowChanged { ref workflow, .. } => {
+                self.workflow = workflow.clone();
+            }
+            GroupUpdated::FormChanged { ref form, .. } => {
+                self.form = form.clone();
+            }
+            GroupUpdated::InactivityPolicyChanged { ref policy, .. } => {
+                self.inactivity_policy = policy.clone();
+            }
+            GroupUpdated::SlaPolicyChanged { ref policy, .. } => {
+                self.sla_policy = policy.clone();
+            }
+            GroupUpdated::LabelCreated { ref label, .. } => {
+                self.labels.push(label.clone());
+            }
+            GroupUpdated::LabelDeleted { ref name, .. } => {
+                self.labels.retain(|label| &label.name != name);
+            }
+            GroupUpdated::InvitationSent { .. }
+            | GroupUpdated::InvitationRevoked { .. }
+            | GroupUpdated::InvitationAccepted { .. }
+            | GroupUpdated::InvitationDeclined { .. }
+            | GroupUpdated::JoinRequested { .. }
+            | GroupUpdated::JoinRequestWithdrawn { .. }
+            | GroupUpdated::JoinRequestApproved { .. }
+            | GroupUpdated::JoinRequestRejected { .. } => {}
+        }
+    }
+}
+
+/// Pending invitations and join requests of a group. Only visible to its admins.
+#[derive(Debug, Clone, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct GroupInvitationsView {
+    #[collect_ids(skip)]
+    pub id: GroupId,
+    pub invitations: IndexSet<UserId>,
+    pub join_requests: IndexSet<UserId>,
+}
+
+impl LifecycleView for GroupInvitationsView {
+    type Aggregate = Group;
+
+    fn create(event: CreateEnvelope<'_, Self::Aggregate>) -> Self {
+        Self {
+            id: event.aggregate_id,
+            invitations: IndexSet::new(),
+            join_requests: IndexSet::new(),
+        }
+    }
+
+    fn update(&mut self, event: UpdateEnvelope<'_, Self::Aggregate>) {
+        match *event.payload {
+            GroupUpdated::InvitationSent { invitee, .. } => {
+                self.invitations.insert(invitee);
+            }
+            GroupUpdated::InvitationRevoked { invitee, .. }
+            | GroupUpdated::InvitationAccepted { invitee }
+            | GroupUpdated::InvitationDeclined { invitee } => {
+                self.invitations.shift_remove(&invitee);
+            }
+            GroupUpdated::JoinRequested { requester } => {
+                self.join_requests.insert(requester);
+            }
+            GroupUpdated::JoinRequestWithdrawn { requester }
+            | GroupUpdated::JoinRequestApproved { requester, .. }
+            | GroupUpdated::JoinRequestRejected { requester, .. } => {
+                self.join_requests.shift_remove(&requester);
+            }
+            GroupUpdated::MemberAdded { .. }
+            | GroupUpdated::MemberRemoved { .. }
+            | GroupUpdated::TitleChanged { .. }
+            | GroupUpdated::MemberRoleChanged { .. }
+            | GroupUpdated::DiscoverabilityChanged { .. }
+            | GroupUpdated::ParentChanged { .. }
+            | GroupUpdated::AssignmentStrategyChanged { .. }
+            | GroupUpdated::WorkflowChanged { .. }
+            | GroupUpdated::FormChanged { .. }
+            | GroupUpdated::InactivityPolicyChanged { .. }
+            | GroupUpdated::SlaPolicyChanged { .. }
+            | GroupUpdated::LabelCreated { .. }
+            | GroupUpdated::LabelDeleted { .. } => {}
         }
     }
 }
@@ -376,3 +1135,258 @@ where
         }
     }
 }
+
+/// Groups the user has a pending invitation to
+#[derive(Default, Debug, Clone, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct UserInvitationsView {
+    pub items: IndexSet<GroupId>,
+}
+
+impl View for UserInvitationsView {
+    type Aggregate = GroupAggregate;
+}
+
+pub struct UserInvitationsQuery<R>
+where
+    R: ViewRepository<UserInvitationsView>,
+{
+    view_repository: Arc<R>,
+}
+
+impl<R> UserInvitationsQuery<R>
+where
+    R: ViewRepository<UserInvitationsView>,
+{
+    pub fn new(view_repository: Arc<R>) -> Self {
+        Self { view_repository }
+    }
+}
+
+#[async_trait]
+impl<R> Query<GroupAggregate> for UserInvitationsQuery<R>
+where
+    R: ViewRepository<UserInvitationsView>,
+{
+    async fn dispatch(&self, _aggregate_id: GroupId, events: &[LifecycleEnvelope<Group>]) {
+        for event in events {
+            let group_id = event.aggregate_id;
+            if let LifecycleEvent::Updated(
+                event @ (GroupUpdated::InvitationSent { invitee, .. }
+                | GroupUpdated::InvitationRevoked { invitee, .. }
+                | GroupUpdated::InvitationAccepted { invitee }
+                | GroupUpdated::InvitationDeclined { invitee }),
+            ) = &event.payload
+            {
+                let user_id = invitee.0.to_string();
+
+                self.view_repository
+                    .load_modify_update_default(&user_id, |view| match event {
+                        GroupUpdated::InvitationSent { .. } => {
+                            view.items.insert(group_id);
+                        }
+                        _ => {
+                            view.items.shift_remove(&group_id);
+                        }
+                    })
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Position of a group in the hierarchy
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct GroupHierarchyView {
+    pub parent: Option<GroupId>,
+    pub children: IndexSet<GroupId>,
+}
+
+impl View for GroupHierarchyView {
+    type Aggregate = GroupAggregate;
+}
+
+pub struct GroupHierarchyQuery<R>
+where
+    R: ViewRepository<GroupHierarchyView>,
+{
+    view_repository: Arc<R>,
+}
+
+impl<R> GroupHierarchyQuery<R>
+where
+    R: ViewRepository<GroupHierarchyView>,
+{
+    pub fn new(view_repository: Arc<R>) -> Self {
+        Self { view_repository }
+    }
+
+    async fn move_group(
+        &self,
+        group_id: GroupId,
+        old_parent: Option<GroupId>,
+        new_parent: Option<GroupId>,
+    ) {
+        self.view_repository
+            .load_modify_update_default(&group_id.0.to_string(), |view| {
+                view.parent = new_parent;
+            })
+            .await
+            .unwrap();
+
+        if let Some(old_parent) = old_parent {
+            self.view_repository
+                .load_modify_update_default(&old_parent.0.to_string(), |view| {
+                    view.children.shift_remove(&group_id);
+                })
+                .await
+                .unwrap();
+        }
+        if let Some(new_parent) = new_parent {
+            self.view_repository
+                .load_modify_update_default(&new_parent.0.to_string(), |view| {
+                    view.children.insert(group_id);
+                })
+                .await
+                .unwrap();
+        }
+    }
+}
+
+#[async_trait]
+impl<R> Query<GroupAggregate> for GroupHierarchyQuery<R>
+where
+    R: ViewRepository<GroupHierarchyView>,
+{
+    async fn dispatch(&self, _aggregate_id: GroupId, events: &[LifecycleEnvelope<Group>]) {
+        for event in events {
+            match &event.payload {
+                LifecycleEvent::Created(GroupCreated { parent, .. }) => {
+                    self.move_group(event.aggregate_id, None, *parent).await;
+                }
+                LifecycleEvent::Updated(GroupUpdated::ParentChanged {
+                    old_parent,
+                    new_parent,
+                    ..
+                }) => {
+                    self.move_group(event.aggregate_id, *old_parent, *new_parent)
+                        .await;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lists the ancestors of the group, starting from its parent
+pub async fn load_group_ancestors<R>(
+    view_repository: &R,
+    group_id: GroupId,
+) -> Result<Vec<GroupId>, PersistenceError>
+where
+    R: ViewRepository<GroupHierarchyView> + ?Sized,
+{
+    let mut ancestors = Vec::new();
+    let mut current = group_id;
+    while let Some(parent) = view_repository
+        .load(&current.0.to_string())
+        .await?
+        .and_then(|view| view.parent)
+    {
+        // the aggregate does not allow cycles, but better safe than sorry
+        if parent == group_id || ancestors.contains(&parent) {
+            break;
+        }
+        ancestors.push(parent);
+        current = parent;
+    }
+
+    Ok(ancestors)
+}
+
+/// Maps the group and each of its descendants to their children, level by level
+pub async fn load_group_subtree<R>(
+    view_repository: &R,
+    group_id: GroupId,
+) -> Result<IndexMap<GroupId, IndexSet<GroupId>>, PersistenceError>
+where
+    R: ViewRepository<GroupHierarchyView> + ?Sized,
+{
+    let mut children = IndexMap::new();
+    let mut queue = vec![group_id];
+    while !queue.is_empty() {
+        let mut next = Vec::new();
+        for current in queue {
+            if children.contains_key(&current) {
+                continue;
+            }
+            let view = view_repository
+                .load(&current.0.to_string())
+                .await?
+                .unwrap_or_default();
+            next.extend(view.children.iter().copied());
+            children.insert(current, view.children);
+        }
+        queue = next;
+    }
+
+    Ok(children)
+}
+
+/// Whether the user has at least the given role in the group or in one of its sub-groups
+///
+/// Walks up from the groups of the user instead of down from the group, so the cost
+/// is bounded by the number of memberships and the depth of the hierarchy.
+pub async fn has_role_in_subtree<G, H, U>(
+    group_view_repository: &G,
+    group_hierarchy_view_repository: &H,
+    user_groups_view_repository: &U,
+    group_id: GroupId,
+    user_id: UserId,
+    role: GroupRole,
+) -> Result<bool, PersistenceError>
+where
+    G: ViewRepository<LifecycleViewState<GroupView>> + ?Sized,
+    H: ViewRepository<GroupHierarchyView> + ?Sized,
+    U: ViewRepository<UserGroupsView> + ?Sized,
+{
+    let user_groups = user_groups_view_repository
+        .load(&user_id.0.to_string())
+        .await?
+        .unwrap_or_default();
+    for member_of in user_groups.items {
+        let Some(group) = group_view_repository
+            .load(&member_of.0.to_string())
+            .await?
+            .and_then(LifecycleViewState::into_created)
+        else {
+            continue;
+        };
+        if group.role_of(user_id) < Some(role) {
+            continue;
+        }
+        if member_of == group_id
+            || load_group_ancestors(group_hierarchy_view_repository, member_of)
+                .await?
+                .contains(&group_id)
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// A sub-tree of the group hierarchy
+#[derive(Debug, Clone, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct GroupTreeView {
+    pub root: GroupId,
+    /// Ancestors of the root, starting from the top-level group
+    pub ancestors: Vec<GroupId>,
+    /// Children of the root and of each of its descendants
+    pub children: IndexMap<GroupId, IndexSet<GroupId>>,
+}
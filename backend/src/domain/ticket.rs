@@ -1,20 +1,27 @@
 use crate::auth::Authenticated;
-use crate::domain::group::{GroupId, GroupView};
+use crate::domain::group::{
+    has_role_in_subtree, AssignmentStrategy, GroupHierarchyView, GroupId, GroupRole, GroupView,
+    UserGroupsView,
+};
+use crate::domain::upload::{discard_key, UploadId, UploadView};
 use crate::domain::user::UserId;
 use crate::error::ApiError;
+use crate::process_manager::CommandSender;
 use crate::related_data::CollectIds;
-use crate::view_repositry_ext::ViewRepositoryExt;
+use crate::scheduler::{Clock, ScheduledCommand, Scheduler};
+use crate::view_repositry_ext::{LifecycleViewRepositoryExt as _, ViewRepositoryExt};
 use async_trait::async_trait;
 use axum::http::StatusCode;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use cqrs_es::lifecycle::{
-    CreateEnvelope, LifecycleAggregate, LifecycleAggregateState, LifecycleEnvelope, LifecycleEvent,
-    LifecycleView, LifecycleViewState, UpdateEnvelope,
+    CreateEnvelope, LifecycleAggregate, LifecycleAggregateState, LifecycleCommand,
+    LifecycleEnvelope, LifecycleEvent, LifecycleView, LifecycleViewState, UpdateEnvelope,
 };
-use cqrs_es::persist::ViewRepository;
+use cqrs_es::persist::{ViewContext, ViewRepository};
 use cqrs_es::{AnyId, Id};
-use cqrs_es::{DomainEvent, Query, View};
-use indexmap::IndexSet;
+use cqrs_es::{DomainEvent, EventStore, Query, View};
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools as _;
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use std::collections::HashSet;
@@ -33,6 +40,23 @@ impl CollectIds<UserId> for TicketId {
 impl CollectIds<GroupId> for TicketId {
     fn collect_ids(&self, _: &mut IndexSet<GroupId>) {}
 }
+impl CollectIds<UploadId> for TicketId {
+    fn collect_ids(&self, _: &mut IndexSet<UploadId>) {}
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, TS, Serialize, Deserialize)]
+#[ts(export)]
+pub struct TicketMessageId(pub Id);
+
+impl CollectIds<UserId> for TicketMessageId {
+    fn collect_ids(&self, _: &mut IndexSet<UserId>) {}
+}
+impl CollectIds<GroupId> for TicketMessageId {
+    fn collect_ids(&self, _: &mut IndexSet<GroupId>) {}
+}
+impl CollectIds<UploadId> for TicketMessageId {
+    fn collect_ids(&self, _: &mut IndexSet<UploadId>) {}
+}
 
 impl AnyId for TicketId {
     fn from_id(id: Id) -> Self {
@@ -51,6 +75,12 @@ pub struct CreateTicket {
     pub destination: TicketDestination,
     pub title: String,
     pub body: String,
+    /// Values of the destination group form fields, by field id
+    #[ts(optional)]
+    pub fields: Option<IndexMap<String, TicketFieldValue>>,
+    /// Finalized uploads of the creator, attached to the first message
+    #[ts(optional)]
+    pub attachments: Option<Vec<UploadId>>,
 }
 
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
@@ -58,6 +88,9 @@ pub struct CreateTicket {
 #[collect_ids(UserId, GroupId)]
 pub struct SendTicketMessage {
     pub body: String,
+    /// Finalized uploads of the sender
+    #[ts(optional)]
+    pub attachments: Option<Vec<UploadId>>,
 }
 
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
@@ -74,6 +107,125 @@ pub struct ChangeAssignee {
     pub new_assignee: Option<UserId>,
 }
 
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangePriority {
+    pub new_priority: TicketPriority,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct SetDueDate {
+    pub due_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct AddTicketLabel {
+    pub name: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct RemoveTicketLabel {
+    pub name: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct SendInternalNote {
+    pub body: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct EditMessage {
+    pub message: TicketMessageId,
+    pub new_text: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct RedactMessage {
+    pub message: TicketMessageId,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeTicketTitle {
+    pub new_title: String,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct SnoozeTicket {
+    pub until: DateTime<Utc>,
+}
+
+/// How a ticket relates to the linked one
+#[derive(Debug, Copy, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub enum TicketRelation {
+    DuplicateOf,
+    DuplicatedBy,
+    Related,
+    Blocks,
+    BlockedBy,
+}
+
+impl TicketRelation {
+    /// The relation as seen from the linked ticket
+    pub fn inverse(self) -> Self {
+        match self {
+            TicketRelation::DuplicateOf => TicketRelation::DuplicatedBy,
+            TicketRelation::DuplicatedBy => TicketRelation::DuplicateOf,
+            TicketRelation::Related => TicketRelation::Related,
+            TicketRelation::Blocks => TicketRelation::BlockedBy,
+            TicketRelation::BlockedBy => TicketRelation::Blocks,
+        }
+    }
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct LinkTicket {
+    pub other: TicketId,
+    pub kind: TicketRelation,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct UnlinkTicket {
+    pub other: TicketId,
+}
+
+/// Closes this ticket as a duplicate of the primary one
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct MergeInto {
+    pub primary: TicketId,
+}
+
+#[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct ChangeDestination {
+    pub new_destination: TicketDestination,
+}
+
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
 #[serde(tag = "type")]
@@ -82,6 +234,58 @@ pub enum UpdateTicket {
     SendTicketMessage(SendTicketMessage),
     ChangeStatus(ChangeStatus),
     ChangeAssignee(ChangeAssignee),
+    ChangePriority(ChangePriority),
+    SetDueDate(SetDueDate),
+    AddLabel(AddTicketLabel),
+    RemoveLabel(RemoveTicketLabel),
+    ChangeDestination(ChangeDestination),
+    Watch,
+    Unwatch,
+    SendInternalNote(SendInternalNote),
+    EditMessage(EditMessage),
+    RedactMessage(RedactMessage),
+    LinkTicket(LinkTicket),
+    UnlinkTicket(UnlinkTicket),
+    MergeInto(MergeInto),
+    ChangeTitle(ChangeTicketTitle),
+    /// Hides the ticket from the assignee listing until the date, or until the owner replies
+    Snooze(SnoozeTicket),
+    Unsnooze,
+}
+
+/// Commands issued by the system itself (e.g. process managers), bypassing the access checks
+#[derive(Debug)]
+pub enum SystemTicketCommand {
+    ChangeAssignee(ChangeAssignee),
+    /// Mirrors a link added on the other side
+    AddLink(LinkTicket),
+    /// Mirrors a link removed on the other side
+    RemoveLink(UnlinkTicket),
+    /// Takes over the watchers of a ticket merged into this one
+    AbsorbDuplicate {
+        duplicate: TicketId,
+        watchers: Vec<UserId>,
+    },
+    /// Warns the owner that the ticket will be closed if they do not reply
+    WarnInactivity {
+        close_at: DateTime<Utc>,
+    },
+    /// Closes a ticket that waited for its owner for too long, according to the group [`InactivityPolicy`]
+    CloseInactive,
+    /// Ends the snooze of the ticket when its date comes
+    WakeUp,
+}
+
+#[derive(Debug)]
+pub enum TicketCommand {
+    User(Authenticated<UpdateTicket>),
+    System(SystemTicketCommand),
+}
+
+impl From<Authenticated<UpdateTicket>> for TicketCommand {
+    fn from(command: Authenticated<UpdateTicket>) -> Self {
+        Self::User(command)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -90,6 +294,9 @@ pub struct TicketCreated {
     destination: TicketDestination,
     owner: UserId,
     title: String,
+    status: TicketStatusDefinition,
+    #[serde(default)]
+    fields: Vec<TicketField>,
 }
 
 impl DomainEvent for TicketCreated {
@@ -106,19 +313,112 @@ impl DomainEvent for TicketCreated {
 pub enum TicketUpdated {
     Message {
         date: DateTime<Utc>,
+        id: TicketMessageId,
         from: UserId,
         text: String,
+        #[serde(default)]
+        attachments: Vec<UploadId>,
     },
     StatusChanged {
         date: DateTime<Utc>,
         old_status: TicketStatus,
-        new_status: TicketStatus,
+        new_status: TicketStatusDefinition,
     },
     AssigneeChanged {
         date: DateTime<Utc>,
         old_assignee: Option<UserId>,
         new_assignee: Option<UserId>,
     },
+    PriorityChanged {
+        date: DateTime<Utc>,
+        old_priority: TicketPriority,
+        new_priority: TicketPriority,
+    },
+    DueDateChanged {
+        date: DateTime<Utc>,
+        old_due_date: Option<DateTime<Utc>>,
+        new_due_date: Option<DateTime<Utc>>,
+    },
+    LabelAdded {
+        date: DateTime<Utc>,
+        label: TicketLabel,
+    },
+    LabelRemoved {
+        date: DateTime<Utc>,
+        name: String,
+    },
+    DestinationChanged {
+        date: DateTime<Utc>,
+        old_destination: TicketDestination,
+        new_destination: TicketDestination,
+    },
+    WatcherAdded {
+        date: DateTime<Utc>,
+        user: UserId,
+    },
+    WatcherRemoved {
+        date: DateTime<Utc>,
+        user: UserId,
+    },
+    /// A message only visible to the handling side of the ticket
+    InternalNote {
+        date: DateTime<Utc>,
+        id: TicketMessageId,
+        from: UserId,
+        text: String,
+    },
+    MessageEdited {
+        date: DateTime<Utc>,
+        message: TicketMessageId,
+        text: String,
+    },
+    /// Removes the text of the message and all its revisions
+    MessageRedacted {
+        date: DateTime<Utc>,
+        message: TicketMessageId,
+        performer: UserId,
+    },
+    LinkAdded {
+        date: DateTime<Utc>,
+        other: TicketId,
+        kind: TicketRelation,
+    },
+    LinkRemoved {
+        date: DateTime<Utc>,
+        other: TicketId,
+    },
+    MergedInto {
+        date: DateTime<Utc>,
+        primary: TicketId,
+        performer: UserId,
+        /// The owner and the watchers of the ticket, to be moved to the primary ticket
+        watchers: Vec<UserId>,
+    },
+    DuplicateMerged {
+        date: DateTime<Utc>,
+        duplicate: TicketId,
+    },
+    TitleChanged {
+        date: DateTime<Utc>,
+        old_title: String,
+        new_title: String,
+    },
+    InactivityWarning {
+        date: DateTime<Utc>,
+        close_at: DateTime<Utc>,
+    },
+    /// Followed by the status change closing the ticket
+    ClosedForInactivity {
+        date: DateTime<Utc>,
+    },
+    Snoozed {
+        date: DateTime<Utc>,
+        until: DateTime<Utc>,
+        performer: UserId,
+    },
+    WokenUp {
+        date: DateTime<Utc>,
+    },
 }
 
 impl DomainEvent for TicketUpdated {
@@ -127,6 +427,25 @@ impl DomainEvent for TicketUpdated {
             TicketUpdated::Message { .. } => "Message".to_string(),
             TicketUpdated::StatusChanged { .. } => "StatusChanged".to_string(),
             TicketUpdated::AssigneeChanged { .. } => "AssigneeChanged".to_string(),
+            TicketUpdated::PriorityChanged { .. } => "PriorityChanged".to_string(),
+            TicketUpdated::DueDateChanged { .. } => "DueDateChanged".to_string(),
+            TicketUpdated::LabelAdded { .. } => "LabelAdded".to_string(),
+            TicketUpdated::LabelRemoved { .. } => "LabelRemoved".to_string(),
+            TicketUpdated::DestinationChanged { .. } => "DestinationChanged".to_string(),
+            TicketUpdated::WatcherAdded { .. } => "WatcherAdded".to_string(),
+            TicketUpdated::WatcherRemoved { .. } => "WatcherRemoved".to_string(),
+            TicketUpdated::InternalNote { .. } => "InternalNote".to_string(),
+            TicketUpdated::MessageEdited { .. } => "MessageEdited".to_string(),
+            TicketUpdated::MessageRedacted { .. } => "MessageRedacted".to_string(),
+            TicketUpdated::LinkAdded { .. } => "LinkAdded".to_string(),
+            TicketUpdated::LinkRemoved { .. } => "LinkRemoved".to_string(),
+            TicketUpdated::MergedInto { .. } => "MergedInto".to_string(),
+            TicketUpdated::DuplicateMerged { .. } => "DuplicateMerged".to_string(),
+            TicketUpdated::TitleChanged { .. } => "TitleChanged".to_string(),
+            TicketUpdated::InactivityWarning { .. } => "InactivityWarning".to_string(),
+            TicketUpdated::ClosedForInactivity { .. } => "ClosedForInactivity".to_string(),
+            TicketUpdated::Snoozed { .. } => "Snoozed".to_string(),
+            TicketUpdated::WokenUp { .. } => "WokenUp".to_string(),
         }
     }
 
@@ -141,6 +460,39 @@ pub enum TicketError {
     AlreadyExists,
     /// User cannot perform this action with this ticket
     Forbidden,
+    /// Status `{status}` is not part of the ticket workflow
+    UnknownStatus { status: TicketStatus },
+    /// The ticket workflow does not allow changing the status from `{from}` to `{to}`
+    StatusTransitionNotAllowed {
+        from: TicketStatus,
+        to: TicketStatus,
+    },
+    /// Label `{name}` is not in the label catalog of the ticket destination
+    UnknownLabel { name: String },
+    /// Message with the specified ID does not exist in this ticket
+    NoSuchMessage,
+    /// The message has been redacted and can no longer be edited
+    MessageRedacted,
+    /// Ticket with the specified ID does not exist
+    NoSuchTicket,
+    /// A ticket cannot be linked to or merged into itself
+    SelfReference,
+    /// The ticket has already been merged into another one
+    AlreadyMerged,
+    /// The ticket workflow has no closed status to close the merged ticket with
+    NoClosedStatus,
+    /// Field `{field}` is not part of the ticket form
+    UnknownField { field: String },
+    /// Field `{field}` is required
+    MissingField { field: String },
+    /// Invalid value for field `{field}`: {reason}
+    InvalidFieldValue { field: String, reason: String },
+    /// A ticket can only be snoozed until a date in the future
+    SnoozeInThePast,
+    /// Only finalized uploads can be attached to a ticket
+    AttachmentNotFinalized,
+    /// Only the owner of an upload can attach it to a ticket
+    AttachmentForbidden,
 }
 
 impl ApiError for TicketError {
@@ -148,30 +500,327 @@ impl ApiError for TicketError {
         match self {
             TicketError::AlreadyExists => StatusCode::BAD_REQUEST,
             TicketError::Forbidden => StatusCode::FORBIDDEN,
+            TicketError::UnknownStatus { .. } => StatusCode::BAD_REQUEST,
+            TicketError::StatusTransitionNotAllowed { .. } => StatusCode::BAD_REQUEST,
+            TicketError::UnknownLabel { .. } => StatusCode::BAD_REQUEST,
+            TicketError::NoSuchMessage => StatusCode::NOT_FOUND,
+            TicketError::MessageRedacted => StatusCode::BAD_REQUEST,
+            TicketError::NoSuchTicket => StatusCode::NOT_FOUND,
+            TicketError::SelfReference => StatusCode::BAD_REQUEST,
+            TicketError::AlreadyMerged => StatusCode::BAD_REQUEST,
+            TicketError::NoClosedStatus => StatusCode::BAD_REQUEST,
+            TicketError::UnknownField { .. } => StatusCode::BAD_REQUEST,
+            TicketError::MissingField { .. } => StatusCode::BAD_REQUEST,
+            TicketError::InvalidFieldValue { .. } => StatusCode::BAD_REQUEST,
+            TicketError::SnoozeInThePast => StatusCode::BAD_REQUEST,
+            TicketError::AttachmentNotFinalized => StatusCode::BAD_REQUEST,
+            TicketError::AttachmentForbidden => StatusCode::FORBIDDEN,
         }
     }
 }
 
-#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+/// Identifier of a status in a [`TicketWorkflow`]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, TS, Serialize, Deserialize)]
+#[ts(export)]
+pub struct TicketStatus(pub String);
+
+impl CollectIds<UserId> for TicketStatus {
+    fn collect_ids(&self, _: &mut IndexSet<UserId>) {}
+}
+impl CollectIds<GroupId> for TicketStatus {
+    fn collect_ids(&self, _: &mut IndexSet<GroupId>) {}
+}
+impl CollectIds<UploadId> for TicketStatus {
+    fn collect_ids(&self, _: &mut IndexSet<UploadId>) {}
+}
+
+impl Display for TicketStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub enum StatusCategory {
+    /// The ticket still requires work from its assignee
+    Open,
+    /// The ticket is resolved one way or another
+    Closed,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct TicketStatusDefinition {
+    pub id: TicketStatus,
+    pub label: String,
+    pub category: StatusCategory,
+}
+
+impl TicketStatusDefinition {
+    fn new(id: &str, label: &str, category: StatusCategory) -> Self {
+        Self {
+            id: TicketStatus(id.to_string()),
+            label: label.to_string(),
+            category,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.category == StatusCategory::Open
+    }
+}
+
+/// The statuses the tickets addressed to a group go through
+#[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
 #[collect_ids(UserId, GroupId)]
-pub enum TicketStatus {
+pub struct TicketWorkflow {
+    pub statuses: Vec<TicketStatusDefinition>,
+    /// Status of the new tickets
+    pub initial: TicketStatus,
+    /// Statuses that can be set from each status
+    pub transitions: IndexMap<TicketStatus, IndexSet<TicketStatus>>,
+}
+
+impl Default for TicketWorkflow {
+    /// Any status can be set from any other, as it was before the workflows were configurable
+    fn default() -> Self {
+        let statuses = vec![
+            TicketStatusDefinition::new("Pending", "Pending", StatusCategory::Open),
+            TicketStatusDefinition::new("InProgress", "In Progress", StatusCategory::Open),
+            TicketStatusDefinition::new("Declined", "Declined", StatusCategory::Closed),
+            TicketStatusDefinition::new("Fixed", "Fixed", StatusCategory::Closed),
+        ];
+        let transitions = statuses
+            .iter()
+            .map(|from| {
+                let targets = statuses
+                    .iter()
+                    .filter(|to| to.id != from.id)
+                    .map(|to| to.id.clone())
+                    .collect();
+                (from.id.clone(), targets)
+            })
+            .collect();
+
+        Self {
+            initial: statuses[0].id.clone(),
+            statuses,
+            transitions,
+        }
+    }
+}
+
+impl TicketWorkflow {
+    pub fn status(&self, id: &TicketStatus) -> Option<&TicketStatusDefinition> {
+        self.statuses.iter().find(|status| &status.id == id)
+    }
+
+    pub fn initial_status(&self) -> &TicketStatusDefinition {
+        self.status(&self.initial)
+            .expect("BUG: the initial status is not part of the workflow")
+    }
+
+    /// Checks that the statuses are unique and that the initial status and the transitions only refer to them
+    pub fn is_valid(&self) -> bool {
+        let ids = self
+            .statuses
+            .iter()
+            .map(|status| &status.id)
+            .collect::<HashSet<_>>();
+
+        ids.len() == self.statuses.len()
+            && ids.contains(&self.initial)
+            && self
+                .transitions
+                .iter()
+                .all(|(from, to)| ids.contains(from) && to.iter().all(|to| ids.contains(to)))
+    }
+
+    /// Checks that the status can be changed, returning the definition of the new status
+    ///
+    /// A ticket whose status is not part of the workflow (because the workflow was changed since) can be moved to any status.
+    pub fn check_transition(
+        &self,
+        from: &TicketStatus,
+        to: &TicketStatus,
+    ) -> Result<&TicketStatusDefinition, TicketError> {
+        let Some(new_status) = self.status(to) else {
+            return Err(TicketError::UnknownStatus { status: to.clone() });
+        };
+
+        let allowed = self.status(from).is_none()
+            || self
+                .transitions
+                .get(from)
+                .is_some_and(|targets| targets.contains(to));
+        if !allowed {
+            return Err(TicketError::StatusTransitionNotAllowed {
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+
+        Ok(new_status)
+    }
+}
+
+/// A label from the catalog of a group, used to categorize its tickets
+#[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub struct TicketLabel {
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(
+    Default,
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Ord,
+    PartialOrd,
+    TS,
+    Serialize,
+    Deserialize,
+    CollectIds,
+)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub enum TicketPriority {
+    Low,
     #[default]
-    Pending,
-    InProgress,
-    Declined,
-    Fixed,
+    Normal,
+    High,
+    Urgent,
+}
+
+/// Time limits for handling a ticket, in minutes since its creation
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct SlaTargets {
+    /// Until someone other than the owner answers the ticket
+    pub first_response_minutes: Option<u32>,
+    /// Until the ticket is closed
+    pub resolution_minutes: Option<u32>,
+}
+
+/// Service level agreement of a group. Priorities without targets have no time limits.
+#[derive(Default, Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct SlaPolicy {
+    pub targets: IndexMap<TicketPriority, SlaTargets>,
+}
+
+impl SlaPolicy {
+    pub fn targets(&self, priority: TicketPriority) -> SlaTargets {
+        self.targets.get(&priority).copied().unwrap_or_default()
+    }
+}
+
+/// Closes the tickets that wait for a reply from their owner for too long
+///
+/// Changing the policy does not restart the timers of the tickets that already wait: the new one applies
+/// from their next status change or reply, the pending warnings and closings check it when they are due
+#[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct InactivityPolicy {
+    /// Statuses in which the ticket waits for a reply from its owner
+    pub waiting_statuses: IndexSet<TicketStatus>,
+    /// Days without a reply before the owner is warned
+    pub warn_after_days: u32,
+    /// Days without a reply before the ticket is closed
+    pub close_after_days: u32,
+    /// Status the abandoned tickets are closed with
+    pub closed_status: TicketStatus,
+}
+
+impl InactivityPolicy {
+    /// Checks that the waiting statuses are open and the closing status is closed in the workflow, and that the warning comes first
+    pub fn is_valid(&self, workflow: &TicketWorkflow) -> bool {
+        self.warn_after_days < self.close_after_days
+            && workflow
+                .status(&self.closed_status)
+                .is_some_and(|status| !status.is_open())
+            && self.waiting_statuses.iter().all(|status| {
+                workflow
+                    .status(status)
+                    .is_some_and(TicketStatusDefinition::is_open)
+            })
+    }
+}
+
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, TS, Serialize, Deserialize, CollectIds,
+)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub enum SlaStatus {
+    /// The ticket has no pending SLA deadline
+    None,
+    OnTrack,
+    /// Less than a quarter of the time to a deadline remains
+    AtRisk,
+    Breached,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
 #[serde(tag = "type", content = "id")]
 #[ts(export)]
-#[collect_ids(UserId, GroupId)]
+#[collect_ids(UserId, GroupId, UploadId)]
 pub enum TicketDestination {
     User(UserId),
     Group(GroupId),
 }
 
+impl TicketDestination {
+    pub fn group(&self) -> Option<GroupId> {
+        match self {
+            TicketDestination::User(_) => None,
+            TicketDestination::Group(group) => Some(*group),
+        }
+    }
+
+    /// Whether the user handles the tickets addressed to this destination
+    pub async fn is_handled_by<G, H, U>(
+        &self,
+        user: UserId,
+        group_view_repository: &G,
+        group_hierarchy_view_repository: &H,
+        user_groups_view_repository: &U,
+    ) -> bool
+    where
+        G: ViewRepository<LifecycleViewState<GroupView>> + ?Sized,
+        H: ViewRepository<GroupHierarchyView> + ?Sized,
+        U: ViewRepository<UserGroupsView> + ?Sized,
+    {
+        match *self {
+            TicketDestination::User(dest_user) => user == dest_user,
+            // agents of the sub-groups handle the tickets addressed to their parent groups too
+            TicketDestination::Group(group) => has_role_in_subtree(
+                group_view_repository,
+                group_hierarchy_view_repository,
+                user_groups_view_repository,
+                group,
+                user,
+                GroupRole::Agent,
+            )
+            .await
+            .unwrap(),
+        }
+    }
+}
+
 impl Display for TicketDestination {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -184,12 +833,9 @@ impl Display for TicketDestination {
 #[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
 #[serde(tag = "type")]
-#[collect_ids(UserId, GroupId)]
+#[collect_ids(UserId, GroupId, UploadId)]
 pub enum TicketTimelineItemContent {
-    Message {
-        from: UserId,
-        text: String,
-    },
+    Message(TicketTimelineMessage),
     StatusChange {
         old: TicketStatus,
         new: TicketStatus,
@@ -198,11 +844,296 @@ pub enum TicketTimelineItemContent {
         old: Option<UserId>,
         new: Option<UserId>,
     },
+    PriorityChange {
+        old: TicketPriority,
+        new: TicketPriority,
+    },
+    DueDateChange {
+        old: Option<DateTime<Utc>>,
+        new: Option<DateTime<Utc>>,
+    },
+    DestinationChange {
+        old: TicketDestination,
+        new: TicketDestination,
+    },
+    InternalNote(TicketTimelineMessage),
+    MergedInto {
+        ticket: TicketId,
+    },
+    DuplicateMerged {
+        ticket: TicketId,
+    },
+    TitleChange {
+        old: String,
+        new: String,
+    },
+    /// The ticket waits for its owner, and will be closed if they do not reply in time
+    InactivityWarning {
+        close_at: DateTime<Utc>,
+    },
+    ClosedForInactivity,
+    Snoozed {
+        until: DateTime<Utc>,
+        performer: UserId,
+    },
+    WokenUp,
+}
+
+impl TicketTimelineItemContent {
+    /// Whether the item should only be shown to the handling side of the ticket
+    pub fn is_internal(&self) -> bool {
+        // snoozing is how the handling side organizes its work, the owner is not waiting on it
+        matches!(
+            self,
+            TicketTimelineItemContent::InternalNote(_)
+                | TicketTimelineItemContent::Snoozed { .. }
+                | TicketTimelineItemContent::WokenUp
+        )
+    }
+
+    fn message_mut(&mut self, id: TicketMessageId) -> Option<&mut TicketTimelineMessage> {
+        match self {
+            TicketTimelineItemContent::Message(message)
+            | TicketTimelineItemContent::InternalNote(message)
+                if message.id == id =>
+            {
+                Some(message)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
-#[collect_ids(UserId, GroupId)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub struct TicketTimelineMessage {
+    pub id: TicketMessageId,
+    pub from: UserId,
+    pub text: String,
+    pub attachments: Vec<UploadId>,
+    /// Previous versions of the message, oldest first
+    pub revisions: Vec<TicketMessageRevision>,
+    pub redacted: bool,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub struct TicketMessageRevision {
+    pub date: DateTime<Utc>,
+    pub text: String,
+}
+
+/// Form the tickets addressed to a group are filled in with, besides the title and the first message
+#[derive(Default, Debug, Clone, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct TicketForm {
+    pub fields: Vec<TicketFormField>,
+}
+
+#[derive(Debug, Clone, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId)]
+pub struct TicketFormField {
+    pub id: String,
+    pub label: String,
+    pub required: bool,
+    pub kind: TicketFormFieldKind,
+}
+
+/// Type of a form field, along with its validation rules
+#[derive(Debug, Clone, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[serde(tag = "type")]
+#[collect_ids(UserId, GroupId)]
+pub enum TicketFormFieldKind {
+    Text {
+        min_length: Option<u32>,
+        max_length: Option<u32>,
+    },
+    Number {
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Select {
+        options: Vec<String>,
+        /// Whether more than one option can be chosen
+        multiple: bool,
+    },
+    Date {
+        allow_past: bool,
+    },
+    Upload {
+        max_files: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[serde(tag = "type", content = "value")]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub enum TicketFieldValue {
+    Text(String),
+    Number(f64),
+    Select(Vec<String>),
+    Date(DateTime<Utc>),
+    Upload(Vec<UploadId>),
+}
+
+/// A validated form field value, with the label it had when the ticket was created
+#[derive(Debug, Clone, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub struct TicketField {
+    pub id: String,
+    pub label: String,
+    pub value: TicketFieldValue,
+}
+
+impl TicketForm {
+    pub fn is_valid(&self) -> bool {
+        let ids = self
+            .fields
+            .iter()
+            .map(|field| &field.id)
+            .collect::<HashSet<_>>();
+
+        ids.len() == self.fields.len()
+            && self.fields.iter().all(|field| match &field.kind {
+                TicketFormFieldKind::Text {
+                    min_length: Some(min),
+                    max_length: Some(max),
+                } => min <= max,
+                TicketFormFieldKind::Number {
+                    min: Some(min),
+                    max: Some(max),
+                } => min <= max,
+                TicketFormFieldKind::Select { options, .. } => !options.is_empty(),
+                _ => true,
+            })
+    }
+
+    /// Checks the values against the form, returning them in the order of the form
+    pub fn validate(
+        &self,
+        mut values: IndexMap<String, TicketFieldValue>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<TicketField>, TicketError> {
+        let mut fields = Vec::new();
+
+        for field in &self.fields {
+            let invalid = |reason: String| TicketError::InvalidFieldValue {
+                field: field.id.clone(),
+                reason,
+            };
+
+            let value = values
+                .shift_remove(&field.id)
+                .filter(|value| !value.is_empty());
+            let Some(value) = value else {
+                if field.required {
+                    return Err(TicketError::MissingField {
+                        field: field.id.clone(),
+                    });
+                }
+                continue;
+            };
+
+            match (&field.kind, &value) {
+                (
+                    &TicketFormFieldKind::Text {
+                        min_length,
+                        max_length,
+                    },
+                    TicketFieldValue::Text(text),
+                ) => {
+                    let length = text.chars().count();
+                    if min_length.is_some_and(|min| length < min as usize) {
+                        return Err(invalid(format!(
+                            "must be at least {} characters long",
+                            min_length.unwrap()
+                        )));
+                    }
+                    if max_length.is_some_and(|max| length > max as usize) {
+                        return Err(invalid(format!(
+                            "must be at most {} characters long",
+                            max_length.unwrap()
+                        )));
+                    }
+                }
+                (&TicketFormFieldKind::Number { min, max }, &TicketFieldValue::Number(number)) => {
+                    if !number.is_finite() {
+                        return Err(invalid("must be a finite number".to_string()));
+                    }
+                    if min.is_some_and(|min| number < min) {
+                        return Err(invalid(format!("must be at least {}", min.unwrap())));
+                    }
+                    if max.is_some_and(|max| number > max) {
+                        return Err(invalid(format!("must be at most {}", max.unwrap())));
+                    }
+                }
+                (
+                    TicketFormFieldKind::Select { options, multiple },
+                    TicketFieldValue::Select(chosen),
+                ) => {
+                    if !multiple && chosen.len() > 1 {
+                        return Err(invalid("only one option can be chosen".to_string()));
+                    }
+                    if let Some(option) = chosen.iter().find(|option| !options.contains(option)) {
+                        return Err(invalid(format!("`{}` is not one of the options", option)));
+                    }
+                }
+                (&TicketFormFieldKind::Date { allow_past }, &TicketFieldValue::Date(date)) => {
+                    if !allow_past && date < now {
+                        return Err(invalid("must not be in the past".to_string()));
+                    }
+                }
+                (&TicketFormFieldKind::Upload { max_files }, TicketFieldValue::Upload(uploads)) => {
+                    if max_files.is_some_and(|max| uploads.len() > max as usize) {
+                        return Err(invalid(format!(
+                            "at most {} files can be attached",
+                            max_files.unwrap()
+                        )));
+                    }
+                }
+                _ => {
+                    return Err(invalid(
+                        "the value does not match the field type".to_string(),
+                    ))
+                }
+            }
+
+            fields.push(TicketField {
+                id: field.id.clone(),
+                label: field.label.clone(),
+                value,
+            });
+        }
+
+        if let Some(field) = values.into_keys().next() {
+            return Err(TicketError::UnknownField { field });
+        }
+
+        Ok(fields)
+    }
+}
+
+impl TicketFieldValue {
+    fn is_empty(&self) -> bool {
+        match self {
+            TicketFieldValue::Text(text) => text.trim().is_empty(),
+            TicketFieldValue::Select(chosen) => chosen.is_empty(),
+            TicketFieldValue::Upload(uploads) => uploads.is_empty(),
+            TicketFieldValue::Number(_) | TicketFieldValue::Date(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
 pub struct TicketTimelineItem {
     date: DateTime<Utc>,
     content: TicketTimelineItemContent,
@@ -210,6 +1141,12 @@ pub struct TicketTimelineItem {
 
 pub struct TicketServices {
     pub group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+    pub group_hierarchy_view_repository: Arc<dyn ViewRepository<GroupHierarchyView>>,
+    pub user_groups_view_repository: Arc<dyn ViewRepository<UserGroupsView>>,
+    pub ticket_view_repository: Arc<dyn ViewRepository<LifecycleViewState<TicketView>>>,
+    pub upload_view_repository: Arc<dyn ViewRepository<UploadView>>,
+    /// The clock of the scheduler, so that the time-based commands agree with their deadlines
+    pub clock: Arc<dyn Clock>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -219,6 +1156,20 @@ pub struct Ticket {
     pub assignee: Option<UserId>,
     pub title: String,
     pub status: TicketStatus,
+    pub priority: TicketPriority,
+    pub due_date: Option<DateTime<Utc>>,
+    pub labels: IndexSet<String>,
+    pub watchers: IndexSet<UserId>,
+    pub messages: IndexMap<TicketMessageId, TicketMessageState>,
+    pub links: IndexMap<TicketId, TicketRelation>,
+    pub merged_into: Option<TicketId>,
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TicketMessageState {
+    pub author: UserId,
+    pub redacted: bool,
 }
 
 pub type TicketAggregate = LifecycleAggregateState<Ticket>;
@@ -229,15 +1180,85 @@ impl Ticket {
         user: UserId,
         services: &TicketServices,
     ) -> Result<(), TicketError> {
-        match self.destination {
-            TicketDestination::User(dest_user) => {
-                if user == dest_user {
-                    Ok(())
-                } else {
-                    error!("User does not have access to this ticket because it is not addressed to them");
-                    Err(TicketError::Forbidden)
-                }
+        let handled_by_user = self
+            .destination
+            .is_handled_by(
+                user,
+                services.group_view_repository.as_ref(),
+                services.group_hierarchy_view_repository.as_ref(),
+                services.user_groups_view_repository.as_ref(),
+            )
+            .await;
+
+        if handled_by_user {
+            Ok(())
+        } else {
+            error!("User does not have access to this ticket because they are not on its destination side");
+            Err(TicketError::Forbidden)
+        }
+    }
+
+    /// Looks up a message that can still be changed
+    fn message(&self, id: TicketMessageId) -> Result<&TicketMessageState, TicketError> {
+        let message = self.messages.get(&id).ok_or(TicketError::NoSuchMessage)?;
+        if message.redacted {
+            return Err(TicketError::MessageRedacted);
+        }
+        Ok(message)
+    }
+
+    /// Whether the user may follow the ticket: they own it or handle it
+    async fn can_watch(&self, user: UserId, services: &TicketServices) -> bool {
+        user == self.owner
+            || self
+                .destination
+                .is_handled_by(
+                    user,
+                    services.group_view_repository.as_ref(),
+                    services.group_hierarchy_view_repository.as_ref(),
+                    services.user_groups_view_repository.as_ref(),
+                )
+                .await
+    }
+
+    /// Subscribes the authors of messages to the ticket
+    fn auto_watch(&self, user: UserId, date: DateTime<Utc>, events: &mut Vec<TicketUpdated>) {
+        // the owner follows the ticket anyway
+        if user != self.owner && !self.watchers.contains(&user) {
+            events.push(TicketUpdated::WatcherAdded { date, user });
+        }
+    }
+
+    /// Checks that the user can attach the uploads: they must be finalized and belong to the user
+    async fn check_attachments(
+        user: UserId,
+        uploads: &IndexSet<UploadId>,
+        services: &TicketServices,
+    ) -> Result<(), TicketError> {
+        for upload in uploads {
+            let view = services
+                .upload_view_repository
+                .load(&upload.0.to_string())
+                .await
+                .expect("Persistence error")
+                .unwrap_or_default();
+            let UploadView::Finalized { owner, .. } = view else {
+                return Err(TicketError::AttachmentNotFinalized);
+            };
+            if owner != user {
+                return Err(TicketError::AttachmentForbidden);
             }
+        }
+        Ok(())
+    }
+
+    /// Loads the group the ticket is addressed to, if any
+    async fn load_destination_group(
+        destination: TicketDestination,
+        services: &TicketServices,
+    ) -> Result<Option<GroupView>, TicketError> {
+        match destination {
+            TicketDestination::User(_) => Ok(None),
             TicketDestination::Group(group) => {
                 let Some(group) = services
                     .group_view_repository
@@ -249,22 +1270,166 @@ impl Ticket {
                     error!("Group not found");
                     return Err(TicketError::Forbidden);
                 };
-                if group.members.contains(&user) {
-                    Ok(())
-                } else {
-                    error!("User does not have access to this ticket because they are not a member of the group it is addressed to");
-                    Err(TicketError::Forbidden)
-                }
+                Ok(Some(group))
             }
         }
     }
+
+    /// Whether the user is the destination of the ticket or an admin of the destination group
+    async fn is_destination_admin(
+        &self,
+        user: UserId,
+        services: &TicketServices,
+    ) -> Result<bool, TicketError> {
+        Ok(match self.destination {
+            TicketDestination::User(dest_user) => user == dest_user,
+            TicketDestination::Group(_) => Self::load_destination_group(self.destination, services)
+                .await?
+                .and_then(|group| group.role_of(user))
+                .is_some_and(|role| role >= GroupRole::Admin),
+        })
+    }
+
+    async fn load_workflow(
+        destination: TicketDestination,
+        services: &TicketServices,
+    ) -> Result<TicketWorkflow, TicketError> {
+        Ok(Self::load_destination_group(destination, services)
+            .await?
+            .map(|group| group.workflow)
+            .unwrap_or_default())
+    }
+
+    /// The inactivity policy of the destination group and its workflow, if the ticket waits for its owner according to it
+    async fn load_inactivity_policy(
+        &self,
+        services: &TicketServices,
+    ) -> Result<Option<(InactivityPolicy, TicketWorkflow)>, TicketError> {
+        let Some(group) = Self::load_destination_group(self.destination, services).await? else {
+            return Ok(None);
+        };
+        // the timer is stopped when the ticket stops waiting, but the group might have been changed since it was started
+        Ok(group
+            .inactivity_policy
+            .filter(|policy| policy.waiting_statuses.contains(&self.status))
+            .map(|policy| (policy, group.workflow)))
+    }
+
+    async fn warn_inactive(
+        &self,
+        close_at: DateTime<Utc>,
+        services: &TicketServices,
+        events: &mut Vec<TicketUpdated>,
+    ) -> Result<(), TicketError> {
+        if self.load_inactivity_policy(services).await?.is_some() {
+            events.push(TicketUpdated::InactivityWarning {
+                date: services.clock.now(),
+                close_at,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn close_inactive(
+        &self,
+        services: &TicketServices,
+        events: &mut Vec<TicketUpdated>,
+    ) -> Result<(), TicketError> {
+        let Some((policy, workflow)) = self.load_inactivity_policy(services).await? else {
+            return Ok(());
+        };
+        let Some(new_status) = workflow
+            .status(&policy.closed_status)
+            .filter(|status| !status.is_open())
+        else {
+            return Ok(());
+        };
+
+        let date = services.clock.now();
+        events.push(TicketUpdated::ClosedForInactivity { date });
+        events.push(TicketUpdated::StatusChanged {
+            date,
+            old_status: self.status.clone(),
+            new_status: new_status.clone(),
+        });
+
+        Ok(())
+    }
+
+    fn wake_up(&self, services: &TicketServices, events: &mut Vec<TicketUpdated>) {
+        if self.snoozed_until.is_some() {
+            events.push(TicketUpdated::WokenUp {
+                date: services.clock.now(),
+            });
+        }
+    }
+
+    fn change_assignee(&self, new_assignee: Option<UserId>, events: &mut Vec<TicketUpdated>) {
+        if self.assignee != new_assignee {
+            events.push(TicketUpdated::AssigneeChanged {
+                date: Utc::now(),
+                old_assignee: self.assignee,
+                new_assignee,
+            });
+        }
+    }
+
+    fn link(&self, other: TicketId, kind: TicketRelation, events: &mut Vec<TicketUpdated>) {
+        if self.links.get(&other) != Some(&kind) {
+            events.push(TicketUpdated::LinkAdded {
+                date: Utc::now(),
+                other,
+                kind,
+            });
+        }
+    }
+
+    fn unlink(&self, other: TicketId, events: &mut Vec<TicketUpdated>) {
+        if self.links.contains_key(&other) {
+            events.push(TicketUpdated::LinkRemoved {
+                date: Utc::now(),
+                other,
+            });
+        }
+    }
+
+    /// Loads another ticket the user wants to relate this one to
+    async fn load_other_ticket(
+        user: UserId,
+        other: TicketId,
+        services: &TicketServices,
+    ) -> Result<TicketView, TicketError> {
+        let other = services
+            .ticket_view_repository
+            .load_lifecycle(other)
+            .await
+            .unwrap()
+            .ok_or(TicketError::NoSuchTicket)?;
+
+        let handled_by_user = other
+            .destination
+            .is_handled_by(
+                user,
+                services.group_view_repository.as_ref(),
+                services.group_hierarchy_view_repository.as_ref(),
+                services.user_groups_view_repository.as_ref(),
+            )
+            .await;
+        if !handled_by_user {
+            error!("User does not have access to the other ticket because they are not on its destination side");
+            return Err(TicketError::Forbidden);
+        }
+
+        Ok(other)
+    }
 }
 
 #[async_trait]
 impl LifecycleAggregate for Ticket {
     type Id = TicketId;
     type CreateCommand = Authenticated<CreateTicket>;
-    type UpdateCommand = Authenticated<UpdateTicket>;
+    type UpdateCommand = TicketCommand;
     type DeleteCommand = ();
     type CreateEvent = TicketCreated;
     type UpdateEvent = TicketUpdated;
@@ -283,23 +1448,42 @@ impl LifecycleAggregate for Ticket {
                     title,
                     destination,
                     body,
+                    fields,
+                    attachments,
                 },
         }: Self::CreateCommand,
-        _service: &Self::Services,
+        service: &Self::Services,
     ) -> Result<(Self::CreateEvent, Vec<Self::UpdateEvent>), Self::Error> {
+        let (workflow, form) = Self::load_destination_group(destination, service)
+            .await?
+            .map(|group| (group.workflow, group.form))
+            .unwrap_or_default();
+        let fields = form.validate(fields.unwrap_or_default(), Utc::now())?;
+        let attachments = attachments.unwrap_or_default();
+
+        // the upload form fields are attachments too
+        let mut uploads = IndexSet::new();
+        fields.collect_ids(&mut uploads);
+        attachments.collect_ids(&mut uploads);
+        Self::check_attachments(user_id, &uploads, service).await?;
+
         let created = TicketCreated {
             // TODO: make this external maybe? Unit testing is hard otherwise...
             date: Utc::now(),
             destination,
             owner: user_id,
             title,
+            status: workflow.initial_status().clone(),
+            fields,
         };
 
         let mut updated = vec![TicketUpdated::Message {
             // TODO: make this external maybe? Unit testing is hard otherwise...
             date: Utc::now(),
+            id: TicketMessageId(Id::generate()),
             from: user_id,
             text: body,
+            attachments,
         }];
         if let TicketDestination::User(dest) = destination {
             updated.push(TicketUpdated::AssigneeChanged {
@@ -314,46 +1498,302 @@ impl LifecycleAggregate for Ticket {
 
     async fn handle(
         &self,
-        Authenticated {
-            user_id,
-            payload: command,
-        }: Self::UpdateCommand,
+        command: Self::UpdateCommand,
         service: &Self::Services,
     ) -> Result<Vec<Self::UpdateEvent>, Self::Error> {
         let mut events = Vec::new();
 
+        let (user_id, command) = match command {
+            TicketCommand::User(Authenticated { user_id, payload }) => (user_id, payload),
+            TicketCommand::System(command) => {
+                match command {
+                    SystemTicketCommand::ChangeAssignee(ChangeAssignee { new_assignee }) => {
+                        self.change_assignee(new_assignee, &mut events);
+                    }
+                    SystemTicketCommand::AddLink(LinkTicket { other, kind }) => {
+                        self.link(other, kind, &mut events);
+                    }
+                    SystemTicketCommand::RemoveLink(UnlinkTicket { other }) => {
+                        self.unlink(other, &mut events);
+                    }
+                    SystemTicketCommand::AbsorbDuplicate {
+                        duplicate,
+                        watchers,
+                    } => {
+                        let date = Utc::now();
+                        events.push(TicketUpdated::DuplicateMerged { date, duplicate });
+                        for watcher in watchers.into_iter().unique() {
+                            self.auto_watch(watcher, date, &mut events);
+                        }
+                    }
+                    SystemTicketCommand::WarnInactivity { close_at } => {
+                        self.warn_inactive(close_at, service, &mut events).await?;
+                    }
+                    SystemTicketCommand::CloseInactive => {
+                        self.close_inactive(service, &mut events).await?;
+                    }
+                    SystemTicketCommand::WakeUp => {
+                        self.wake_up(service, &mut events);
+                    }
+                }
+                return Ok(events);
+            }
+        };
+
         match command {
-            UpdateTicket::SendTicketMessage(SendTicketMessage { body }) => {
+            UpdateTicket::SendTicketMessage(SendTicketMessage { body, attachments }) => {
+                let attachments = attachments.unwrap_or_default();
+                Self::check_attachments(user_id, &attachments.iter().copied().collect(), service)
+                    .await?;
+
+                let date = Utc::now();
                 events.push(TicketUpdated::Message {
-                    date: Utc::now(),
+                    date,
+                    id: TicketMessageId(Id::generate()),
+                    from: user_id,
+                    text: body,
+                    attachments,
+                });
+                if self.can_watch(user_id, service).await {
+                    self.auto_watch(user_id, date, &mut events);
+                }
+                if user_id == self.owner {
+                    self.wake_up(service, &mut events);
+                }
+            }
+            UpdateTicket::SendInternalNote(SendInternalNote { body }) => {
+                self.check_access(user_id, service).await?;
+                let date = Utc::now();
+                events.push(TicketUpdated::InternalNote {
+                    date,
+                    id: TicketMessageId(Id::generate()),
                     from: user_id,
                     text: body,
                 });
+                self.auto_watch(user_id, date, &mut events);
+            }
+            UpdateTicket::EditMessage(EditMessage { message, new_text }) => {
+                let state = self.message(message)?;
+                if state.author != user_id {
+                    error!("Only the author of the message can edit it");
+                    return Err(TicketError::Forbidden);
+                }
+                events.push(TicketUpdated::MessageEdited {
+                    date: Utc::now(),
+                    message,
+                    text: new_text,
+                });
+            }
+            UpdateTicket::RedactMessage(RedactMessage { message }) => {
+                let state = self.message(message)?;
+                if state.author != user_id {
+                    let group = Self::load_destination_group(self.destination, service).await?;
+                    if group.and_then(|group| group.role_of(user_id)) < Some(GroupRole::Admin) {
+                        error!("Only the author of the message or an admin of the destination group can redact it");
+                        return Err(TicketError::Forbidden);
+                    }
+                }
+                events.push(TicketUpdated::MessageRedacted {
+                    date: Utc::now(),
+                    message,
+                    performer: user_id,
+                });
+            }
+            UpdateTicket::LinkTicket(LinkTicket { other, kind }) => {
+                self.check_access(user_id, service).await?;
+                Self::load_other_ticket(user_id, other, service).await?;
+                self.link(other, kind, &mut events);
+            }
+            UpdateTicket::UnlinkTicket(UnlinkTicket { other }) => {
+                self.check_access(user_id, service).await?;
+                self.unlink(other, &mut events);
+            }
+            UpdateTicket::MergeInto(MergeInto { primary }) => {
+                self.check_access(user_id, service).await?;
+                let primary_view = Self::load_other_ticket(user_id, primary, service).await?;
+                if self.merged_into.is_some() || primary_view.merged_into.is_some() {
+                    return Err(TicketError::AlreadyMerged);
+                }
+
+                let workflow = Self::load_workflow(self.destination, service).await?;
+                let is_open = workflow
+                    .status(&self.status)
+                    .is_none_or(TicketStatusDefinition::is_open);
+                let closed_status = if is_open {
+                    let closed_status = workflow
+                        .statuses
+                        .iter()
+                        .find(|status| !status.is_open())
+                        .ok_or(TicketError::NoClosedStatus)?;
+                    Some(closed_status.clone())
+                } else {
+                    None
+                };
+
+                let date = Utc::now();
+                self.link(primary, TicketRelation::DuplicateOf, &mut events);
+                events.push(TicketUpdated::MergedInto {
+                    date,
+                    primary,
+                    performer: user_id,
+                    watchers: std::iter::once(self.owner)
+                        .chain(self.watchers.iter().copied())
+                        .collect(),
+                });
+                for &user in &self.watchers {
+                    events.push(TicketUpdated::WatcherRemoved { date, user });
+                }
+                if let Some(closed_status) = closed_status {
+                    events.push(TicketUpdated::StatusChanged {
+                        date,
+                        old_status: self.status.clone(),
+                        new_status: closed_status,
+                    });
+                }
+            }
+            UpdateTicket::ChangeTitle(ChangeTicketTitle { new_title }) => {
+                if user_id != self.owner && !self.is_destination_admin(user_id, service).await? {
+                    error!("Only the owner of the ticket or an admin of its destination can change its title");
+                    return Err(TicketError::Forbidden);
+                }
+                if self.title != new_title {
+                    events.push(TicketUpdated::TitleChanged {
+                        date: Utc::now(),
+                        old_title: self.title.clone(),
+                        new_title,
+                    });
+                }
+            }
+            UpdateTicket::Snooze(SnoozeTicket { until }) => {
+                self.check_access(user_id, service).await?;
+                let date = service.clock.now();
+                if until <= date {
+                    return Err(TicketError::SnoozeInThePast);
+                }
+                events.push(TicketUpdated::Snoozed {
+                    date,
+                    until,
+                    performer: user_id,
+                });
+            }
+            UpdateTicket::Unsnooze => {
+                self.check_access(user_id, service).await?;
+                self.wake_up(service, &mut events);
+            }
+            UpdateTicket::Watch => {
+                if !self.can_watch(user_id, service).await {
+                    error!("Only the owner and the destination side can watch the ticket");
+                    return Err(TicketError::Forbidden);
+                }
+                if !self.watchers.contains(&user_id) {
+                    events.push(TicketUpdated::WatcherAdded {
+                        date: Utc::now(),
+                        user: user_id,
+                    });
+                }
+            }
+            UpdateTicket::Unwatch => {
+                if self.watchers.contains(&user_id) {
+                    events.push(TicketUpdated::WatcherRemoved {
+                        date: Utc::now(),
+                        user: user_id,
+                    });
+                }
             }
             UpdateTicket::ChangeStatus(ChangeStatus { new_status }) => {
                 self.check_access(user_id, service).await?;
                 if self.status != new_status {
+                    let workflow = Self::load_workflow(self.destination, service).await?;
+                    let new_status = workflow.check_transition(&self.status, &new_status)?;
                     events.push(TicketUpdated::StatusChanged {
                         date: Utc::now(),
-                        old_status: self.status,
-                        new_status,
+                        old_status: self.status.clone(),
+                        new_status: new_status.clone(),
                     })
                 }
             }
             UpdateTicket::ChangeAssignee(ChangeAssignee { new_assignee }) => {
                 self.check_access(user_id, service).await?;
-                if self.assignee != new_assignee {
-                    events.push(TicketUpdated::AssigneeChanged {
+                self.change_assignee(new_assignee, &mut events);
+            }
+            UpdateTicket::ChangePriority(ChangePriority { new_priority }) => {
+                self.check_access(user_id, service).await?;
+                if self.priority != new_priority {
+                    events.push(TicketUpdated::PriorityChanged {
                         date: Utc::now(),
-                        old_assignee: self.assignee,
-                        new_assignee,
+                        old_priority: self.priority,
+                        new_priority,
                     });
                 }
             }
-        }
+            UpdateTicket::SetDueDate(SetDueDate { due_date }) => {
+                self.check_access(user_id, service).await?;
+                if self.due_date != due_date {
+                    events.push(TicketUpdated::DueDateChanged {
+                        date: Utc::now(),
+                        old_due_date: self.due_date,
+                        new_due_date: due_date,
+                    });
+                }
+            }
+            UpdateTicket::AddLabel(AddTicketLabel { name }) => {
+                self.check_access(user_id, service).await?;
+                if !self.labels.contains(&name) {
+                    // tickets addressed to users have no label catalog
+                    let label = Self::load_destination_group(self.destination, service)
+                        .await?
+                        .and_then(|group| group.labels.into_iter().find(|l| l.name == name))
+                        .ok_or(TicketError::UnknownLabel { name })?;
+                    events.push(TicketUpdated::LabelAdded {
+                        date: Utc::now(),
+                        label,
+                    });
+                }
+            }
+            UpdateTicket::ChangeDestination(ChangeDestination { new_destination }) => {
+                // the owner can fix the destination until someone starts working on the ticket
+                let workflow = Self::load_workflow(self.destination, service).await?;
+                if user_id != self.owner || self.status != workflow.initial {
+                    self.check_access(user_id, service).await?;
+                }
 
-        Ok(events)
-    }
+                if self.destination != new_destination {
+                    let date = Utc::now();
+                    events.push(TicketUpdated::DestinationChanged {
+                        date,
+                        old_destination: self.destination,
+                        new_destination,
+                    });
+
+                    let new_assignee = match new_destination {
+                        TicketDestination::User(user) => Some(user),
+                        TicketDestination::Group(_) => None,
+                    };
+                    self.change_assignee(new_assignee, &mut events);
+
+                    let new_workflow = Self::load_workflow(new_destination, service).await?;
+                    if new_workflow.status(&self.status).is_none() {
+                        events.push(TicketUpdated::StatusChanged {
+                            date,
+                            old_status: self.status.clone(),
+                            new_status: new_workflow.initial_status().clone(),
+                        });
+                    }
+                }
+            }
+            UpdateTicket::RemoveLabel(RemoveTicketLabel { name }) => {
+                self.check_access(user_id, service).await?;
+                if self.labels.contains(&name) {
+                    events.push(TicketUpdated::LabelRemoved {
+                        date: Utc::now(),
+                        name,
+                    });
+                }
+            }
+        }
+
+        Ok(events)
+    }
 
     async fn handle_delete(
         &self,
@@ -369,6 +1809,8 @@ impl LifecycleAggregate for Ticket {
             title,
             destination,
             owner,
+            status,
+            fields: _,
         }: Self::CreateEvent,
     ) -> Self {
         Self {
@@ -376,7 +1818,15 @@ impl LifecycleAggregate for Ticket {
             owner,
             assignee: None,
             title,
-            status: TicketStatus::Pending,
+            status: status.id,
+            priority: TicketPriority::default(),
+            due_date: None,
+            labels: IndexSet::new(),
+            watchers: IndexSet::new(),
+            messages: IndexMap::new(),
+            links: IndexMap::new(),
+            merged_into: None,
+            snoozed_until: None,
         }
     }
 
@@ -384,15 +1834,89 @@ impl LifecycleAggregate for Ticket {
         match event {
             TicketUpdated::Message {
                 date: _,
-                from: _,
+                id,
+                from,
+                text: _,
+                attachments: _,
+            }
+            | TicketUpdated::InternalNote {
+                date: _,
+                id,
+                from,
                 text: _,
+            } => {
+                self.messages.insert(
+                    id,
+                    TicketMessageState {
+                        author: from,
+                        redacted: false,
+                    },
+                );
+            }
+            TicketUpdated::MessageEdited {
+                date: _,
+                message: _,
+                text: _,
+            } => {}
+            TicketUpdated::LinkAdded {
+                date: _,
+                other,
+                kind,
+            } => {
+                self.links.insert(other, kind);
+            }
+            TicketUpdated::LinkRemoved { date: _, other } => {
+                self.links.shift_remove(&other);
+            }
+            TicketUpdated::MergedInto {
+                date: _,
+                primary,
+                performer: _,
+                watchers: _,
+            } => {
+                self.merged_into = Some(primary);
+            }
+            TicketUpdated::DuplicateMerged {
+                date: _,
+                duplicate: _,
+            } => {}
+            TicketUpdated::TitleChanged {
+                date: _,
+                old_title: _,
+                new_title,
+            } => {
+                self.title = new_title;
+            }
+            TicketUpdated::InactivityWarning {
+                date: _,
+                close_at: _,
             } => {}
+            TicketUpdated::ClosedForInactivity { date: _ } => {}
+            TicketUpdated::Snoozed {
+                date: _,
+                until,
+                performer: _,
+            } => {
+                self.snoozed_until = Some(until);
+            }
+            TicketUpdated::WokenUp { date: _ } => {
+                self.snoozed_until = None;
+            }
+            TicketUpdated::MessageRedacted {
+                date: _,
+                message,
+                performer: _,
+            } => {
+                if let Some(message) = self.messages.get_mut(&message) {
+                    message.redacted = true;
+                }
+            }
             TicketUpdated::StatusChanged {
                 date: _,
                 old_status: _,
                 new_status,
             } => {
-                self.status = new_status;
+                self.status = new_status.id;
             }
             TicketUpdated::AssigneeChanged {
                 date: _,
@@ -401,13 +1925,46 @@ impl LifecycleAggregate for Ticket {
             } => {
                 self.assignee = new_assignee;
             }
+            TicketUpdated::PriorityChanged {
+                date: _,
+                old_priority: _,
+                new_priority,
+            } => {
+                self.priority = new_priority;
+            }
+            TicketUpdated::DueDateChanged {
+                date: _,
+                old_due_date: _,
+                new_due_date,
+            } => {
+                self.due_date = new_due_date;
+            }
+            TicketUpdated::LabelAdded { date: _, label } => {
+                self.labels.insert(label.name);
+            }
+            TicketUpdated::LabelRemoved { date: _, name } => {
+                self.labels.shift_remove(&name);
+            }
+            TicketUpdated::WatcherAdded { date: _, user } => {
+                self.watchers.insert(user);
+            }
+            TicketUpdated::WatcherRemoved { date: _, user } => {
+                self.watchers.shift_remove(&user);
+            }
+            TicketUpdated::DestinationChanged {
+                date: _,
+                old_destination: _,
+                new_destination,
+            } => {
+                self.destination = new_destination;
+            }
         }
     }
 }
 
 #[derive(Debug, Clone, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
-#[collect_ids(UserId, GroupId)]
+#[collect_ids(UserId, GroupId, UploadId)]
 pub struct TicketView {
     pub id: TicketId,
     pub destination: TicketDestination,
@@ -415,10 +1972,75 @@ pub struct TicketView {
     pub assignee: Option<UserId>,
     pub title: String,
     pub status: TicketStatus,
+    pub status_label: String,
+    pub status_category: StatusCategory,
+    pub priority: TicketPriority,
+    pub due_date: Option<DateTime<Utc>>,
+    pub labels: Vec<TicketLabel>,
+    pub fields: Vec<TicketField>,
+    pub watchers: Vec<UserId>,
+    pub links: Vec<TicketLink>,
+    pub merged_into: Option<TicketId>,
+    /// The ticket is hidden from the assignee listing until this date
+    pub snoozed_until: Option<DateTime<Utc>>,
     pub timeline: Vec<TicketTimelineItem>,
     pub latest_update: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, TS, Serialize, Deserialize, CollectIds)]
+#[ts(export)]
+#[collect_ids(UserId, GroupId, UploadId)]
+pub struct TicketLink {
+    pub ticket: TicketId,
+    pub kind: TicketRelation,
+}
+
+impl TicketView {
+    /// Whether the user can read the ticket: they own it or handle it
+    pub async fn is_readable_by<G, H, U>(
+        &self,
+        user: UserId,
+        group_view_repository: &G,
+        group_hierarchy_view_repository: &H,
+        user_groups_view_repository: &U,
+    ) -> bool
+    where
+        G: ViewRepository<LifecycleViewState<GroupView>> + ?Sized,
+        H: ViewRepository<GroupHierarchyView> + ?Sized,
+        U: ViewRepository<UserGroupsView> + ?Sized,
+    {
+        user == self.owner
+            || self
+                .destination
+                .is_handled_by(
+                    user,
+                    group_view_repository,
+                    group_hierarchy_view_repository,
+                    user_groups_view_repository,
+                )
+                .await
+    }
+
+    /// Removes the timeline items the owner of the ticket must not see
+    pub fn hide_internal_notes(&mut self) {
+        self.timeline.retain(|item| !item.content.is_internal());
+    }
+
+    /// Whether each of the timeline items is internal, in the timeline order
+    pub fn internal_timeline_items(&self) -> Vec<bool> {
+        self.timeline
+            .iter()
+            .map(|item| item.content.is_internal())
+            .collect()
+    }
+
+    fn message_mut(&mut self, id: TicketMessageId) -> Option<&mut TicketTimelineMessage> {
+        self.timeline
+            .iter_mut()
+            .find_map(|item| item.content.message_mut(id))
+    }
+}
+
 impl LifecycleView for TicketView {
     type Aggregate = Ticket;
     fn create(event: CreateEnvelope<'_, Self::Aggregate>) -> Self {
@@ -427,6 +2049,8 @@ impl LifecycleView for TicketView {
             destination,
             owner,
             ref title,
+            ref status,
+            ref fields,
         } = *event.payload;
 
         TicketView {
@@ -435,7 +2059,17 @@ impl LifecycleView for TicketView {
             owner,
             assignee: None,
             title: title.clone(),
-            status: TicketStatus::Pending,
+            status: status.id.clone(),
+            status_label: status.label.clone(),
+            status_category: status.category,
+            priority: TicketPriority::default(),
+            due_date: None,
+            labels: vec![],
+            fields: fields.clone(),
+            watchers: vec![],
+            links: vec![],
+            merged_into: None,
+            snoozed_until: None,
             timeline: vec![],
             latest_update: date,
         }
@@ -445,29 +2079,162 @@ impl LifecycleView for TicketView {
         match *event.payload {
             TicketUpdated::Message {
                 date,
+                id,
                 from,
                 ref text,
+                ref attachments,
             } => {
                 self.timeline.push(TicketTimelineItem {
                     date,
-                    content: TicketTimelineItemContent::Message {
+                    content: TicketTimelineItemContent::Message(TicketTimelineMessage {
+                        id,
                         from,
                         text: text.clone(),
+                        attachments: attachments.clone(),
+                        revisions: vec![],
+                        redacted: false,
+                    }),
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::InternalNote {
+                date,
+                id,
+                from,
+                ref text,
+            } => {
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::InternalNote(TicketTimelineMessage {
+                        id,
+                        from,
+                        text: text.clone(),
+                        attachments: vec![],
+                        revisions: vec![],
+                        redacted: false,
+                    }),
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::MessageEdited {
+                date,
+                message,
+                ref text,
+            } => {
+                if let Some(message) = self.message_mut(message) {
+                    let old_text = std::mem::replace(&mut message.text, text.clone());
+                    message.revisions.push(TicketMessageRevision {
+                        date,
+                        text: old_text,
+                    });
+                }
+            }
+            TicketUpdated::MessageRedacted {
+                date: _,
+                message,
+                performer: _,
+            } => {
+                if let Some(message) = self.message_mut(message) {
+                    message.text.clear();
+                    message.attachments.clear();
+                    message.revisions.clear();
+                    message.redacted = true;
+                }
+            }
+            TicketUpdated::LinkAdded {
+                date: _,
+                other,
+                kind,
+            } => match self.links.iter_mut().find(|link| link.ticket == other) {
+                Some(link) => link.kind = kind,
+                None => self.links.push(TicketLink {
+                    ticket: other,
+                    kind,
+                }),
+            },
+            TicketUpdated::LinkRemoved { date: _, other } => {
+                self.links.retain(|link| link.ticket != other);
+            }
+            TicketUpdated::MergedInto {
+                date,
+                primary,
+                performer: _,
+                watchers: _,
+            } => {
+                self.merged_into = Some(primary);
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::MergedInto { ticket: primary },
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::DuplicateMerged { date, duplicate } => {
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::DuplicateMerged { ticket: duplicate },
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::TitleChanged {
+                date,
+                ref old_title,
+                ref new_title,
+            } => {
+                self.title = new_title.clone();
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::TitleChange {
+                        old: old_title.clone(),
+                        new: new_title.clone(),
                     },
                 });
                 self.latest_update = date;
             }
+            TicketUpdated::InactivityWarning { date, close_at } => {
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::InactivityWarning { close_at },
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::ClosedForInactivity { date } => {
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::ClosedForInactivity,
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::Snoozed {
+                date,
+                until,
+                performer,
+            } => {
+                self.snoozed_until = Some(until);
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::Snoozed { until, performer },
+                });
+            }
+            TicketUpdated::WokenUp { date } => {
+                self.snoozed_until = None;
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::WokenUp,
+                });
+            }
             TicketUpdated::StatusChanged {
                 date,
-                old_status,
-                new_status,
+                ref old_status,
+                ref new_status,
             } => {
-                self.status = new_status;
+                self.status = new_status.id.clone();
+                self.status_label = new_status.label.clone();
+                self.status_category = new_status.category;
                 self.timeline.push(TicketTimelineItem {
                     date,
                     content: TicketTimelineItemContent::StatusChange {
-                        old: old_status,
-                        new: new_status,
+                        old: old_status.clone(),
+                        new: new_status.id.clone(),
                     },
                 });
                 self.latest_update = date;
@@ -487,6 +2254,67 @@ impl LifecycleView for TicketView {
                 });
                 self.latest_update = date;
             }
+            TicketUpdated::PriorityChanged {
+                date,
+                old_priority,
+                new_priority,
+            } => {
+                self.priority = new_priority;
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::PriorityChange {
+                        old: old_priority,
+                        new: new_priority,
+                    },
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::DueDateChanged {
+                date,
+                old_due_date,
+                new_due_date,
+            } => {
+                self.due_date = new_due_date;
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::DueDateChange {
+                        old: old_due_date,
+                        new: new_due_date,
+                    },
+                });
+                self.latest_update = date;
+            }
+            TicketUpdated::LabelAdded { date, ref label } => {
+                self.labels.push(label.clone());
+                self.latest_update = date;
+            }
+            TicketUpdated::LabelRemoved { date, ref name } => {
+                self.labels.retain(|label| &label.name != name);
+                self.latest_update = date;
+            }
+            TicketUpdated::WatcherAdded { date: _, user } => {
+                if !self.watchers.contains(&user) {
+                    self.watchers.push(user);
+                }
+            }
+            TicketUpdated::WatcherRemoved { date: _, user } => {
+                self.watchers.retain(|watcher| *watcher != user);
+            }
+            TicketUpdated::DestinationChanged {
+                date,
+                old_destination,
+                new_destination,
+            } => {
+                self.destination = new_destination;
+                self.timeline.push(TicketTimelineItem {
+                    date,
+                    content: TicketTimelineItemContent::DestinationChange {
+                        old: old_destination,
+                        new: new_destination,
+                    },
+                });
+                self.latest_update = date;
+            }
         }
     }
 }
@@ -501,6 +2329,13 @@ pub struct TicketListingViewExpandedItem {
     pub assignee: Option<UserId>,
     pub title: String,
     pub status: TicketStatus,
+    pub status_label: String,
+    pub status_category: StatusCategory,
+    pub priority: TicketPriority,
+    pub due_date: Option<DateTime<Utc>>,
+    pub labels: Vec<TicketLabel>,
+    pub sla: SlaStatus,
+    pub snoozed_until: Option<DateTime<Utc>>,
     pub latest_update: DateTime<Utc>,
 }
 
@@ -518,6 +2353,7 @@ pub enum TicketListingKind {
     Owned,
     Assigned,
     Destination,
+    Watched,
 }
 
 pub struct TicketListingQuery<R>
@@ -604,8 +2440,871 @@ where
                         .await
                         .expect("Persistence error");
                 }
+                (
+                    TicketListingKind::Watched,
+                    LifecycleEvent::Updated(TicketUpdated::WatcherAdded { user, .. }),
+                ) => {
+                    self.listing_view_repository
+                        .load_modify_update_default(&user.0.to_string(), |view| {
+                            view.items.insert(event.aggregate_id);
+                        })
+                        .await
+                        .expect("Persistence error");
+                }
+                (
+                    TicketListingKind::Watched,
+                    LifecycleEvent::Updated(TicketUpdated::WatcherRemoved { user, .. }),
+                ) => {
+                    self.listing_view_repository
+                        .load_modify_update_default(&user.0.to_string(), |view| {
+                            view.items.remove(&event.aggregate_id);
+                        })
+                        .await
+                        .expect("Persistence error");
+                }
+                (
+                    TicketListingKind::Destination,
+                    LifecycleEvent::Updated(TicketUpdated::DestinationChanged {
+                        old_destination,
+                        new_destination,
+                        ..
+                    }),
+                ) => {
+                    self.listing_view_repository
+                        .load_modify_update_default(&old_destination.to_string(), |view| {
+                            view.items.remove(&event.aggregate_id);
+                        })
+                        .await
+                        .expect("Persistence error");
+
+                    self.listing_view_repository
+                        .load_modify_update_default(&new_destination.to_string(), |view| {
+                            view.items.insert(event.aggregate_id);
+                        })
+                        .await
+                        .expect("Persistence error");
+                }
                 _ => {}
             }
         }
     }
 }
+
+/// Open tickets assigned to an agent, used to balance the workload between them
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AgentWorkloadView {
+    pub open_tickets: IndexSet<TicketId>,
+}
+
+impl View for AgentWorkloadView {
+    type Aggregate = TicketAggregate;
+}
+
+/// Auxiliary per-ticket state of [`AgentWorkloadQuery`]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct TicketWorkloadState {
+    pub assignee: Option<UserId>,
+    pub open: bool,
+}
+
+impl View for TicketWorkloadState {
+    type Aggregate = TicketAggregate;
+}
+
+pub struct AgentWorkloadQuery<R, S>
+where
+    R: ViewRepository<AgentWorkloadView>,
+    S: ViewRepository<TicketWorkloadState>,
+{
+    workload_view_repository: Arc<R>,
+    ticket_state_repository: Arc<S>,
+}
+
+impl<R, S> AgentWorkloadQuery<R, S>
+where
+    R: ViewRepository<AgentWorkloadView>,
+    S: ViewRepository<TicketWorkloadState>,
+{
+    pub fn new(workload_view_repository: Arc<R>, ticket_state_repository: Arc<S>) -> Self {
+        Self {
+            workload_view_repository,
+            ticket_state_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl<R, S> Query<TicketAggregate> for AgentWorkloadQuery<R, S>
+where
+    R: ViewRepository<AgentWorkloadView>,
+    S: ViewRepository<TicketWorkloadState>,
+{
+    async fn dispatch(&self, aggregate_id: TicketId, events: &[LifecycleEnvelope<Ticket>]) {
+        let aggregate_id_str = aggregate_id.0.to_string();
+
+        let (mut state, context) = self
+            .ticket_state_repository
+            .load_with_context(&aggregate_id_str)
+            .await
+            .expect("Persistence error")
+            .unwrap_or_else(|| {
+                (
+                    TicketWorkloadState::default(),
+                    ViewContext::new(aggregate_id_str),
+                )
+            });
+        let old_state = state.clone();
+
+        for event in events {
+            match &event.payload {
+                LifecycleEvent::Created(TicketCreated { status, .. }) => {
+                    state.open = status.is_open();
+                }
+                LifecycleEvent::Updated(TicketUpdated::StatusChanged { new_status, .. }) => {
+                    state.open = new_status.is_open();
+                }
+                LifecycleEvent::Updated(TicketUpdated::AssigneeChanged {
+                    new_assignee, ..
+                }) => {
+                    state.assignee = *new_assignee;
+                }
+                _ => {}
+            }
+        }
+
+        if let (Some(assignee), true) = (old_state.assignee, old_state.open) {
+            self.workload_view_repository
+                .load_modify_update_default(&assignee.0.to_string(), |view| {
+                    view.open_tickets.shift_remove(&aggregate_id);
+                })
+                .await
+                .expect("Persistence error");
+        }
+        if let (Some(assignee), true) = (state.assignee, state.open) {
+            self.workload_view_repository
+                .load_modify_update_default(&assignee.0.to_string(), |view| {
+                    view.open_tickets.insert(aggregate_id);
+                })
+                .await
+                .expect("Persistence error");
+        }
+
+        self.ticket_state_repository
+            .update_view(state, context)
+            .await
+            .expect("Persistence error");
+    }
+}
+
+/// The agent a group has last assigned a ticket to, used by the round-robin strategy
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct GroupAssignmentView {
+    pub last_assignee: Option<UserId>,
+}
+
+impl View for GroupAssignmentView {
+    type Aggregate = TicketAggregate;
+}
+
+/// Process manager assigning the new group tickets to the group agents, according to the group [`AssignmentStrategy`]
+pub struct TicketAssigner<ES>
+where
+    ES: EventStore<TicketAggregate>,
+{
+    group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+    workload_view_repository: Arc<dyn ViewRepository<AgentWorkloadView>>,
+    assignment_view_repository: Arc<dyn ViewRepository<GroupAssignmentView>>,
+    sender: CommandSender<TicketAggregate, ES>,
+}
+
+impl<ES> TicketAssigner<ES>
+where
+    ES: EventStore<TicketAggregate>,
+{
+    pub fn new(
+        group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+        workload_view_repository: Arc<dyn ViewRepository<AgentWorkloadView>>,
+        assignment_view_repository: Arc<dyn ViewRepository<GroupAssignmentView>>,
+        sender: CommandSender<TicketAggregate, ES>,
+    ) -> Self {
+        Self {
+            group_view_repository,
+            workload_view_repository,
+            assignment_view_repository,
+            sender,
+        }
+    }
+
+    async fn choose_assignee(&self, group: &GroupView) -> Option<UserId> {
+        let agents = group.agents().collect::<Vec<_>>();
+        if agents.is_empty() {
+            return None;
+        }
+
+        match group.assignment_strategy {
+            AssignmentStrategy::Manual => None,
+            AssignmentStrategy::RoundRobin => {
+                let mut assignee = None;
+                self.assignment_view_repository
+                    .load_modify_update_default(&group.id.0.to_string(), |view| {
+                        let next = view
+                            .last_assignee
+                            .and_then(|last| agents.iter().position(|&agent| agent == last))
+                            .map_or(0, |last| (last + 1) % agents.len());
+                        view.last_assignee = Some(agents[next]);
+                        assignee = view.last_assignee;
+                    })
+                    .await
+                    .expect("Persistence error");
+                assignee
+            }
+            AssignmentStrategy::LeastOpen => {
+                let workloads =
+                    futures_util::future::join_all(agents.iter().map(|agent| async move {
+                        self.workload_view_repository
+                            .load(&agent.0.to_string())
+                            .await
+                            .expect("Persistence error")
+                            .map_or(0, |view| view.open_tickets.len())
+                    }))
+                    .await;
+
+                // on ties, prefer the agent that joined the group first
+                agents
+                    .into_iter()
+                    .zip(workloads)
+                    .min_by_key(|&(_, workload)| workload)
+                    .map(|(agent, _)| agent)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<ES> Query<TicketAggregate> for TicketAssigner<ES>
+where
+    ES: EventStore<TicketAggregate>,
+    ES::AC: Send,
+{
+    async fn dispatch(&self, aggregate_id: TicketId, events: &[LifecycleEnvelope<Ticket>]) {
+        for event in events {
+            // re-routed tickets are assigned again in their new group
+            let (LifecycleEvent::Created(TicketCreated {
+                destination: TicketDestination::Group(group_id),
+                ..
+            })
+            | LifecycleEvent::Updated(TicketUpdated::DestinationChanged {
+                new_destination: TicketDestination::Group(group_id),
+                ..
+            })) = &event.payload
+            else {
+                continue;
+            };
+
+            let Some(group) = self
+                .group_view_repository
+                .load(&group_id.0.to_string())
+                .await
+                .expect("Persistence error")
+                .and_then(|v| v.into_created())
+            else {
+                error!("Group not found");
+                continue;
+            };
+
+            let Some(assignee) = self.choose_assignee(&group).await else {
+                continue;
+            };
+
+            if let Err(e) = self
+                .sender
+                .send(
+                    aggregate_id,
+                    LifecycleCommand::Update(TicketCommand::System(
+                        SystemTicketCommand::ChangeAssignee(ChangeAssignee {
+                            new_assignee: Some(assignee),
+                        }),
+                    )),
+                )
+                .await
+            {
+                error!("Failed to assign the ticket: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Keeps the links between tickets symmetric and moves the watchers of merged tickets
+pub struct TicketRelationManager<ES>
+where
+    ES: EventStore<TicketAggregate>,
+{
+    sender: CommandSender<TicketAggregate, ES>,
+}
+
+impl<ES> TicketRelationManager<ES>
+where
+    ES: EventStore<TicketAggregate>,
+{
+    pub fn new(sender: CommandSender<TicketAggregate, ES>) -> Self {
+        Self { sender }
+    }
+}
+
+#[async_trait]
+impl<ES> Query<TicketAggregate> for TicketRelationManager<ES>
+where
+    ES: EventStore<TicketAggregate>,
+    ES::AC: Send,
+{
+    async fn dispatch(&self, aggregate_id: TicketId, events: &[LifecycleEnvelope<Ticket>]) {
+        for event in events {
+            // mirroring a link that is already there is a no-op, so this does not loop
+            let (other, command) = match &event.payload {
+                LifecycleEvent::Updated(TicketUpdated::LinkAdded { other, kind, .. }) => (
+                    *other,
+                    SystemTicketCommand::AddLink(LinkTicket {
+                        other: aggregate_id,
+                        kind: kind.inverse(),
+                    }),
+                ),
+                LifecycleEvent::Updated(TicketUpdated::LinkRemoved { other, .. }) => (
+                    *other,
+                    SystemTicketCommand::RemoveLink(UnlinkTicket {
+                        other: aggregate_id,
+                    }),
+                ),
+                LifecycleEvent::Updated(TicketUpdated::MergedInto {
+                    primary, watchers, ..
+                }) => (
+                    *primary,
+                    SystemTicketCommand::AbsorbDuplicate {
+                        duplicate: aggregate_id,
+                        watchers: watchers.clone(),
+                    },
+                ),
+                _ => continue,
+            };
+
+            if let Err(e) = self
+                .sender
+                .send(
+                    other,
+                    LifecycleCommand::Update(TicketCommand::System(command)),
+                )
+                .await
+            {
+                error!("Failed to update the related ticket: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Auxiliary per-ticket state of [`TicketInactivityManager`]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct TicketInactivityState {
+    pub owner: Option<UserId>,
+    pub group: Option<GroupId>,
+    pub status: Option<TicketStatus>,
+}
+
+impl View for TicketInactivityState {
+    type Aggregate = TicketAggregate;
+}
+
+/// Process manager warning the owners of the tickets that wait for their reply, and closing the abandoned ones according to the group [`InactivityPolicy`]
+pub struct TicketInactivityManager {
+    group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+    ticket_state_repository: Arc<dyn ViewRepository<TicketInactivityState>>,
+    scheduler: Arc<Scheduler>,
+}
+
+impl TicketInactivityManager {
+    pub fn new(
+        group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+        ticket_state_repository: Arc<dyn ViewRepository<TicketInactivityState>>,
+        scheduler: Arc<Scheduler>,
+    ) -> Self {
+        Self {
+            group_view_repository,
+            ticket_state_repository,
+            scheduler,
+        }
+    }
+
+    fn timer_key(ticket: TicketId) -> String {
+        format!("ticket-inactivity-{}", ticket.0)
+    }
+
+    /// Starts the timer over if the ticket waits for its owner, stops it otherwise
+    async fn reset_timer(&self, ticket: TicketId, state: &TicketInactivityState) {
+        let policy = match (state.group, &state.status) {
+            (Some(group), Some(status)) => self
+                .group_view_repository
+                .load(&group.0.to_string())
+                .await
+                .expect("Persistence error")
+                .and_then(|v| v.into_created())
+                .and_then(|group| group.inactivity_policy)
+                .filter(|policy| policy.waiting_statuses.contains(status)),
+            _ => None,
+        };
+
+        let Some(policy) = policy else {
+            self.scheduler.cancel(&Self::timer_key(ticket)).await;
+            return;
+        };
+
+        let now = self.scheduler.now();
+        self.scheduler
+            .schedule(
+                Self::timer_key(ticket),
+                now + Duration::days(policy.warn_after_days.into()),
+                ScheduledCommand::WarnInactiveTicket {
+                    ticket,
+                    close_at: now + Duration::days(policy.close_after_days.into()),
+                },
+            )
+            .await;
+    }
+}
+
+#[async_trait]
+impl Query<TicketAggregate> for TicketInactivityManager {
+    async fn dispatch(&self, aggregate_id: TicketId, events: &[LifecycleEnvelope<Ticket>]) {
+        let aggregate_id_str = aggregate_id.0.to_string();
+
+        let (mut state, context) = self
+            .ticket_state_repository
+            .load_with_context(&aggregate_id_str)
+            .await
+            .expect("Persistence error")
+            .unwrap_or_else(|| {
+                (
+                    TicketInactivityState::default(),
+                    ViewContext::new(aggregate_id_str),
+                )
+            });
+
+        for event in events {
+            let reset = match &event.payload {
+                LifecycleEvent::Created(TicketCreated {
+                    owner,
+                    destination,
+                    status,
+                    ..
+                }) => {
+                    state.owner = Some(*owner);
+                    state.group = destination.group();
+                    state.status = Some(status.id.clone());
+                    true
+                }
+                LifecycleEvent::Updated(TicketUpdated::StatusChanged { new_status, .. }) => {
+                    state.status = Some(new_status.id.clone());
+                    true
+                }
+                LifecycleEvent::Updated(TicketUpdated::DestinationChanged {
+                    new_destination,
+                    ..
+                }) => {
+                    state.group = new_destination.group();
+                    true
+                }
+                // any reply from the owner gives them the full time again
+                LifecycleEvent::Updated(TicketUpdated::Message { from, .. }) => {
+                    state.owner == Some(*from)
+                }
+                LifecycleEvent::Updated(TicketUpdated::InactivityWarning { close_at, .. }) => {
+                    self.scheduler
+                        .schedule(
+                            Self::timer_key(aggregate_id),
+                            *close_at,
+                            ScheduledCommand::CloseInactiveTicket {
+                                ticket: aggregate_id,
+                            },
+                        )
+                        .await;
+                    false
+                }
+                LifecycleEvent::Deleted => {
+                    self.scheduler.cancel(&Self::timer_key(aggregate_id)).await;
+                    false
+                }
+                _ => false,
+            };
+
+            if reset {
+                self.reset_timer(aggregate_id, &state).await;
+            }
+        }
+
+        self.ticket_state_repository
+            .update_view(state, context)
+            .await
+            .expect("Persistence error");
+    }
+}
+
+/// Tickets referring to an upload, used to authorize its downloads and to keep it from being discarded
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct UploadReferencesView {
+    pub tickets: IndexSet<TicketId>,
+}
+
+impl View for UploadReferencesView {
+    type Aggregate = TicketAggregate;
+}
+
+/// Auxiliary per-ticket state of [`UploadReferencesQuery`]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct TicketUploadsState {
+    pub fields: IndexSet<UploadId>,
+    pub messages: IndexMap<TicketMessageId, IndexSet<UploadId>>,
+}
+
+impl View for TicketUploadsState {
+    type Aggregate = TicketAggregate;
+}
+
+impl TicketUploadsState {
+    fn uploads(&self) -> IndexSet<UploadId> {
+        self.fields
+            .iter()
+            .chain(self.messages.values().flatten())
+            .copied()
+            .collect()
+    }
+}
+
+/// Also schedules the discard of the uploads no ticket refers to anymore, and cancels it when a ticket starts referring to them again
+pub struct UploadReferencesQuery<R, S>
+where
+    R: ViewRepository<UploadReferencesView>,
+    S: ViewRepository<TicketUploadsState>,
+{
+    view_repository: Arc<R>,
+    ticket_state_repository: Arc<S>,
+    upload_view_repository: Arc<dyn ViewRepository<UploadView>>,
+    scheduler: Arc<Scheduler>,
+}
+
+impl<R, S> UploadReferencesQuery<R, S>
+where
+    R: ViewRepository<UploadReferencesView>,
+    S: ViewRepository<TicketUploadsState>,
+{
+    pub fn new(
+        view_repository: Arc<R>,
+        ticket_state_repository: Arc<S>,
+        upload_view_repository: Arc<dyn ViewRepository<UploadView>>,
+        scheduler: Arc<Scheduler>,
+    ) -> Self {
+        Self {
+            view_repository,
+            ticket_state_repository,
+            upload_view_repository,
+            scheduler,
+        }
+    }
+
+    async fn schedule_discard(&self, upload: UploadId) {
+        let Some(UploadView::Finalized { owner, .. }) = self
+            .upload_view_repository
+            .load(&upload.0.to_string())
+            .await
+            .expect("Persistence error")
+        else {
+            error!(
+                "Upload {} is not finalized in its view, not scheduling its discard",
+                upload.0
+            );
+            return;
+        };
+
+        self.scheduler
+            .schedule(
+                discard_key(upload),
+                self.scheduler.now(),
+                ScheduledCommand::DiscardUpload { upload, owner },
+            )
+            .await
+    }
+}
+
+#[async_trait]
+impl<R, S> Query<TicketAggregate> for UploadReferencesQuery<R, S>
+where
+    R: ViewRepository<UploadReferencesView>,
+    S: ViewRepository<TicketUploadsState>,
+{
+    async fn dispatch(&self, aggregate_id: TicketId, events: &[LifecycleEnvelope<Ticket>]) {
+        let aggregate_id_str = aggregate_id.0.to_string();
+
+        let (mut state, context) = self
+            .ticket_state_repository
+            .load_with_context(&aggregate_id_str)
+            .await
+            .expect("Persistence error")
+            .unwrap_or_else(|| {
+                (
+                    TicketUploadsState::default(),
+                    ViewContext::new(aggregate_id_str),
+                )
+            });
+        let old_uploads = state.uploads();
+
+        for event in events {
+            match &event.payload {
+                LifecycleEvent::Created(TicketCreated { fields, .. }) => {
+                    fields.collect_ids(&mut state.fields);
+                }
+                LifecycleEvent::Updated(TicketUpdated::Message {
+                    id, attachments, ..
+                }) => {
+                    let mut uploads = IndexSet::new();
+                    attachments.collect_ids(&mut uploads);
+                    state.messages.insert(*id, uploads);
+                }
+                // the redacted messages lose their attachments
+                LifecycleEvent::Updated(TicketUpdated::MessageRedacted { message, .. }) => {
+                    state.messages.shift_remove(message);
+                }
+                _ => {}
+            }
+        }
+
+        let new_uploads = state.uploads();
+        for upload in old_uploads.difference(&new_uploads) {
+            let mut unreferenced = false;
+            self.view_repository
+                .load_modify_update_default(&upload.0.to_string(), |view| {
+                    view.tickets.shift_remove(&aggregate_id);
+                    unreferenced = view.tickets.is_empty();
+                })
+                .await
+                .expect("Persistence error");
+            if unreferenced {
+                self.schedule_discard(*upload).await;
+            }
+        }
+        for upload in new_uploads.difference(&old_uploads) {
+            self.view_repository
+                .load_modify_update_default(&upload.0.to_string(), |view| {
+                    view.tickets.insert(aggregate_id);
+                })
+                .await
+                .expect("Persistence error");
+            self.scheduler.cancel(&discard_key(*upload)).await;
+        }
+
+        self.ticket_state_repository
+            .update_view(state, context)
+            .await
+            .expect("Persistence error");
+    }
+}
+
+/// Process manager waking the snoozed tickets up when their date comes
+pub struct TicketSnoozeManager {
+    scheduler: Arc<Scheduler>,
+}
+
+impl TicketSnoozeManager {
+    pub fn new(scheduler: Arc<Scheduler>) -> Self {
+        Self { scheduler }
+    }
+
+    fn wake_up_key(ticket: TicketId) -> String {
+        format!("ticket-wake-up-{}", ticket.0)
+    }
+}
+
+#[async_trait]
+impl Query<TicketAggregate> for TicketSnoozeManager {
+    async fn dispatch(&self, aggregate_id: TicketId, events: &[LifecycleEnvelope<Ticket>]) {
+        for event in events {
+            match &event.payload {
+                // snoozing again moves the wake-up
+                LifecycleEvent::Updated(TicketUpdated::Snoozed { until, .. }) => {
+                    self.scheduler
+                        .schedule(
+                            Self::wake_up_key(aggregate_id),
+                            *until,
+                            ScheduledCommand::WakeTicket {
+                                ticket: aggregate_id,
+                            },
+                        )
+                        .await;
+                }
+                LifecycleEvent::Updated(TicketUpdated::WokenUp { .. })
+                | LifecycleEvent::Deleted => {
+                    self.scheduler
+                        .cancel(&Self::wake_up_key(aggregate_id))
+                        .await;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// SLA deadlines of a ticket
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct TicketSlaView {
+    pub created: Option<DateTime<Utc>>,
+    pub owner: Option<UserId>,
+    pub group: Option<GroupId>,
+    pub priority: TicketPriority,
+    pub due_date: Option<DateTime<Utc>>,
+    pub first_response_deadline: Option<DateTime<Utc>>,
+    /// The explicit due date takes precedence over the resolution target of the SLA policy
+    pub resolution_deadline: Option<DateTime<Utc>>,
+    pub responded: bool,
+    pub resolved: bool,
+}
+
+impl View for TicketSlaView {
+    type Aggregate = TicketAggregate;
+}
+
+impl TicketSlaView {
+    pub fn status(&self, now: DateTime<Utc>) -> SlaStatus {
+        let Some(created) = self.created else {
+            return SlaStatus::None;
+        };
+        let deadline_status = |deadline: Option<DateTime<Utc>>, met: bool| match deadline {
+            _ if met => SlaStatus::None,
+            None => SlaStatus::None,
+            Some(deadline) if now >= deadline => SlaStatus::Breached,
+            Some(deadline) if (deadline - now) * 4 < deadline - created => SlaStatus::AtRisk,
+            Some(_) => SlaStatus::OnTrack,
+        };
+
+        std::cmp::max(
+            deadline_status(self.first_response_deadline, self.responded),
+            deadline_status(self.resolution_deadline, self.resolved),
+        )
+    }
+
+    fn update_deadlines(&mut self, policy: &SlaPolicy) {
+        let Some(created) = self.created else {
+            return;
+        };
+        let targets = policy.targets(self.priority);
+        let deadline =
+            |minutes: Option<u32>| minutes.map(|m| created + chrono::Duration::minutes(m.into()));
+
+        self.first_response_deadline = deadline(targets.first_response_minutes);
+        self.resolution_deadline = self
+            .due_date
+            .or_else(|| deadline(targets.resolution_minutes));
+    }
+}
+
+pub struct TicketSlaQuery<R>
+where
+    R: ViewRepository<TicketSlaView>,
+{
+    sla_view_repository: Arc<R>,
+    group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+}
+
+impl<R> TicketSlaQuery<R>
+where
+    R: ViewRepository<TicketSlaView>,
+{
+    pub fn new(
+        sla_view_repository: Arc<R>,
+        group_view_repository: Arc<dyn ViewRepository<LifecycleViewState<GroupView>>>,
+    ) -> Self {
+        Self {
+            sla_view_repository,
+            group_view_repository,
+        }
+    }
+
+    async fn load_policy(&self, group: Option<GroupId>) -> SlaPolicy {
+        let Some(group) = group else {
+            return SlaPolicy::default();
+        };
+        self.group_view_repository
+            .load(&group.0.to_string())
+            .await
+            .expect("Persistence error")
+            .and_then(|v| v.into_created())
+            .map(|group| group.sla_policy)
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl<R> Query<TicketAggregate> for TicketSlaQuery<R>
+where
+    R: ViewRepository<TicketSlaView>,
+{
+    async fn dispatch(&self, aggregate_id: TicketId, events: &[LifecycleEnvelope<Ticket>]) {
+        let aggregate_id_str = aggregate_id.0.to_string();
+
+        let (mut view, context) = self
+            .sla_view_repository
+            .load_with_context(&aggregate_id_str)
+            .await
+            .expect("Persistence error")
+            .unwrap_or_else(|| (TicketSlaView::default(), ViewContext::new(aggregate_id_str)));
+
+        let mut deadlines_changed = false;
+        for event in events {
+            match &event.payload {
+                LifecycleEvent::Created(TicketCreated {
+                    date,
+                    destination,
+                    owner,
+                    status,
+                    ..
+                }) => {
+                    view.created = Some(*date);
+                    view.owner = Some(*owner);
+                    view.group = destination.group();
+                    view.resolved = !status.is_open();
+                    deadlines_changed = true;
+                }
+                LifecycleEvent::Updated(TicketUpdated::Message { from, .. })
+                    if view.owner != Some(*from) =>
+                {
+                    view.responded = true;
+                }
+                LifecycleEvent::Updated(TicketUpdated::StatusChanged { new_status, .. }) => {
+                    view.resolved = !new_status.is_open();
+                }
+                LifecycleEvent::Updated(TicketUpdated::PriorityChanged {
+                    new_priority, ..
+                }) => {
+                    view.priority = *new_priority;
+                    deadlines_changed = true;
+                }
+                LifecycleEvent::Updated(TicketUpdated::DueDateChanged { new_due_date, .. }) => {
+                    view.due_date = *new_due_date;
+                    deadlines_changed = true;
+                }
+                LifecycleEvent::Updated(TicketUpdated::DestinationChanged {
+                    new_destination,
+                    ..
+                }) => {
+                    view.group = new_destination.group();
+                    deadlines_changed = true;
+                }
+                _ => {}
+            }
+        }
+
+        if deadlines_changed {
+            let policy = self.load_policy(view.group).await;
+            view.update_deadlines(&policy);
+        }
+
+        self.sla_view_repository
+            .update_view(view, context)
+            .await
+            .expect("Persistence error");
+    }
+}
@@ -1,8 +1,16 @@
 use crate::auth::Authenticated;
+use crate::domain::ticket::UploadReferencesView;
 use crate::domain::user::UserId;
 use crate::error::ApiError;
+use crate::process_manager::SendCommand;
 use crate::related_data::CollectIds;
-use crate::services::upload::{PolicyViolation, UploadMetadata, UploadService};
+use crate::scheduler::{ScheduledCommand, Scheduler};
+use crate::services::storage::StorageError;
+use crate::services::upload::{
+    PolicyViolation, UploadMetadata, UploadService, UploadThumbnail, UPLOAD_CLAIM_WINDOW,
+    UPLOAD_EXPIRATION,
+};
+use crate::view_repositry_ext::ViewRepositoryExt;
 use async_trait::async_trait;
 use cqrs_es::persist::{ViewContext, ViewRepository};
 use cqrs_es::{Aggregate, AnyId, DomainEvent, EventEnvelope, Id, Query, View};
@@ -11,6 +19,7 @@ use indexmap::IndexSet;
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
 use std::sync::Arc;
+use tracing::error;
 use ts_rs::TS;
 
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash, TS, Serialize, Deserialize)]
@@ -35,6 +44,12 @@ impl CollectIds<super::group::GroupId> for UploadId {
     fn collect_ids(&self, _: &mut IndexSet<super::group::GroupId>) {}
 }
 
+impl CollectIds<UploadId> for UploadId {
+    fn collect_ids(&self, target: &mut IndexSet<UploadId>) {
+        target.insert(*self);
+    }
+}
+
 pub enum UploadCommand {
     /// Start the upload process, generate a presigned URL for the client to upload the file to
     Initiate(UploadMetadata),
@@ -42,6 +57,12 @@ pub enum UploadCommand {
     Finalize,
     /// Cancel upload. This will be done automatically if the upload is not finalized within a certain time
     Drop,
+    /// Remove a finalized upload that nothing refers to. This is done automatically some time after the finalization, and once no ticket refers to the upload anymore
+    ///
+    /// Carries the id of the upload, so that the tickets referring to it can be looked up
+    Discard { upload: UploadId },
+    /// Record the thumbnail made for a finalized image
+    RecordThumbnail(UploadThumbnail),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -52,6 +73,8 @@ pub enum UploadEvent {
     },
     Finalized,
     Dropped,
+    Discarded,
+    ThumbnailMade(UploadThumbnail),
 }
 
 impl DomainEvent for UploadEvent {
@@ -60,6 +83,8 @@ impl DomainEvent for UploadEvent {
             UploadEvent::Initiated { .. } => "Initiated".to_string(),
             UploadEvent::Finalized => "Finalized".to_string(),
             UploadEvent::Dropped => "Dropped".to_string(),
+            UploadEvent::Discarded => "Discarded".to_string(),
+            UploadEvent::ThumbnailMade(_) => "ThumbnailMade".to_string(),
         }
     }
 
@@ -80,12 +105,18 @@ pub enum UploadError {
     AlreadyFinalized,
     /// The upload was already dropped
     AlreadyDropped,
+    /// The upload was already discarded
+    AlreadyDiscarded,
     /// User cannot access this upload
     Forbidden,
     /// Upload policy violated: `{violations:?}`
     PolicyViolated { violations: Vec<PolicyViolation> },
-    /// Error in the underlying S3 library
-    S3 { source: s3::error::S3Error },
+    /// Error while accessing the storage
+    Storage { source: StorageError },
+    /// Can't make a thumbnail of this file type
+    ThumbnailNotSupported,
+    /// Error while making a thumbnail
+    Thumbnail { source: image::ImageError },
 }
 
 impl ApiError for UploadError {
@@ -95,9 +126,12 @@ impl ApiError for UploadError {
             | UploadError::AlreadyInitiated
             | UploadError::AlreadyFinalized
             | UploadError::AlreadyDropped
+            | UploadError::AlreadyDiscarded
             | UploadError::PolicyViolated { .. } => StatusCode::BAD_REQUEST,
             UploadError::Forbidden => StatusCode::FORBIDDEN,
-            UploadError::S3 { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            UploadError::Storage { source } => source.status_code(),
+            UploadError::ThumbnailNotSupported => StatusCode::BAD_REQUEST,
+            UploadError::Thumbnail { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
@@ -115,6 +149,13 @@ pub enum Upload {
         owner: UserId,
     },
     Dropped,
+    Discarded,
+}
+
+pub struct UploadServices {
+    pub upload_service: Arc<UploadService>,
+    pub usage_view_repository: Arc<dyn ViewRepository<UploadUsageView>>,
+    pub references_view_repository: Arc<dyn ViewRepository<UploadReferencesView>>,
 }
 
 #[async_trait]
@@ -123,7 +164,7 @@ impl Aggregate for Upload {
     type Command = Authenticated<UploadCommand>;
     type Event = UploadEvent;
     type Error = UploadError;
-    type Services = Arc<UploadService>;
+    type Services = UploadServices;
 
     fn aggregate_type() -> String {
         "Upload".to_string()
@@ -140,9 +181,15 @@ impl Aggregate for Upload {
         let mut events = Vec::new();
         match (self, command) {
             (Upload::NotInitiated, UploadCommand::Initiate(metadata)) => {
-                // TODO: check user quota (don't care for now)
+                let usage = service
+                    .usage_view_repository
+                    .load(&performer.0.to_string())
+                    .await
+                    .expect("Persistence error")
+                    .unwrap_or_default();
                 service
-                    .validate_upload_initiation(&metadata)
+                    .upload_service
+                    .validate_upload_initiation(&metadata, &usage)
                     .map_err(|violations| UploadError::PolicyViolated { violations })?;
                 // NOTE: the presigned link generation is done OUTSIDE of the command handler
                 // there's some logic stacked on top in the route handler
@@ -163,11 +210,33 @@ impl Aggregate for Upload {
                 }
                 events.push(UploadEvent::Dropped);
             }
+            (Upload::Finalized { owner, .. }, UploadCommand::RecordThumbnail(thumbnail)) => {
+                if owner != &performer {
+                    return Err(UploadError::Forbidden);
+                }
+                events.push(UploadEvent::ThumbnailMade(thumbnail));
+            }
+            (Upload::Finalized { owner, .. }, UploadCommand::Discard { upload }) => {
+                if owner != &performer {
+                    return Err(UploadError::Forbidden);
+                }
+                // a ticket might have started referring to the upload after the discard was scheduled
+                let referenced = service
+                    .references_view_repository
+                    .load(&upload.0.to_string())
+                    .await
+                    .expect("Persistence error")
+                    .is_some_and(|references| !references.tickets.is_empty());
+                if !referenced {
+                    events.push(UploadEvent::Discarded);
+                }
+            }
             // error conditions
             (Upload::NotInitiated, _) => return Err(UploadError::NotInitiated),
             (Upload::Initiated { .. }, _) => return Err(UploadError::AlreadyInitiated),
             (Upload::Finalized { .. }, _) => return Err(UploadError::AlreadyFinalized),
             (Upload::Dropped, _) => return Err(UploadError::AlreadyDropped),
+            (Upload::Discarded, _) => return Err(UploadError::AlreadyDiscarded),
         };
 
         Ok(events)
@@ -181,9 +250,13 @@ impl Aggregate for Upload {
             (Upload::Initiated { owner, metadata }, UploadEvent::Finalized) => {
                 *self = Upload::Finalized { metadata, owner }
             }
+            (Upload::Finalized { .. }, UploadEvent::ThumbnailMade(_)) => {}
             (Upload::Initiated { .. }, UploadEvent::Dropped) => {
                 *self = Upload::Dropped;
             }
+            (Upload::Finalized { .. }, UploadEvent::Discarded) => {
+                *self = Upload::Discarded;
+            }
             _ => {
                 unreachable!()
             }
@@ -202,8 +275,19 @@ pub enum UploadView {
     Finalized {
         metadata: UploadMetadata,
         owner: UserId,
+        /// Only made for some image types, and only some time after the finalization
+        #[serde(default)]
+        thumbnail: Option<UploadThumbnail>,
+    },
+    // the removed uploads keep their metadata, so that their objects can be found and deleted
+    Dropped {
+        metadata: UploadMetadata,
+        owner: UserId,
+    },
+    Discarded {
+        metadata: UploadMetadata,
+        owner: UserId,
     },
-    Dropped,
 }
 
 impl View for UploadView {
@@ -258,15 +342,43 @@ impl<R: ViewRepository<UploadView>> Query<Upload> for UploadQuery<R> {
                         payload: UploadEvent::Finalized,
                         ..
                     },
-                ) => state = UploadView::Finalized { metadata, owner },
+                ) => {
+                    state = UploadView::Finalized {
+                        metadata,
+                        owner,
+                        thumbnail: None,
+                    }
+                }
                 (
-                    UploadView::Initiated { .. },
+                    UploadView::Initiated { metadata, owner },
                     EventEnvelope {
                         payload: UploadEvent::Dropped,
                         ..
                     },
+                ) => state = UploadView::Dropped { metadata, owner },
+                (
+                    UploadView::Finalized {
+                        metadata, owner, ..
+                    },
+                    EventEnvelope {
+                        payload: UploadEvent::Discarded,
+                        ..
+                    },
+                ) => state = UploadView::Discarded { metadata, owner },
+                (
+                    UploadView::Finalized {
+                        metadata, owner, ..
+                    },
+                    EventEnvelope {
+                        payload: UploadEvent::ThumbnailMade(thumbnail),
+                        ..
+                    },
                 ) => {
-                    state = UploadView::Dropped;
+                    state = UploadView::Finalized {
+                        metadata,
+                        owner,
+                        thumbnail: Some(thumbnail.clone()),
+                    }
                 }
                 _ => unreachable!("Invalid upload event"),
             }
@@ -278,3 +390,302 @@ impl<R: ViewRepository<UploadView>> Query<Upload> for UploadQuery<R> {
             .expect("Persistence Error");
     }
 }
+
+/// Storage used by the uploads of a user
+///
+/// The uploads that are not finalized yet are counted too, so that a user can't go over the quota by initiating many uploads at once
+#[derive(Debug, Default, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct UploadUsageView {
+    pub total_size: u64,
+    pub files: u64,
+}
+
+impl View for UploadUsageView {
+    type Aggregate = Upload;
+}
+
+pub struct UploadUsageQuery<R, S> {
+    usage_view_repository: Arc<R>,
+    upload_view_repository: Arc<S>,
+}
+
+impl<R, S> UploadUsageQuery<R, S> {
+    pub fn new(usage_view_repository: Arc<R>, upload_view_repository: Arc<S>) -> Self {
+        Self {
+            usage_view_repository,
+            upload_view_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl<R, S> Query<Upload> for UploadUsageQuery<R, S>
+where
+    R: ViewRepository<UploadUsageView>,
+    S: ViewRepository<UploadView>,
+{
+    async fn dispatch(
+        &self,
+        aggregate_id: UploadId,
+        events: &[EventEnvelope<UploadId, UploadEvent>],
+    ) {
+        for event in events {
+            match &event.payload {
+                UploadEvent::Initiated { metadata, owner } => {
+                    self.usage_view_repository
+                        .load_modify_update_default(&owner.0.to_string(), |usage| {
+                            usage.total_size += metadata.size;
+                            usage.files += 1;
+                        })
+                        .await
+                        .expect("Persistence error");
+                }
+                UploadEvent::Finalized | UploadEvent::ThumbnailMade(_) => {}
+                UploadEvent::Dropped | UploadEvent::Discarded => {
+                    let (UploadView::Dropped { metadata, owner }
+                    | UploadView::Discarded { metadata, owner }) = self
+                        .upload_view_repository
+                        .load(&aggregate_id.id().to_string())
+                        .await
+                        .expect("Persistence error")
+                        .unwrap_or_default()
+                    else {
+                        unreachable!("The upload view should be updated before the usage")
+                    };
+
+                    self.usage_view_repository
+                        .load_modify_update_default(&owner.0.to_string(), |usage| {
+                            usage.total_size = usage.total_size.saturating_sub(metadata.size);
+                            usage.files = usage.files.saturating_sub(1);
+                        })
+                        .await
+                        .expect("Persistence error");
+                }
+            }
+        }
+    }
+}
+
+/// Process manager collecting the abandoned uploads and deleting their objects from the storage
+///
+/// Uploads that are not finalized in time are dropped, and finalized uploads that are not claimed within [`UPLOAD_CLAIM_WINDOW`] are discarded.
+/// The uploads no ticket refers to anymore are discarded by [`UploadReferencesQuery`](super::ticket::UploadReferencesQuery)
+pub struct UploadCollector<R> {
+    view_repository: Arc<R>,
+    upload_service: Arc<UploadService>,
+    scheduler: Arc<Scheduler>,
+}
+
+impl<R> UploadCollector<R> {
+    pub fn new(
+        view_repository: Arc<R>,
+        upload_service: Arc<UploadService>,
+        scheduler: Arc<Scheduler>,
+    ) -> Self {
+        Self {
+            view_repository,
+            upload_service,
+            scheduler,
+        }
+    }
+
+    fn expiry_key(upload: UploadId) -> String {
+        format!("upload-expiry-{}", upload.id())
+    }
+}
+
+/// Key of the scheduled discard of an upload, shared by everything scheduling or cancelling it
+pub(crate) fn discard_key(upload: UploadId) -> String {
+    format!("upload-discard-{}", upload.id())
+}
+
+impl<R: ViewRepository<UploadView>> UploadCollector<R> {
+    async fn load_view(&self, upload: UploadId) -> UploadView {
+        self.view_repository
+            .load(&upload.id().to_string())
+            .await
+            .expect("Persistence Error")
+            .unwrap_or_default()
+    }
+
+    async fn delete_objects(&self, upload: UploadId) {
+        let (UploadView::Dropped { owner, .. } | UploadView::Discarded { owner, .. }) =
+            self.load_view(upload).await
+        else {
+            error!(
+                "Upload {} is not removed in its view, not deleting its objects",
+                upload.id()
+            );
+            return;
+        };
+
+        // the upload is already gone from the users' point of view, a failure only leaves some garbage in the storage
+        if let Err(e) = self.upload_service.delete_objects(owner, upload).await {
+            error!(
+                "Failed to delete the objects of upload {}: {}",
+                upload.id(),
+                e
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl<R: ViewRepository<UploadView>> Query<Upload> for UploadCollector<R> {
+    async fn dispatch(
+        &self,
+        aggregate_id: UploadId,
+        events: &[EventEnvelope<UploadId, UploadEvent>],
+    ) {
+        for event in events {
+            match &event.payload {
+                UploadEvent::Initiated { owner, .. } => {
+                    self.scheduler
+                        .schedule(
+                            Self::expiry_key(aggregate_id),
+                            self.scheduler.now()
+                                + chrono::Duration::from_std(UPLOAD_EXPIRATION).unwrap(),
+                            ScheduledCommand::DropUpload {
+                                upload: aggregate_id,
+                                owner: *owner,
+                            },
+                        )
+                        .await
+                }
+                UploadEvent::Finalized => {
+                    let UploadView::Finalized { owner, .. } = self.load_view(aggregate_id).await
+                    else {
+                        error!(
+                            "Upload {} is not finalized in its view, not scheduling its discard",
+                            aggregate_id.id()
+                        );
+                        continue;
+                    };
+
+                    self.scheduler.cancel(&Self::expiry_key(aggregate_id)).await;
+                    self.scheduler
+                        .schedule(
+                            discard_key(aggregate_id),
+                            self.scheduler.now()
+                                + chrono::Duration::from_std(UPLOAD_CLAIM_WINDOW).unwrap(),
+                            ScheduledCommand::DiscardUpload {
+                                upload: aggregate_id,
+                                owner,
+                            },
+                        )
+                        .await
+                }
+                UploadEvent::Dropped => {
+                    self.scheduler.cancel(&Self::expiry_key(aggregate_id)).await;
+                    // the client might have uploaded the file, but never finalized the upload
+                    self.delete_objects(aggregate_id).await;
+                }
+                UploadEvent::Discarded => {
+                    self.scheduler.cancel(&discard_key(aggregate_id)).await;
+                    self.delete_objects(aggregate_id).await;
+                }
+                UploadEvent::ThumbnailMade(_) => {}
+            }
+        }
+    }
+}
+
+/// Process manager making the thumbnails of the finalized images
+///
+/// They are made in background, so that the finalization does not wait for them
+pub struct UploadThumbnailer<R> {
+    view_repository: Arc<R>,
+    upload_service: Arc<UploadService>,
+    upload_sender: Arc<dyn SendCommand<Upload>>,
+}
+
+impl<R> UploadThumbnailer<R> {
+    pub fn new(
+        view_repository: Arc<R>,
+        upload_service: Arc<UploadService>,
+        upload_sender: Arc<dyn SendCommand<Upload>>,
+    ) -> Self {
+        Self {
+            view_repository,
+            upload_service,
+            upload_sender,
+        }
+    }
+}
+
+#[async_trait]
+impl<R: ViewRepository<UploadView>> Query<Upload> for UploadThumbnailer<R> {
+    async fn dispatch(
+        &self,
+        aggregate_id: UploadId,
+        events: &[EventEnvelope<UploadId, UploadEvent>],
+    ) {
+        for event in events {
+            let UploadEvent::Finalized = event.payload else {
+                continue;
+            };
+            let Some(UploadView::Finalized {
+                metadata, owner, ..
+            }) = self
+                .view_repository
+                .load(&aggregate_id.id().to_string())
+                .await
+                .expect("Persistence Error")
+            else {
+                error!(
+                    "Upload {} is not finalized in its view, not making its thumbnail",
+                    aggregate_id.id()
+                );
+                continue;
+            };
+            if !self.upload_service.supports_thumbnail(&metadata) {
+                continue;
+            }
+
+            let upload_service = self.upload_service.clone();
+            let upload_sender = self.upload_sender.clone();
+            tokio::spawn(async move {
+                let thumbnail = match upload_service
+                    .make_thumbnail(owner, aggregate_id, &metadata)
+                    .await
+                {
+                    Ok(thumbnail) => thumbnail,
+                    Err(e) => {
+                        error!(
+                            "Failed to make a thumbnail of upload {}: {}",
+                            aggregate_id.id(),
+                            e
+                        );
+                        return;
+                    }
+                };
+
+                if let Err(e) = upload_sender
+                    .send(
+                        aggregate_id,
+                        Authenticated {
+                            user_id: owner,
+                            payload: UploadCommand::RecordThumbnail(thumbnail),
+                        },
+                    )
+                    .await
+                {
+                    // the upload was removed while the thumbnail was being made, and the thumbnail was left behind
+                    error!(
+                        "Failed to record the thumbnail of upload {}: {:?}",
+                        aggregate_id.id(),
+                        e
+                    );
+                    if let Err(e) = upload_service.delete_thumbnail(owner, aggregate_id).await {
+                        error!(
+                            "Failed to delete the thumbnail of upload {}: {}",
+                            aggregate_id.id(),
+                            e
+                        );
+                    }
+                }
+            });
+        }
+    }
+}
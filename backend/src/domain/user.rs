@@ -1,6 +1,8 @@
 use super::group::GroupId;
+use super::upload::UploadId;
 use crate::error::ApiError;
 use crate::related_data::CollectIds;
+use crate::services::personal_data::{PersonalData, PersonalDataKey, PersonalDataVault};
 use crate::view_repositry_ext::ViewRepositoryExt;
 use async_trait::async_trait;
 use axum::http::StatusCode;
@@ -44,6 +46,10 @@ impl CollectIds<GroupId> for UserId {
     fn collect_ids(&self, _: &mut IndexSet<GroupId>) {}
 }
 
+impl CollectIds<UploadId> for UserId {
+    fn collect_ids(&self, _: &mut IndexSet<UploadId>) {}
+}
+
 #[derive(Debug, TS, Serialize, Deserialize, CollectIds)]
 #[ts(export)]
 #[collect_ids(UserId, GroupId)]
@@ -61,7 +67,8 @@ pub enum UpdateUser {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct UserCreated {
-    name: String,
+    personal_data_key: PersonalDataKey,
+    name: PersonalData<String>,
 }
 
 impl DomainEvent for UserCreated {
@@ -76,13 +83,19 @@ impl DomainEvent for UserCreated {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum UserUpdated {
-    IdentityAdded { profile: ExternalUserProfile },
+    IdentityAdded {
+        provider: IdentityProvider,
+        profile: PersonalData<ExternalUserProfile>,
+    },
+    /// Emitted when the user is deleted. The personal data key is destroyed afterwards, making the sealed data unrecoverable.
+    Erased { personal_data_key: PersonalDataKey },
 }
 
 impl DomainEvent for UserUpdated {
     fn event_type(&self) -> String {
         match self {
             UserUpdated::IdentityAdded { .. } => "IdentityAdded".to_string(),
+            UserUpdated::Erased { .. } => "Erased".to_string(),
         }
     }
 
@@ -93,8 +106,9 @@ impl DomainEvent for UserUpdated {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
-    pub name: String,
-    pub identities: UserIdentities,
+    pub personal_data_key: PersonalDataKey,
+    /// The profiles themselves are sealed in the events, so only the providers are tracked here
+    pub identity_providers: IndexSet<IdentityProvider>,
 }
 
 pub type UserAggregate = LifecycleAggregateState<User>;
@@ -120,13 +134,6 @@ impl UserIdentities {
         identities
     }
 
-    pub fn can_add_identity(&self, profile: &ExternalUserProfile) -> bool {
-        match profile {
-            ExternalUserProfile::Telegram(_) => self.telegram.is_none(),
-            ExternalUserProfile::University(_) => self.university.is_none(),
-        }
-    }
-
     /// Add a new identity to the user.
     ///
     /// NOTE: this method does not check if the identity already exists, overwriting it.
@@ -165,6 +172,12 @@ impl ApiError for UserError {
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum IdentityProvider {
+    Telegram,
+    University,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ExternalUserIdentity {
     Telegram(i64),
@@ -204,6 +217,13 @@ impl ExternalUserProfile {
         }
     }
 
+    pub fn provider(&self) -> IdentityProvider {
+        match self {
+            ExternalUserProfile::Telegram(_) => IdentityProvider::Telegram,
+            ExternalUserProfile::University(_) => IdentityProvider::University,
+        }
+    }
+
     pub fn identity(&self) -> ExternalUserIdentity {
         match self {
             ExternalUserProfile::Telegram(profile) => ExternalUserIdentity::Telegram(profile.id),
@@ -249,6 +269,7 @@ pub struct UniversityProfile {
 
 pub struct UserServices {
     pub user_identity_view_repository: Arc<dyn ViewRepository<IdentityView>>,
+    pub personal_data_vault: Arc<PersonalDataVault>,
 }
 
 #[async_trait]
@@ -268,13 +289,20 @@ impl LifecycleAggregate for User {
 
     async fn handle_create(
         CreateUser { profile }: Self::CreateCommand,
-        _service: &Self::Services,
+        service: &Self::Services,
     ) -> Result<(Self::CreateEvent, Vec<Self::UpdateEvent>), Self::Error> {
+        let vault = &service.personal_data_vault;
+        let personal_data_key = vault.create_key();
+
         Ok((
             UserCreated {
-                name: profile.name(),
+                personal_data_key,
+                name: vault.seal(personal_data_key, &profile.name()),
             },
-            vec![UserUpdated::IdentityAdded { profile }],
+            vec![UserUpdated::IdentityAdded {
+                provider: profile.provider(),
+                profile: vault.seal(personal_data_key, &profile),
+            }],
         ))
     }
 
@@ -287,7 +315,7 @@ impl LifecycleAggregate for User {
 
         match command {
             UpdateUser::AddIdentity { profile } => {
-                if !self.identities.can_add_identity(&profile) {
+                if self.identity_providers.contains(&profile.provider()) {
                     return Err(UserError::IdentityExists);
                 }
                 if service
@@ -299,7 +327,12 @@ impl LifecycleAggregate for User {
                 {
                     return Err(UserError::IdentityUsed);
                 }
-                events.push(UserUpdated::IdentityAdded { profile });
+                events.push(UserUpdated::IdentityAdded {
+                    provider: profile.provider(),
+                    profile: service
+                        .personal_data_vault
+                        .seal(self.personal_data_key, &profile),
+                });
             }
         }
 
@@ -311,20 +344,31 @@ impl LifecycleAggregate for User {
         _command: Self::DeleteCommand,
         _service: &Self::Services,
     ) -> Result<Vec<Self::UpdateEvent>, Self::Error> {
-        todo!()
+        // NOTE: the key is destroyed by the `PersonalDataShredder` query, after the event is committed
+        Ok(vec![UserUpdated::Erased {
+            personal_data_key: self.personal_data_key,
+        }])
     }
 
-    fn apply_create(UserCreated { name }: Self::CreateEvent) -> Self {
+    fn apply_create(
+        UserCreated {
+            personal_data_key,
+            name: _,
+        }: Self::CreateEvent,
+    ) -> Self {
         Self {
-            name,
-            identities: Default::default(),
+            personal_data_key,
+            identity_providers: IndexSet::new(),
         }
     }
 
     fn apply(&mut self, event: Self::UpdateEvent) {
         match event {
-            UserUpdated::IdentityAdded { profile } => {
-                self.identities.add_identity(profile);
+            UserUpdated::IdentityAdded { provider, .. } => {
+                self.identity_providers.insert(provider);
+            }
+            UserUpdated::Erased { .. } => {
+                self.identity_providers.clear();
             }
         }
     }
@@ -339,6 +383,15 @@ pub struct UserView {
 }
 
 impl UserView {
+    /// An anonymized placeholder shown in place of a deleted user
+    pub fn deleted(id: UserId) -> Self {
+        Self {
+            id,
+            name: "Deleted user".to_string(),
+            identities: Default::default(),
+        }
+    }
+
     pub fn profile(self) -> UserProfileView {
         UserProfileView {
             id: self.id,
@@ -351,7 +404,11 @@ impl LifecycleView for UserView {
     type Aggregate = User;
 
     fn create(event: CreateEnvelope<'_, Self::Aggregate>) -> Self {
-        let UserCreated { name } = event.payload;
+        let UserCreated { name, .. } = event.payload;
+        let Some(name) = name.unsealed() else {
+            warn!("User name is sealed, the view gets a placeholder");
+            return Self::deleted(event.aggregate_id);
+        };
         Self {
             id: event.aggregate_id,
             name: name.clone(),
@@ -361,8 +418,15 @@ impl LifecycleView for UserView {
 
     fn update(&mut self, event: UpdateEnvelope<'_, Self::Aggregate>) {
         match &event.payload {
-            UserUpdated::IdentityAdded { profile } => {
-                self.identities.add_identity(profile.clone());
+            UserUpdated::IdentityAdded { profile, .. } => {
+                if let Some(profile) = profile.unsealed() {
+                    self.identities.add_identity(profile.clone());
+                } else {
+                    warn!("User identity is sealed, not adding it to the view");
+                }
+            }
+            UserUpdated::Erased { .. } => {
+                *self = Self::deleted(self.id);
             }
         }
     }
@@ -385,44 +449,163 @@ impl View for IdentityView {
     type Aggregate = UserAggregate;
 }
 
-pub struct IdentityQuery<R>
+/// Keys of the [`IdentityView`]s belonging to a user, so that they can be released when the user is deleted
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct UserIdentityKeysView {
+    pub items: IndexSet<String>,
+}
+
+impl View for UserIdentityKeysView {
+    type Aggregate = UserAggregate;
+}
+
+pub struct IdentityQuery<R, KR>
 where
     R: ViewRepository<IdentityView>,
+    KR: ViewRepository<UserIdentityKeysView>,
 {
     view_repository: Arc<R>,
+    keys_view_repository: Arc<KR>,
 }
 
-impl<R> IdentityQuery<R>
+impl<R, KR> IdentityQuery<R, KR>
 where
     R: ViewRepository<IdentityView>,
+    KR: ViewRepository<UserIdentityKeysView>,
 {
-    pub fn new(view_repository: Arc<R>) -> Self {
-        Self { view_repository }
+    pub fn new(view_repository: Arc<R>, keys_view_repository: Arc<KR>) -> Self {
+        Self {
+            view_repository,
+            keys_view_repository,
+        }
     }
 }
 
 #[async_trait]
-impl<R> Query<UserAggregate> for IdentityQuery<R>
+impl<R, KR> Query<UserAggregate> for IdentityQuery<R, KR>
 where
     R: ViewRepository<IdentityView>,
+    KR: ViewRepository<UserIdentityKeysView>,
 {
     async fn dispatch(&self, user_id: UserId, events: &[LifecycleEnvelope<User>]) {
+        let user_id_str = user_id.0.to_string();
+
+        for event in events {
+            match &event.payload {
+                LifecycleEvent::Updated(UserUpdated::IdentityAdded { profile, .. }) => {
+                    let Some(profile) = profile.unsealed() else {
+                        warn!("User identity is sealed, cannot index it");
+                        continue;
+                    };
+                    let identity_id = profile.identity().to_string();
+
+                    self.view_repository
+                        .load_modify_update(
+                            &identity_id,
+                            |view| {
+                                warn!("Identity already exists, reassigning to another user");
+                                view.user_id = user_id;
+                            },
+                            || IdentityView { user_id },
+                        )
+                        .await
+                        .unwrap();
+                    self.keys_view_repository
+                        .load_modify_update_default(&user_id_str, |view| {
+                            view.items.insert(identity_id);
+                        })
+                        .await
+                        .unwrap();
+                }
+                LifecycleEvent::Updated(UserUpdated::Erased { .. }) => {
+                    let keys = self
+                        .keys_view_repository
+                        .load(&user_id_str)
+                        .await
+                        .unwrap()
+                        .unwrap_or_default();
+
+                    for identity_id in keys.items {
+                        // the identity might have been reassigned to another user
+                        let owned = self
+                            .view_repository
+                            .load(&identity_id)
+                            .await
+                            .unwrap()
+                            .is_some_and(|view| view.user_id == user_id);
+                        if owned {
+                            self.view_repository
+                                .delete_view(&identity_id)
+                                .await
+                                .unwrap();
+                        }
+                    }
+                    self.keys_view_repository
+                        .delete_view(&user_id_str)
+                        .await
+                        .unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Unseals the personal data in the events before passing them to the wrapped query.
+pub struct UnsealingQuery<Q> {
+    vault: Arc<PersonalDataVault>,
+    inner: Q,
+}
+
+impl<Q> UnsealingQuery<Q> {
+    pub fn new(vault: Arc<PersonalDataVault>, inner: Q) -> Self {
+        Self { vault, inner }
+    }
+}
+
+#[async_trait]
+impl<Q: Query<UserAggregate>> Query<UserAggregate> for UnsealingQuery<Q> {
+    async fn dispatch(&self, user_id: UserId, events: &[LifecycleEnvelope<User>]) {
+        let events = events
+            .iter()
+            .map(|event| {
+                let mut event = event.clone();
+                match &mut event.payload {
+                    LifecycleEvent::Created(UserCreated { name, .. }) => {
+                        *name = self.vault.unseal(name);
+                    }
+                    LifecycleEvent::Updated(UserUpdated::IdentityAdded { profile, .. }) => {
+                        *profile = self.vault.unseal(profile);
+                    }
+                    _ => {}
+                }
+                event
+            })
+            .collect::<Vec<_>>();
+
+        self.inner.dispatch(user_id, &events).await
+    }
+}
+
+/// Destroys the personal data key of an erased user.
+pub struct PersonalDataShredder {
+    vault: Arc<PersonalDataVault>,
+}
+
+impl PersonalDataShredder {
+    pub fn new(vault: Arc<PersonalDataVault>) -> Self {
+        Self { vault }
+    }
+}
+
+#[async_trait]
+impl Query<UserAggregate> for PersonalDataShredder {
+    async fn dispatch(&self, _user_id: UserId, events: &[LifecycleEnvelope<User>]) {
         for event in events {
-            if let LifecycleEvent::Updated(UserUpdated::IdentityAdded { profile }) = &event.payload
+            if let LifecycleEvent::Updated(UserUpdated::Erased { personal_data_key }) =
+                &event.payload
             {
-                let identity_id = profile.identity().to_string();
-
-                self.view_repository
-                    .load_modify_update(
-                        &identity_id,
-                        |view| {
-                            warn!("Identity already exists, reassigning to another user");
-                            view.user_id = user_id;
-                        },
-                        || IdentityView { user_id },
-                    )
-                    .await
-                    .unwrap();
+                self.vault.destroy_key(*personal_data_key);
             }
         }
     }
@@ -2,6 +2,7 @@ use crate::domain::group::GroupError;
 use crate::domain::ticket::TicketError;
 use crate::domain::upload::UploadError;
 use crate::domain::user::UserError;
+use crate::services::storage::StorageError;
 use axum::http::StatusCode;
 use cqrs_es::lifecycle::LifecycleError;
 use cqrs_es::AggregateError;
@@ -73,6 +74,9 @@ pub enum Error {
     /// Upload error
     #[snafu(context(false))] // implement From conversion
     Upload { source: AggregateError<UploadError> },
+    /// Error while accessing the storage of the uploads
+    #[snafu(context(false))]
+    Storage { source: StorageError },
     /// Error while manipulating a ticket
     #[snafu(context(false))] // implement From conversion
     Ticket {
@@ -113,6 +117,7 @@ impl ApiError for Error {
             Error::Auth { source } => source.status_code(),
             Error::Login { source } => source.status_code(),
             Error::Upload { source } => source.status_code(),
+            Error::Storage { source } => source.status_code(),
             Error::Ticket { source } => source.status_code(),
             Error::Group { source } => source.status_code(),
             Error::User { source } => source.status_code(),
@@ -7,8 +7,10 @@ mod extractors;
 mod init_tracing;
 mod meilisearch_view_repository;
 mod memory_view_repository;
+mod process_manager;
 mod related_data;
 mod routes;
+mod scheduler;
 mod services;
 mod state;
 mod view_repositry_ext;
@@ -1,6 +1,8 @@
+use crate::scheduler::{ScheduleRepository, ScheduledEntry};
 use async_trait::async_trait;
 use cqrs_es::persist::{PersistenceError, ViewContext, ViewRepository};
 use cqrs_es::View;
+use meilisearch_sdk::documents::DocumentsQuery;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
@@ -92,4 +94,76 @@ impl<V: View + Serialize + DeserializeOwned> ViewRepository<V> for MeilisearchVi
 
         Ok(())
     }
+
+    #[instrument(skip(self), fields(index = self.index.uid), err, ret)]
+    async fn delete_view(&self, view_id: &str) -> Result<(), PersistenceError> {
+        let task = self
+            .index
+            .delete_document(view_id)
+            .instrument(info_span!("delete_document"))
+            .await
+            .map_err(map_error)?;
+        task.wait_for_completion(&self.index.client, None, None)
+            .instrument(info_span!("wait_for_completion"))
+            .await
+            .map_err(map_error)?;
+
+        Ok(())
+    }
+}
+
+const SCHEDULE_PAGE_SIZE: usize = 1000;
+
+#[async_trait]
+impl ScheduleRepository for MeilisearchViewRepository<ScheduledEntry> {
+    #[instrument(skip(self), fields(index = self.index.uid), err)]
+    async fn load_all(&self) -> Result<Vec<ScheduledEntry>, PersistenceError> {
+        let mut entries = Vec::new();
+        loop {
+            let page = self
+                .index
+                .get_documents_with::<WithId<ScheduledEntry>>(
+                    DocumentsQuery::new(&self.index)
+                        .with_offset(entries.len())
+                        .with_limit(SCHEDULE_PAGE_SIZE),
+                )
+                .await
+                .map_err(map_error)?;
+            let count = page.results.len();
+            entries.extend(page.results.into_iter().map(WithId::data));
+            if count < SCHEDULE_PAGE_SIZE {
+                return Ok(entries);
+            }
+        }
+    }
+
+    #[instrument(skip(self), fields(index = self.index.uid), err)]
+    async fn save(&self, entry: &ScheduledEntry) -> Result<(), PersistenceError> {
+        let task = self
+            .index
+            .add_or_replace(
+                &[WithId {
+                    _view_id: entry.key.clone(),
+                    data: entry,
+                }],
+                None,
+            )
+            .await
+            .map_err(map_error)?;
+        task.wait_for_completion(&self.index.client, None, None)
+            .await
+            .map_err(map_error)?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(index = self.index.uid), err)]
+    async fn remove(&self, key: &str) -> Result<(), PersistenceError> {
+        let task = self.index.delete_document(key).await.map_err(map_error)?;
+        task.wait_for_completion(&self.index.client, None, None)
+            .await
+            .map_err(map_error)?;
+
+        Ok(())
+    }
 }
@@ -46,4 +46,9 @@ impl<V: View + Clone> ViewRepository<V> for MemViewRepository<V> {
             .insert(context.view_instance_id.clone(), (view, context));
         Ok(())
     }
+
+    async fn delete_view(&self, view_id: &str) -> Result<(), PersistenceError> {
+        self.views.write().await.remove(view_id);
+        Ok(())
+    }
 }
@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use cqrs_es::{Aggregate, AggregateError, CqrsFramework, EventStore};
+use std::sync::{Arc, OnceLock};
+
+/// Lets process managers (queries that react to events by issuing commands) send commands to a CQRS framework.
+///
+/// The framework owns its queries, so it can only be connected after it is built.
+pub struct CommandSender<A, ES>
+where
+    A: Aggregate,
+    ES: EventStore<A>,
+{
+    cqrs: Arc<OnceLock<Arc<CqrsFramework<A, ES>>>>,
+}
+
+impl<A, ES> Clone for CommandSender<A, ES>
+where
+    A: Aggregate,
+    ES: EventStore<A>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            cqrs: self.cqrs.clone(),
+        }
+    }
+}
+
+impl<A, ES> CommandSender<A, ES>
+where
+    A: Aggregate,
+    ES: EventStore<A>,
+{
+    pub fn new() -> Self {
+        Self {
+            cqrs: Arc::new(OnceLock::new()),
+        }
+    }
+
+    pub fn connect(&self, cqrs: Arc<CqrsFramework<A, ES>>) {
+        if self.cqrs.set(cqrs).is_err() {
+            panic!("BUG: command sender is already connected");
+        }
+    }
+
+    pub async fn send(
+        &self,
+        aggregate_id: A::Id,
+        command: A::Command,
+    ) -> Result<(), AggregateError<A::Error>> {
+        self.cqrs
+            .get()
+            .expect("BUG: command sender is not connected")
+            .execute(aggregate_id, command)
+            .await
+    }
+}
+
+/// Sending side of a [`CommandSender`] that does not depend on the event store, for the components that can't be generic over it
+#[async_trait]
+pub trait SendCommand<A: Aggregate>: Send + Sync {
+    async fn send(
+        &self,
+        aggregate_id: A::Id,
+        command: A::Command,
+    ) -> Result<(), AggregateError<A::Error>>;
+}
+
+#[async_trait]
+impl<A, ES> SendCommand<A> for CommandSender<A, ES>
+where
+    A: Aggregate,
+    A::Command: Send,
+    ES: EventStore<A>,
+    ES::AC: Send,
+{
+    async fn send(
+        &self,
+        aggregate_id: A::Id,
+        command: A::Command,
+    ) -> Result<(), AggregateError<A::Error>> {
+        CommandSender::send(self, aggregate_id, command).await
+    }
+}
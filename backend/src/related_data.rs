@@ -1,6 +1,8 @@
 use crate::domain::group::{GroupId, GroupProfileView, GroupView};
+use crate::domain::upload::{UploadId, UploadView};
 use crate::domain::user::{UserId, UserProfileView, UserView};
 use crate::error::{Error, PersistenceSnafu};
+use crate::services::upload::UploadMetadata;
 use crate::state::CqrsState;
 use crate::view_repositry_ext::LifecycleViewRepositoryExt;
 use async_trait::async_trait;
@@ -27,7 +29,7 @@ macro_rules! noop_impl {
     };
 }
 
-noop_impl!((), i32, i64, String);
+noop_impl!((), bool, i32, i64, u32, f64, String);
 noop_impl!(chrono::DateTime<chrono::Utc>);
 
 impl<Id: AnyId, T: CollectIds<Id>> CollectIds<Id> for Option<T> {
@@ -53,6 +55,15 @@ impl<Id: AnyId, T: CollectIds<Id>> CollectIds<Id> for IndexSet<T> {
     }
 }
 
+impl<Id: AnyId, K: CollectIds<Id>, V: CollectIds<Id>> CollectIds<Id> for IndexMap<K, V> {
+    fn collect_ids(&self, target: &mut IndexSet<Id>) {
+        for (key, value) in self {
+            key.collect_ids(target);
+            value.collect_ids(target);
+        }
+    }
+}
+
 impl<Id: AnyId, A: LifecycleAggregate> CollectIds<Id> for LifecycleCommand<A>
 where
     <A as LifecycleAggregate>::CreateCommand: CollectIds<Id>,
@@ -84,6 +95,26 @@ where
         .collect::<Result<Vec<_>, _>>()
 }
 
+/// Loads the profile of a user. Deleted users get an anonymized placeholder, so that they can still be displayed.
+pub async fn load_user_profile<R>(
+    view_repository: &R,
+    user_id: UserId,
+) -> Result<Option<UserProfileView>, Error>
+where
+    R: ViewRepository<LifecycleViewState<UserView>>,
+{
+    let view = view_repository
+        .load(&user_id.0.to_string())
+        .await
+        .context(PersistenceSnafu)?;
+
+    Ok(match view {
+        Some(LifecycleViewState::Created(user)) => Some(user.profile()),
+        Some(LifecycleViewState::Deleted) => Some(UserView::deleted(user_id).profile()),
+        Some(LifecycleViewState::NotCreated) | None => None,
+    })
+}
+
 pub async fn retrieve_users<R>(
     view_repository: &R,
     user_ids: IndexSet<UserId>,
@@ -91,11 +122,28 @@ pub async fn retrieve_users<R>(
 where
     R: ViewRepository<LifecycleViewState<UserView>>,
 {
-    Ok(load_all(view_repository, user_ids.into_iter())
-        .await?
-        .into_iter()
-        .map(|user| (user.id, user.profile()))
-        .collect())
+    futures_util::future::join_all(
+        user_ids
+            .into_iter()
+            .map(|id| load_user_profile(view_repository, id)),
+    )
+    .await
+    .into_iter()
+    .map(|v| v.and_then(|v| v.ok_or(Error::ViewRelatedItemNotFound)))
+    .map(|v| v.map(|user| (user.id, user)))
+    .collect()
+}
+
+/// Unlike [`retrieve_users`], does not accept deleted users
+pub async fn ensure_users_exist<R>(
+    view_repository: &R,
+    user_ids: IndexSet<UserId>,
+) -> Result<(), Error>
+where
+    R: ViewRepository<LifecycleViewState<UserView>>,
+{
+    load_all(view_repository, user_ids.into_iter()).await?;
+    Ok(())
 }
 
 pub async fn retrieve_groups<R>(
@@ -112,6 +160,29 @@ where
         .collect())
 }
 
+/// Loads the metadata of the finalized uploads. Other uploads can't be downloaded, so they are left out
+pub async fn retrieve_uploads<R>(
+    view_repository: &R,
+    upload_ids: IndexSet<UploadId>,
+) -> Result<IndexMap<UploadId, UploadMetadata>, Error>
+where
+    R: ViewRepository<UploadView>,
+{
+    futures_util::future::join_all(
+        upload_ids
+            .into_iter()
+            .map(|id| async move { (id, view_repository.load(&id.0.to_string()).await) }),
+    )
+    .await
+    .into_iter()
+    .filter_map(|(id, view)| match view.context(PersistenceSnafu) {
+        Ok(Some(UploadView::Finalized { metadata, .. })) => Some(Ok((id, metadata))),
+        Ok(_) => None,
+        Err(e) => Some(Err(e)),
+    })
+    .collect()
+}
+
 #[async_trait]
 pub trait ViewWithRelated: Sized {
     type View;
@@ -190,3 +261,40 @@ impl<T: CollectIds<UserId> + CollectIds<GroupId> + Send> ViewWithRelated for Wit
         })
     }
 }
+
+#[derive(Debug, TS, Serialize, Deserialize)]
+#[ts(export)]
+pub struct WithGroupsUsersAndUploads<T> {
+    pub groups: IndexMap<GroupId, GroupProfileView>,
+    pub users: IndexMap<UserId, UserProfileView>,
+    pub uploads: IndexMap<UploadId, UploadMetadata>,
+    pub payload: T,
+}
+
+#[async_trait]
+impl<T> ViewWithRelated for WithGroupsUsersAndUploads<T>
+where
+    T: CollectIds<UserId> + CollectIds<GroupId> + CollectIds<UploadId> + Send,
+{
+    type View = T;
+
+    async fn new(state: &CqrsState, payload: Self::View) -> Result<Self, Error> {
+        let mut user_ids = IndexSet::new();
+        let mut group_ids = IndexSet::new();
+        let mut upload_ids = IndexSet::new();
+        payload.collect_ids(&mut user_ids);
+        payload.collect_ids(&mut group_ids);
+        payload.collect_ids(&mut upload_ids);
+
+        let users = retrieve_users(state.user_view_repository.as_ref(), user_ids).await?;
+        let groups = retrieve_groups(state.group_view_repository.as_ref(), group_ids).await?;
+        let uploads = retrieve_uploads(state.upload_view_repository.as_ref(), upload_ids).await?;
+
+        Ok(Self {
+            users,
+            groups,
+            uploads,
+            payload,
+        })
+    }
+}
@@ -1,18 +1,108 @@
 use crate::api_result::ApiResult;
-use crate::domain::group::{GroupError, GroupId};
+use crate::domain::group::{
+    has_role_in_subtree, load_group_ancestors, load_group_subtree, CreateGroup, GroupError,
+    GroupId, GroupInvitationsView, GroupRole, GroupTreeView, InviteGroupMember, NewGroup,
+    ResolveGroupJoinRequest, RevokeGroupInvitation, UpdateGroup,
+};
 use crate::domain::ticket::{TicketDestination, TicketListingViewExpandedItem};
+use crate::domain::user::UserId;
 use crate::error::{Error, PersistenceSnafu};
-use crate::extractors::{Path, UserContext};
-use crate::related_data::WithGroupsAndUsers;
+use crate::extractors::{Json, Path, UserContext};
+use crate::related_data::{ViewWithRelated as _, WithGroups, WithGroupsAndUsers, WithUsers};
 use crate::routes::ticket;
 use crate::state::ApplicationState;
+use crate::view_repositry_ext::LifecycleViewRepositoryExt as _;
 use axum::extract::State;
-use cqrs_es::lifecycle::{LifecycleError, LifecycleViewState};
+use cqrs_es::lifecycle::{LifecycleCommand, LifecycleError};
 use cqrs_es::persist::ViewRepository;
 use cqrs_es::AggregateError;
 use snafu::ResultExt;
 use tracing::error;
 
+fn forbidden() -> Error {
+    Error::Group {
+        source: AggregateError::UserError(LifecycleError::AggregateError(GroupError::Forbidden)),
+    }
+}
+
+async fn execute_update_command(
+    state: &ApplicationState,
+    user_context: &UserContext,
+    id: GroupId,
+    command: UpdateGroup,
+) -> Result<(), Error> {
+    super::verify_command(&state.cqrs, &command).await?;
+    state
+        .cqrs
+        .group_cqrs
+        .execute(
+            id,
+            LifecycleCommand::Update(user_context.authenticated(command)),
+        )
+        .await
+        .map_err(Into::into)
+}
+
+/// Groups need to know their own id, so they cannot use the generic create command
+pub async fn create_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<GroupId>,
+    Json(command): Json<CreateGroup>,
+) -> ApiResult {
+    ApiResult::from_async_fn(move || async move {
+        super::verify_command(&state.cqrs, &command).await?;
+        state
+            .cqrs
+            .group_cqrs
+            .execute(
+                id,
+                LifecycleCommand::Create(user_context.authenticated(NewGroup {
+                    id,
+                    creation: command,
+                })),
+            )
+            .await
+            .map_err(Into::into)
+    })
+    .await
+}
+
+pub async fn tree_query(
+    State(state): State<ApplicationState>,
+    Path(id): Path<GroupId>,
+) -> ApiResult<WithGroups<GroupTreeView>> {
+    ApiResult::from_async_fn(|| async {
+        state
+            .cqrs
+            .group_view_repository
+            .load_lifecycle(id)
+            .await
+            .context(PersistenceSnafu)?
+            .ok_or(Error::NotFound)?;
+
+        let hierarchy = state.cqrs.group_hierarchy_view_repository.as_ref();
+        let mut ancestors = load_group_ancestors(hierarchy, id)
+            .await
+            .context(PersistenceSnafu)?;
+        ancestors.reverse();
+        let children = load_group_subtree(hierarchy, id)
+            .await
+            .context(PersistenceSnafu)?;
+
+        WithGroups::new(
+            &state.cqrs,
+            GroupTreeView {
+                root: id,
+                ancestors,
+                children,
+            },
+        )
+        .await
+    })
+    .await
+}
+
 pub async fn tickets_query(
     State(state): State<ApplicationState>,
     user_context: UserContext,
@@ -22,22 +112,31 @@ pub async fn tickets_query(
         let group_view = state
             .cqrs
             .group_view_repository
-            .load(&id.0.to_string())
+            .load_lifecycle(id)
             .await
             .context(PersistenceSnafu)?
-            .and_then(LifecycleViewState::into_created);
-        let group_view = group_view.ok_or(Error::NotFound)?;
-        if !group_view.members.contains(&user_context.user_id()) {
+            .ok_or(Error::NotFound)?;
+
+        // any member can see the tickets addressed to their group,
+        // but only the agents of the sub-groups can see the tickets addressed to the parent groups
+        let can_view = group_view.members.contains(&user_context.user_id())
+            || has_role_in_subtree(
+                state.cqrs.group_view_repository.as_ref(),
+                state.cqrs.group_hierarchy_view_repository.as_ref(),
+                state.cqrs.user_groups_view_repository.as_ref(),
+                id,
+                user_context.user_id(),
+                GroupRole::Agent,
+            )
+            .await
+            .context(PersistenceSnafu)?;
+        if !can_view {
             error!(
-                "User {:?} is not a member of group {:?}",
+                "User {:?} is neither a member of group {:?} nor an agent of its sub-groups",
                 user_context.user_id(),
                 id
             );
-            return Err(Error::Group {
-                source: AggregateError::UserError(LifecycleError::AggregateError(
-                    GroupError::Forbidden,
-                )),
-            });
+            return Err(forbidden());
         }
 
         let destination_id = TicketDestination::Group(id);
@@ -53,3 +152,147 @@ pub async fn tickets_query(
     })
     .await
 }
+
+pub async fn invitations_query(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<GroupId>,
+) -> ApiResult<WithUsers<GroupInvitationsView>> {
+    ApiResult::from_async_fn(|| async {
+        let group_view = state
+            .cqrs
+            .group_view_repository
+            .load_lifecycle(id)
+            .await
+            .context(PersistenceSnafu)?
+            .ok_or(Error::NotFound)?;
+        if group_view.role_of(user_context.user_id()) < Some(GroupRole::Admin) {
+            error!(
+                "User {:?} is not an admin of group {:?}",
+                user_context.user_id(),
+                id
+            );
+            return Err(forbidden());
+        }
+
+        let invitations_view = state
+            .cqrs
+            .group_invitations_view_repository
+            .load_lifecycle(id)
+            .await
+            .context(PersistenceSnafu)?
+            .ok_or(Error::NotFound)?;
+
+        WithUsers::new(&state.cqrs, invitations_view).await
+    })
+    .await
+}
+
+pub async fn invite_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<GroupId>,
+    Json(command): Json<InviteGroupMember>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(
+            &state,
+            &user_context,
+            id,
+            UpdateGroup::InviteMember(command),
+        )
+    })
+    .await
+}
+
+pub async fn revoke_invitation_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path((id, invitee)): Path<(GroupId, UserId)>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(
+            &state,
+            &user_context,
+            id,
+            UpdateGroup::RevokeInvitation(RevokeGroupInvitation { invitee }),
+        )
+    })
+    .await
+}
+
+pub async fn accept_invitation_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<GroupId>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(&state, &user_context, id, UpdateGroup::AcceptInvitation)
+    })
+    .await
+}
+
+pub async fn decline_invitation_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<GroupId>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(&state, &user_context, id, UpdateGroup::DeclineInvitation)
+    })
+    .await
+}
+
+pub async fn join_request_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<GroupId>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(&state, &user_context, id, UpdateGroup::RequestToJoin)
+    })
+    .await
+}
+
+pub async fn withdraw_join_request_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<GroupId>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(&state, &user_context, id, UpdateGroup::WithdrawJoinRequest)
+    })
+    .await
+}
+
+pub async fn approve_join_request_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path((id, requester)): Path<(GroupId, UserId)>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(
+            &state,
+            &user_context,
+            id,
+            UpdateGroup::ApproveJoinRequest(ResolveGroupJoinRequest { requester }),
+        )
+    })
+    .await
+}
+
+pub async fn reject_join_request_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path((id, requester)): Path<(GroupId, UserId)>,
+) -> ApiResult {
+    ApiResult::from_async_fn(|| {
+        execute_update_command(
+            &state,
+            &user_context,
+            id,
+            UpdateGroup::RejectJoinRequest(ResolveGroupJoinRequest { requester }),
+        )
+    })
+    .await
+}
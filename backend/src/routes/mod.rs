@@ -1,13 +1,15 @@
 mod group;
 mod login;
+mod scheduler;
 mod search;
+mod storage;
 mod ticket;
 mod upload;
 mod user;
 
 use crate::state::{ApplicationState, BattsAggregate, BattsView, CqrsState};
-use axum::extract::State;
-use axum::routing::{get, post};
+use axum::extract::{DefaultBodyLimit, State};
+use axum::routing::{delete, get, post};
 use axum::Router;
 use cqrs_es::lifecycle::{LifecycleAggregate, LifecycleCommand, LifecycleError, LifecycleView};
 use cqrs_es::AggregateError;
@@ -20,11 +22,11 @@ use crate::api_result::ApiResult;
 use crate::error::{Error, PersistenceSnafu};
 
 use crate::auth::Authenticated;
-use crate::domain::group::{Group, GroupId, GroupView};
-use crate::domain::ticket::{Ticket, TicketView};
+use crate::domain::group::{Group, GroupId, GroupView, UpdateGroup};
+use crate::domain::ticket::Ticket;
 use crate::domain::user::UserId;
 use crate::extractors::{Json, Path, UserContext};
-use crate::related_data::{CollectIds, ViewWithRelated, WithGroupsAndUsers, WithUsers};
+use crate::related_data::{CollectIds, ViewWithRelated, WithUsers};
 use crate::view_repositry_ext::LifecycleViewRepositoryExt as _;
 pub use login::LoginError;
 
@@ -66,7 +68,7 @@ where
         }
     }
 
-    super::related_data::retrieve_users(state.user_view_repository.as_ref(), user_ids)
+    super::related_data::ensure_users_exist(state.user_view_repository.as_ref(), user_ids)
         .await
         .map_err(map_error)?;
     super::related_data::retrieve_groups(state.group_view_repository.as_ref(), group_ids)
@@ -107,7 +109,8 @@ async fn generic_authenticated_update_command<A, C>(
     Json(command): Json<C>,
 ) -> ApiResult
 where
-    A: BattsAggregate<UpdateCommand = Authenticated<C>>,
+    A: BattsAggregate,
+    A::UpdateCommand: From<Authenticated<C>>,
     C: DeserializeOwned + CollectIds<UserId> + CollectIds<GroupId> + 'static,
     AggregateError<LifecycleError<A::Error>>: Into<Error>,
 {
@@ -116,7 +119,7 @@ where
         verify_command(&state.cqrs, &command).await?;
         cqrs.execute(
             id,
-            LifecycleCommand::Update(user_context.authenticated(command)),
+            LifecycleCommand::Update(user_context.authenticated(command).into()),
         )
         .await
         .map_err(Into::into)
@@ -129,24 +132,58 @@ pub fn make_api_router(config: &crate::config::Routes) -> Router<ApplicationStat
     router = router
         .route(
             "/tickets/:id",
-            get(generic_query::<WithGroupsAndUsers<TicketView>>)
+            get(ticket::ticket_query)
                 .put(generic_authenticated_create_command::<Ticket, _>)
-                .post(generic_authenticated_update_command::<Ticket, _>),
+                .post(ticket::update_command),
         )
         .route("/tickets/assigned", get(ticket::assignee_listing_query))
-        .route("/tickets/owned", get(ticket::owned_listing_query));
+        .route("/tickets/owned", get(ticket::owned_listing_query))
+        .route("/tickets/watched", get(ticket::watched_listing_query));
 
     router = router
         .route(
             "/groups/:id",
             get(generic_query::<WithUsers<GroupView>>)
-                .put(generic_authenticated_create_command::<Group, _>)
-                .post(generic_authenticated_update_command::<Group, _>),
+                .put(group::create_command)
+                .post(generic_authenticated_update_command::<Group, UpdateGroup>),
         )
-        .route("/groups/:id/tickets", get(group::tickets_query));
+        .route("/groups/:id/tree", get(group::tree_query))
+        .route("/groups/:id/tickets", get(group::tickets_query))
+        .route(
+            "/groups/:id/invitations",
+            get(group::invitations_query).post(group::invite_command),
+        )
+        .route(
+            "/groups/:id/invitations/accept",
+            post(group::accept_invitation_command),
+        )
+        .route(
+            "/groups/:id/invitations/decline",
+            post(group::decline_invitation_command),
+        )
+        .route(
+            "/groups/:id/invitations/:user_id",
+            delete(group::revoke_invitation_command),
+        )
+        .route(
+            "/groups/:id/join-requests",
+            post(group::join_request_command).delete(group::withdraw_join_request_command),
+        )
+        .route(
+            "/groups/:id/join-requests/:user_id/approve",
+            post(group::approve_join_request_command),
+        )
+        .route(
+            "/groups/:id/join-requests/:user_id/reject",
+            post(group::reject_join_request_command),
+        );
 
     router = router
-        .route("/users/me", get(user::me_query))
+        .route(
+            "/users/me",
+            get(user::me_query).delete(user::me_delete_command),
+        )
+        .route("/users/me/invitations", get(user::invitations_query))
         .route("/users/:id/profile", get(user::profile_query))
         .route("/users/:id/groups", get(user::groups_query));
 
@@ -155,9 +192,20 @@ pub fn make_api_router(config: &crate::config::Routes) -> Router<ApplicationStat
     router = router
         .route("/upload/:id/file", get(upload::get_file))
         .route("/upload/:id/file/:filename", get(upload::get_file))
+        .route("/upload/:id/thumbnail", get(upload::get_thumbnail))
         .route("/upload/initiate", post(upload::initiate))
+        .route("/upload/usage", get(upload::usage_query))
         .route("/upload/:id/finalize", post(upload::finalize));
 
+    // only serve anything when the uploads are kept in the local storage
+    router = router
+        .route(
+            "/storage/upload",
+            // the size is limited by the storage itself
+            post(storage::upload).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/storage/object", get(storage::download));
+
     router = router
         .route("/search/tickets", get(search::tickets))
         .route("/search/users", get(search::users))
@@ -171,10 +219,15 @@ pub fn make_api_router(config: &crate::config::Routes) -> Router<ApplicationStat
                 "/users/:id",
                 get(user::internal_query)
                     .put(user::internal_create_command)
-                    .post(user::internal_update_command),
+                    .post(user::internal_update_command)
+                    .delete(user::internal_delete_command),
             )
             .route("/user-identities/:id", get(user::internal_identity))
             .route("/fake-login/:id", post(login::internal_fake_login))
+            .route(
+                "/fake-clock/advance",
+                post(scheduler::internal_advance_clock),
+            )
     }
 
     router.fallback(fallback)
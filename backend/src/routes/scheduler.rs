@@ -0,0 +1,35 @@
+use crate::api_result::ApiResult;
+use crate::error::Error;
+use crate::extractors::Json;
+use crate::state::ApplicationState;
+use axum::extract::State;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::info;
+
+#[derive(Debug, Deserialize)]
+pub struct AdvanceClock {
+    #[serde(with = "humantime_serde")]
+    pub by: Duration,
+}
+
+/// Moves the fake clock forward and sends the scheduled commands that became due. Returns the new fake time
+pub async fn internal_advance_clock(
+    State(state): State<ApplicationState>,
+    Json(AdvanceClock { by }): Json<AdvanceClock>,
+) -> ApiResult<DateTime<Utc>> {
+    ApiResult::from_async_fn(|| async {
+        let Some(clock) = &state.fake_clock else {
+            return Err(Error::RouteNotFound);
+        };
+
+        let now = clock.advance(chrono::Duration::from_std(by).unwrap());
+        info!("Fake clock moved to {}", now);
+
+        state.cqrs.scheduler.run_due().await;
+
+        Ok(now)
+    })
+    .await
+}
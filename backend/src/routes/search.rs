@@ -16,6 +16,13 @@ pub struct SearchQuery {
     q: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct TicketSearchQuery {
+    q: String,
+    /// Only return the tickets with this label
+    label: Option<String>,
+}
+
 #[derive(Debug, Serialize, TS)]
 #[ts(export)]
 pub struct SearchResultItem<T> {
@@ -45,22 +52,42 @@ impl<T> SearchResults<T> {
     }
 }
 
+/// The search is not tied to a viewer, so the results must be safe to show to the owners
+fn hide_internal_notes(mut results: SearchResults<TicketView>) -> SearchResults<TicketView> {
+    for item in &mut results.top_hits {
+        // the highlighted timeline mirrors the stored one item by item
+        let mut internal = item.value.internal_timeline_items().into_iter();
+        item.value.hide_internal_notes();
+        if let Some(serde_json::Value::Array(timeline)) = item.highlights.get_mut("timeline") {
+            timeline.retain(|_| !internal.next().unwrap_or(true));
+        }
+    }
+    results
+}
+
 pub async fn tickets(
     State(state): State<ApplicationState>,
-    Query(SearchQuery { q: query }): Query<SearchQuery>,
+    Query(TicketSearchQuery { q: query, label }): Query<TicketSearchQuery>,
 ) -> ApiResult<SearchResults<TicketView>> {
     ApiResult::from_async_fn(|| async {
+        let mut filter = r#"lifecycle_state = "Created""#.to_string();
+        if let Some(label) = label {
+            let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+            filter.push_str(&format!(r#" AND labels.name = "{}""#, label));
+        }
+
         state
             .search
             .ticket_index
             .search()
             .with_query(&query)
-            .with_filter(r#"lifecycle_state = "Created""#)
+            .with_filter(&filter)
             .with_attributes_to_highlight(Selectors::All)
             .execute()
             .await
             .context(MeilisearchSnafu)
             .map(SearchResults::from_meili)
+            .map(hide_internal_notes)
     })
     .await
 }
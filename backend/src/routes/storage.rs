@@ -0,0 +1,109 @@
+use crate::api_result::ApiResult;
+use crate::error::Error;
+use crate::extractors::Query;
+use crate::services::storage::{InvalidFormSnafu, ObjectStorage as _, ObjectTooLargeSnafu};
+use crate::state::ApplicationState;
+use axum::extract::{Multipart, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use camino::Utf8PathBuf;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+pub struct SignedGet {
+    pub key: String,
+    pub filename: String,
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Accepts the forms made by the local storage, mimicking the S3 presigned posts: the fields come first, then the file
+pub async fn upload(State(state): State<ApplicationState>, mut form: Multipart) -> ApiResult {
+    ApiResult::from_async_fn(|| async move {
+        let Some(storage) = &state.local_storage else {
+            return Err(Error::RouteNotFound);
+        };
+
+        let invalid_form = |e: axum::extract::multipart::MultipartError| {
+            InvalidFormSnafu {
+                message: e.body_text(),
+            }
+            .build()
+        };
+
+        let mut fields = HashMap::new();
+        while let Some(mut field) = form.next_field().await.map_err(invalid_form)? {
+            let name = field.name().unwrap_or_default().to_string();
+            if name != "file" {
+                fields.insert(name, field.text().await.map_err(invalid_form)?);
+                continue;
+            }
+
+            let field_value = |name: &str| {
+                fields.get(name).cloned().ok_or_else(|| {
+                    InvalidFormSnafu {
+                        message: format!("missing field `{}`", name),
+                    }
+                    .build()
+                })
+            };
+            let key = Utf8PathBuf::from(field_value("key")?);
+            let content_type = field_value("Content-Type")?;
+            let expires = field_value("expires")?.parse().map_err(|_| {
+                InvalidFormSnafu {
+                    message: "invalid field `expires`",
+                }
+                .build()
+            })?;
+            storage.verify_post(&key, &content_type, expires, &field_value("signature")?)?;
+
+            let mut content = Vec::new();
+            while let Some(chunk) = field.chunk().await.map_err(invalid_form)? {
+                if (content.len() + chunk.len()) as u64 > storage.max_object_size() {
+                    return Err(ObjectTooLargeSnafu.build().into());
+                }
+                content.extend_from_slice(&chunk);
+            }
+            storage.put(&key, &content_type, &content).await?;
+
+            // like S3, ignore everything after the file
+            return Ok(());
+        }
+
+        Err(InvalidFormSnafu {
+            message: "missing field `file`",
+        }
+        .build()
+        .into())
+    })
+    .await
+}
+
+/// Serves the URLs made by the local storage
+pub async fn download(
+    State(state): State<ApplicationState>,
+    Query(query): Query<SignedGet>,
+) -> Result<Response, ApiResult> {
+    let Some(storage) = &state.local_storage else {
+        return Err(ApiResult::err(Error::RouteNotFound));
+    };
+
+    let key = Utf8PathBuf::from(query.key);
+    storage
+        .verify_get(&key, &query.filename, query.expires, &query.signature)
+        .map_err(ApiResult::err)?;
+    let (content, content_type) = storage.read(&key).await.map_err(ApiResult::err)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", query.filename),
+            ),
+        ],
+        content,
+    )
+        .into_response())
+}
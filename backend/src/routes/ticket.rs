@@ -1,34 +1,50 @@
 use crate::api_result::ApiResult;
-use crate::domain::ticket::{TicketListingView, TicketListingViewExpandedItem};
+use crate::domain::ticket::{
+    LinkTicket, MergeInto, Ticket, TicketError, TicketId, TicketListingView,
+    TicketListingViewExpandedItem, TicketView, UpdateTicket,
+};
 use crate::error::{Error, PersistenceSnafu};
-use crate::extractors::UserContext;
-use crate::related_data::{ViewWithRelated as _, WithGroupsAndUsers};
+use crate::extractors::{Json, Path, Query, UserContext};
+use crate::related_data::{ViewWithRelated as _, WithGroupsAndUsers, WithGroupsUsersAndUploads};
 use crate::state::ApplicationState;
 use crate::view_repositry_ext::LifecycleViewRepositoryExt;
 use axum::extract::State;
+use chrono::Utc;
+use cqrs_es::lifecycle::LifecycleError;
 use cqrs_es::persist::ViewRepository;
+use cqrs_es::AggregateError;
 use itertools::Itertools;
+use serde::Deserialize;
 use snafu::ResultExt;
+use std::collections::HashSet;
 
-pub async fn expand_ticket_listing_view(
-    state: ApplicationState,
+async fn expand_ticket_listing_items(
+    state: &ApplicationState,
     ticket_view: TicketListingView,
-) -> Result<WithGroupsAndUsers<Vec<TicketListingViewExpandedItem>>, Error> {
-    let results = futures_util::future::join_all(
-        ticket_view
-            .items
-            .iter()
-            .map(|id| async { state.cqrs.ticket_view_repository.load_lifecycle(*id).await }),
-    )
+) -> Result<Vec<TicketListingViewExpandedItem>, Error> {
+    let results = futures_util::future::join_all(ticket_view.items.iter().map(|id| async {
+        let view = state
+            .cqrs
+            .ticket_view_repository
+            .load_lifecycle(*id)
+            .await?;
+        let sla_view = state
+            .cqrs
+            .ticket_sla_view_repository
+            .load(&id.0.to_string())
+            .await?;
+        Ok((view, sla_view))
+    }))
     .await;
     let results = results
         .into_iter()
         .collect::<Result<Vec<_>, _>>()
         .context(PersistenceSnafu)?;
 
+    let now = Utc::now();
     let results = results
         .into_iter()
-        .map(|view| {
+        .map(|(view, sla_view)| {
             let view = view.unwrap();
             TicketListingViewExpandedItem {
                 id: view.id,
@@ -37,6 +53,13 @@ pub async fn expand_ticket_listing_view(
                 assignee: view.assignee,
                 title: view.title,
                 status: view.status,
+                status_label: view.status_label,
+                status_category: view.status_category,
+                priority: view.priority,
+                due_date: view.due_date,
+                labels: view.labels,
+                sla: sla_view.unwrap_or_default().status(now),
+                snoozed_until: view.snoozed_until,
                 latest_update: view.latest_update,
             }
         })
@@ -44,12 +67,29 @@ pub async fn expand_ticket_listing_view(
         .rev()
         .collect();
 
+    Ok(results)
+}
+
+pub async fn expand_ticket_listing_view(
+    state: ApplicationState,
+    ticket_view: TicketListingView,
+) -> Result<WithGroupsAndUsers<Vec<TicketListingViewExpandedItem>>, Error> {
+    let results = expand_ticket_listing_items(&state, ticket_view).await?;
+
     WithGroupsAndUsers::new(&state.cqrs, results).await
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AssigneeListingQuery {
+    /// Also return the snoozed tickets
+    #[serde(default)]
+    include_snoozed: bool,
+}
+
 pub async fn assignee_listing_query(
     State(state): State<ApplicationState>,
     user_context: UserContext,
+    Query(query): Query<AssigneeListingQuery>,
 ) -> ApiResult<WithGroupsAndUsers<Vec<TicketListingViewExpandedItem>>> {
     ApiResult::from_async_fn(|| async {
         let view = state
@@ -60,7 +100,12 @@ pub async fn assignee_listing_query(
             .context(PersistenceSnafu)?
             .unwrap_or_default();
 
-        expand_ticket_listing_view(state, view).await
+        let mut results = expand_ticket_listing_items(&state, view).await?;
+        if !query.include_snoozed {
+            results.retain(|item| item.snoozed_until.is_none());
+        }
+
+        WithGroupsAndUsers::new(&state.cqrs, results).await
     })
     .await
 }
@@ -82,3 +127,109 @@ pub async fn owned_listing_query(
     })
     .await
 }
+
+pub async fn watched_listing_query(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+) -> ApiResult<WithGroupsAndUsers<Vec<TicketListingViewExpandedItem>>> {
+    ApiResult::from_async_fn(|| async {
+        let user_id = user_context.user_id();
+        let mut view = state
+            .cqrs
+            .ticket_watched_listing_view_repository
+            .load(&user_id.0.to_string())
+            .await
+            .context(PersistenceSnafu)?
+            .unwrap_or_default();
+
+        // the watchers keep following a ticket they can no longer read, e.g. after it is re-routed
+        let mut readable = HashSet::new();
+        for id in view.items {
+            let Some(ticket) = state
+                .cqrs
+                .ticket_view_repository
+                .load_lifecycle(id)
+                .await
+                .context(PersistenceSnafu)?
+            else {
+                continue;
+            };
+            if ticket
+                .is_readable_by(
+                    user_id,
+                    state.cqrs.group_view_repository.as_ref(),
+                    state.cqrs.group_hierarchy_view_repository.as_ref(),
+                    state.cqrs.user_groups_view_repository.as_ref(),
+                )
+                .await
+            {
+                readable.insert(id);
+            }
+        }
+        view.items = readable;
+
+        expand_ticket_listing_view(state, view).await
+    })
+    .await
+}
+
+pub async fn ticket_query(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<TicketId>,
+) -> ApiResult<WithGroupsUsersAndUploads<TicketView>> {
+    ApiResult::from_async_fn(|| async {
+        let mut view = state
+            .cqrs
+            .ticket_view_repository
+            .load_lifecycle(id)
+            .await
+            .context(PersistenceSnafu)?
+            .ok_or(Error::NotFound)?;
+
+        let user_id = user_context.user_id();
+        let handled_by_user = view
+            .destination
+            .is_handled_by(
+                user_id,
+                state.cqrs.group_view_repository.as_ref(),
+                state.cqrs.group_hierarchy_view_repository.as_ref(),
+                state.cqrs.user_groups_view_repository.as_ref(),
+            )
+            .await;
+        // the owner never sees the internal notes, even if they handle the ticket themselves
+        if !handled_by_user || user_id == view.owner {
+            view.hide_internal_notes();
+        }
+
+        WithGroupsUsersAndUploads::new(&state.cqrs, view).await
+    })
+    .await
+}
+
+pub async fn update_command(
+    state: State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<TicketId>,
+    Json(command): Json<UpdateTicket>,
+) -> ApiResult {
+    // the aggregate does not know its own id, so it can't catch these
+    let references_itself = match &command {
+        UpdateTicket::LinkTicket(LinkTicket { other, .. }) => *other == id,
+        UpdateTicket::MergeInto(MergeInto { primary }) => *primary == id,
+        _ => false,
+    };
+    if references_itself {
+        return ApiResult::err(AggregateError::UserError(LifecycleError::AggregateError(
+            TicketError::SelfReference,
+        )));
+    }
+
+    super::generic_authenticated_update_command::<Ticket, UpdateTicket>(
+        state,
+        user_context,
+        Path(id),
+        Json(command),
+    )
+    .await
+}
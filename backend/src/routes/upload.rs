@@ -1,9 +1,11 @@
 use crate::api_result::ApiResult;
-use crate::domain::upload::{UploadCommand, UploadError, UploadId, UploadView};
+use crate::domain::upload::{UploadCommand, UploadError, UploadId, UploadUsageView, UploadView};
+use crate::domain::user::UserId;
 use crate::error::{Error, PersistenceSnafu};
 use crate::extractors::{Json, Path, UserContext};
-use crate::services::upload::UploadMetadata;
+use crate::services::upload::{UploadMetadata, UploadQuota, UploadThumbnail};
 use crate::state::ApplicationState;
+use crate::view_repositry_ext::LifecycleViewRepositoryExt as _;
 use axum::extract::State;
 use axum::response::Redirect;
 use chrono::{DateTime, Utc};
@@ -24,48 +26,137 @@ pub struct InitiatedUpload {
     pub expiration: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct UploadUsage {
+    pub usage: UploadUsageView,
+    pub quota: UploadQuota,
+}
+
 fn bad_state(view: &UploadView) -> Error {
     Error::Upload {
         source: AggregateError::UserError(match view {
             UploadView::NotInitiated => UploadError::NotInitiated,
             UploadView::Finalized { .. } => UploadError::AlreadyFinalized,
-            UploadView::Dropped => UploadError::AlreadyDropped,
+            UploadView::Dropped { .. } => UploadError::AlreadyDropped,
+            UploadView::Discarded { .. } => UploadError::AlreadyDiscarded,
             UploadView::Initiated { .. } => UploadError::AlreadyInitiated,
         }),
     }
 }
 
-// TODO: I think this will just redirect to the file in the object storage
-pub async fn get_file(
-    State(state): State<ApplicationState>,
-    // use the default, non-json extractor here
-    // because this URL is not supposed to return JSON
-    axum::extract::Path(id): axum::extract::Path<UploadId>,
-) -> Redirect {
-    let id_str = id.0.to_string();
+/// Loads a finalized upload the user is allowed to download
+///
+/// Only the owner of the upload and the readers of the tickets referring to it can download it
+async fn load_downloadable(
+    state: &ApplicationState,
+    user: UserId,
+    id: UploadId,
+) -> Result<(UserId, UploadMetadata, Option<UploadThumbnail>), Error> {
     let view = state
         .cqrs
         .upload_view_repository
-        .load(&id_str)
+        .load(&id.0.to_string())
         .await
-        .context(PersistenceSnafu)
-        // error handing is aaaaa
-        .unwrap()
-        .ok_or(Error::NotFound)
-        .unwrap();
-
-    let UploadView::Finalized { owner, metadata } = view else {
-        return Err(bad_state(&view)).unwrap();
+        .context(PersistenceSnafu)?
+        .ok_or(Error::NotFound)?;
+
+    let UploadView::Finalized {
+        owner,
+        metadata,
+        thumbnail,
+    } = view
+    else {
+        return Err(bad_state(&view));
     };
 
+    if owner != user && !is_referenced_by_readable_ticket(state, user, id).await? {
+        return Err(Error::Upload {
+            source: AggregateError::UserError(UploadError::Forbidden),
+        });
+    }
+
+    Ok((owner, metadata, thumbnail))
+}
+
+/// Redirects to the file of the upload in the storage
+pub async fn get_file(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<UploadId>,
+) -> Result<Redirect, ApiResult> {
+    let (owner, metadata, _) = load_downloadable(&state, user_context.user_id(), id)
+        .await
+        .map_err(ApiResult::err)?;
+
     let url = state
         .upload_service
         .make_signed_retrieve_url(owner, id, &metadata)
         .await
-        .map_err(AggregateError::UserError)
-        .unwrap();
+        .map_err(|e| ApiResult::err(AggregateError::UserError(e)))?;
 
-    Redirect::to(&url)
+    Ok(Redirect::to(&url))
+}
+
+/// Redirects to the thumbnail of the upload in the storage. Not found until the thumbnail is made, and for the files that don't get one
+pub async fn get_thumbnail(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+    Path(id): Path<UploadId>,
+) -> Result<Redirect, ApiResult> {
+    let (owner, metadata, thumbnail) = load_downloadable(&state, user_context.user_id(), id)
+        .await
+        .map_err(ApiResult::err)?;
+    if thumbnail.is_none() {
+        return Err(ApiResult::err(Error::NotFound));
+    }
+
+    let url = state
+        .upload_service
+        .make_signed_thumbnail_url(owner, id, &metadata)
+        .await
+        .map_err(|e| ApiResult::err(AggregateError::UserError(e)))?;
+
+    Ok(Redirect::to(&url))
+}
+
+async fn is_referenced_by_readable_ticket(
+    state: &ApplicationState,
+    user: UserId,
+    upload: UploadId,
+) -> Result<bool, Error> {
+    let references = state
+        .cqrs
+        .upload_references_view_repository
+        .load(&upload.0.to_string())
+        .await
+        .context(PersistenceSnafu)?
+        .unwrap_or_default();
+
+    for ticket in references.tickets {
+        let Some(ticket) = state
+            .cqrs
+            .ticket_view_repository
+            .load_lifecycle(ticket)
+            .await
+            .context(PersistenceSnafu)?
+        else {
+            continue;
+        };
+        if ticket
+            .is_readable_by(
+                user,
+                state.cqrs.group_view_repository.as_ref(),
+                state.cqrs.group_hierarchy_view_repository.as_ref(),
+                state.cqrs.user_groups_view_repository.as_ref(),
+            )
+            .await
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
 }
 
 pub async fn initiate(
@@ -76,6 +167,10 @@ pub async fn initiate(
     ApiResult::from_async_fn(|| async move {
         let upload_id = UploadId(Id::generate());
 
+        let quota_lock = state
+            .upload_service
+            .lock_quota(user_context.user_id())
+            .await;
         state
             .cqrs
             .upload_cqrs
@@ -84,6 +179,7 @@ pub async fn initiate(
                 user_context.authenticated(UploadCommand::Initiate(meta.clone())),
             )
             .await?;
+        drop(quota_lock);
 
         let presigned = state
             .upload_service
@@ -94,8 +190,8 @@ pub async fn initiate(
         let upload = InitiatedUpload {
             id: upload_id,
             url: presigned.url,
-            fields: presigned.fields.into_iter().collect(),
-            expiration: DateTime::from_timestamp(presigned.expiration.unix_timestamp(), 0).unwrap(),
+            fields: presigned.fields,
+            expiration: presigned.expiration,
         };
 
         Ok(upload)
@@ -143,3 +239,24 @@ pub async fn finalize(
     })
     .await
 }
+
+pub async fn usage_query(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+) -> ApiResult<UploadUsage> {
+    ApiResult::from_async_fn(|| async move {
+        let usage = state
+            .cqrs
+            .upload_usage_view_repository
+            .load(&user_context.user_id().0.to_string())
+            .await
+            .context(PersistenceSnafu)?
+            .unwrap_or_default();
+
+        Ok(UploadUsage {
+            usage,
+            quota: state.upload_service.quota().clone(),
+        })
+    })
+    .await
+}
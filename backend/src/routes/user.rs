@@ -1,11 +1,11 @@
 use crate::api_result::ApiResult;
-use crate::domain::group::GroupView;
+use crate::domain::group::{GroupView, UserInvitationsView};
 use crate::domain::user::{
     CreateUser, IdentityView, UpdateUser, UserId, UserProfileView, UserView,
 };
 use crate::error::{Error, PersistenceSnafu};
 use crate::extractors::{Json, Path, UserContext};
-use crate::related_data::{ViewWithRelated as _, WithUsers};
+use crate::related_data::{load_user_profile, ViewWithRelated as _, WithGroups, WithUsers};
 use crate::state::ApplicationState;
 use crate::view_repositry_ext::LifecycleViewRepositoryExt;
 use axum::extract::State;
@@ -30,18 +30,28 @@ pub async fn me_query(
     .await
 }
 
+/// Deletes the account of the current user, erasing their personal data
+pub async fn me_delete_command(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+) -> ApiResult {
+    ApiResult::from_result(
+        state
+            .cqrs
+            .user_cqrs
+            .execute(user_context.user_id(), LifecycleCommand::Delete(()))
+            .await
+            .map_err(Into::into),
+    )
+}
+
 pub async fn profile_query(
     State(state): State<ApplicationState>,
     Path(id): Path<UserId>,
 ) -> ApiResult<UserProfileView> {
     ApiResult::from_async_fn(|| async {
-        state
-            .cqrs
-            .user_view_repository
-            .load_lifecycle(id)
-            .await
-            .context(PersistenceSnafu)?
-            .map(UserView::profile)
+        load_user_profile(state.cqrs.user_view_repository.as_ref(), id)
+            .await?
             .ok_or(Error::NotFound)
     })
     .await
@@ -89,6 +99,25 @@ pub async fn groups_query(
     .await
 }
 
+/// Lists the groups the current user has a pending invitation to
+pub async fn invitations_query(
+    State(state): State<ApplicationState>,
+    user_context: UserContext,
+) -> ApiResult<WithGroups<UserInvitationsView>> {
+    ApiResult::from_async_fn(|| async {
+        let invitations_view = state
+            .cqrs
+            .user_invitations_view_repository
+            .load(&user_context.user_id().0.to_string())
+            .await
+            .context(PersistenceSnafu)?
+            .unwrap_or_default();
+
+        WithGroups::new(&state.cqrs, invitations_view).await
+    })
+    .await
+}
+
 pub async fn internal_query(
     State(state): State<ApplicationState>,
     Path(id): Path<UserId>,
@@ -135,6 +164,20 @@ pub async fn internal_update_command(
     )
 }
 
+pub async fn internal_delete_command(
+    State(state): State<ApplicationState>,
+    Path(id): Path<UserId>,
+) -> ApiResult {
+    ApiResult::from_result(
+        state
+            .cqrs
+            .user_cqrs
+            .execute(id, LifecycleCommand::Delete(()))
+            .await
+            .map_err(Into::into),
+    )
+}
+
 pub async fn internal_identity(
     State(state): State<ApplicationState>,
     Path(id): Path<String>,
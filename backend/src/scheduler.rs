@@ -0,0 +1,558 @@
+use crate::auth::Authenticated;
+use crate::domain::ticket::{SystemTicketCommand, TicketAggregate, TicketCommand, TicketId};
+use crate::domain::upload::{Upload, UploadCommand, UploadId};
+use crate::domain::user::UserId;
+use crate::process_manager::SendCommand;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use cqrs_es::lifecycle::LifecycleCommand;
+use cqrs_es::persist::PersistenceError;
+use cqrs_es::AggregateError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tracing::{error, info};
+
+/// Source of the current time for the scheduler
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that follows the system one, but can be moved forward to test the time-based behaviors without waiting for them
+pub struct FakeClock {
+    offset: Mutex<Duration>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self {
+            offset: Mutex::new(Duration::zero()),
+        }
+    }
+}
+
+impl FakeClock {
+    pub fn advance(&self, by: Duration) -> DateTime<Utc> {
+        let mut offset = self.offset.lock().unwrap();
+        *offset += by;
+        Utc::now() + *offset
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now() + *self.offset.lock().unwrap()
+    }
+}
+
+/// A command to be sent when its time comes
+///
+/// Those are persisted, so they only store the data needed to build the actual aggregate command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ScheduledCommand {
+    /// Drop an upload that was not finalized in time. Sent on behalf of the upload owner
+    DropUpload { upload: UploadId, owner: UserId },
+    /// Discard a finalized upload that nothing refers to. Sent on behalf of the upload owner
+    DiscardUpload { upload: UploadId, owner: UserId },
+    /// Warn the owner of a ticket that waits for their reply that it is going to be closed
+    WarnInactiveTicket {
+        ticket: TicketId,
+        close_at: DateTime<Utc>,
+    },
+    /// Close a ticket whose owner did not reply in time
+    CloseInactiveTicket { ticket: TicketId },
+    /// End the snooze of a ticket
+    WakeTicket { ticket: TicketId },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledEntry {
+    /// Identifies the entry, scheduling with the same key replaces the previous command
+    pub key: String,
+    pub at: DateTime<Utc>,
+    pub command: ScheduledCommand,
+}
+
+/// Durable storage of the pending scheduled commands
+#[async_trait]
+pub trait ScheduleRepository: Send + Sync {
+    async fn load_all(&self) -> Result<Vec<ScheduledEntry>, PersistenceError>;
+    async fn save(&self, entry: &ScheduledEntry) -> Result<(), PersistenceError>;
+    async fn remove(&self, key: &str) -> Result<(), PersistenceError>;
+}
+
+/// Sends commands at a future instant. Used by the process managers to implement timeouts and reminders
+///
+/// Every change is written to the repository before it is applied, so the pending commands survive restarts
+pub struct Scheduler {
+    clock: Arc<dyn Clock>,
+    repository: Arc<dyn ScheduleRepository>,
+    entries: tokio::sync::Mutex<HashMap<String, ScheduledEntry>>,
+    wakeup: Notify,
+    upload_sender: Arc<dyn SendCommand<Upload>>,
+    ticket_sender: Arc<dyn SendCommand<TicketAggregate>>,
+}
+
+// the clock might be moved or the entries might be changed without waking us up, so don't sleep for too long
+const MAX_SLEEP: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How long to wait before sending again a command that could not be delivered
+fn retry_delay() -> Duration {
+    Duration::minutes(1)
+}
+
+enum SendError {
+    /// The aggregate refused the command, sending it again would not change that
+    Rejected(String),
+    /// The command did not reach the aggregate
+    Failed(String),
+}
+
+impl<E: std::error::Error> From<AggregateError<E>> for SendError {
+    fn from(error: AggregateError<E>) -> Self {
+        match error {
+            AggregateError::UserError(e) => SendError::Rejected(format!("{:?}", e)),
+            e => SendError::Failed(format!("{:?}", e)),
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new(
+        clock: Arc<dyn Clock>,
+        repository: Arc<dyn ScheduleRepository>,
+        upload_sender: Arc<dyn SendCommand<Upload>>,
+        ticket_sender: Arc<dyn SendCommand<TicketAggregate>>,
+    ) -> Self {
+        Self {
+            clock,
+            repository,
+            entries: tokio::sync::Mutex::new(HashMap::new()),
+            wakeup: Notify::new(),
+            upload_sender,
+            ticket_sender,
+        }
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Schedules a command to be sent at (or soon after) `at`, replacing the command with the same key, if any
+    pub async fn schedule(&self, key: String, at: DateTime<Utc>, command: ScheduledCommand) {
+        let entry = ScheduledEntry { key, at, command };
+
+        let mut entries = self.entries.lock().await;
+        self.repository
+            .save(&entry)
+            .await
+            .expect("Persistence error");
+        entries.insert(entry.key.clone(), entry);
+        drop(entries);
+
+        self.wakeup.notify_one();
+    }
+
+    /// Cancels the command with the given key. Does nothing if it was already sent or was never scheduled
+    pub async fn cancel(&self, key: &str) {
+        let mut entries = self.entries.lock().await;
+        if entries.contains_key(key) {
+            self.repository
+                .remove(key)
+                .await
+                .expect("Persistence error");
+            entries.remove(key);
+        }
+    }
+
+    /// Sends all the commands that are due
+    ///
+    /// An entry is only removed once its command was handled, the ones that could not be delivered are retried later
+    pub async fn run_due(&self) {
+        let now = self.clock.now();
+
+        let mut due = self
+            .entries
+            .lock()
+            .await
+            .values()
+            .filter(|entry| entry.at <= now)
+            .cloned()
+            .collect::<Vec<_>>();
+        due.sort_by_key(|entry| entry.at);
+        // the commands may schedule new ones, so do not hold the lock while sending them
+        for entry in due {
+            info!("Sending scheduled command `{}`", entry.key);
+            match self.send(entry.command.clone()).await {
+                Ok(()) => self.remove_sent(&entry).await,
+                Err(SendError::Rejected(e)) => {
+                    error!("Scheduled command `{}` was rejected: {}", entry.key, e);
+                    self.remove_sent(&entry).await;
+                }
+                Err(SendError::Failed(e)) => {
+                    error!("Failed to send scheduled command `{}`: {}", entry.key, e);
+                    self.postpone(&entry, now + retry_delay()).await;
+                }
+            }
+        }
+    }
+
+    /// Removes an entry whose command was sent, unless it was replaced in the meantime
+    async fn remove_sent(&self, entry: &ScheduledEntry) {
+        let mut entries = self.entries.lock().await;
+        if entries.get(&entry.key) == Some(entry) {
+            self.repository
+                .remove(&entry.key)
+                .await
+                .expect("Persistence error");
+            entries.remove(&entry.key);
+        }
+    }
+
+    /// Moves an entry whose command could not be sent, unless it was replaced in the meantime
+    async fn postpone(&self, entry: &ScheduledEntry, at: DateTime<Utc>) {
+        let mut entries = self.entries.lock().await;
+        if entries.get(&entry.key) == Some(entry) {
+            let entry = ScheduledEntry {
+                at,
+                ..entry.clone()
+            };
+            self.repository
+                .save(&entry)
+                .await
+                .expect("Persistence error");
+            entries.insert(entry.key.clone(), entry);
+        }
+    }
+
+    async fn send(&self, command: ScheduledCommand) -> Result<(), SendError> {
+        match command {
+            ScheduledCommand::DropUpload { upload, owner } => {
+                self.send_upload(upload, owner, UploadCommand::Drop).await
+            }
+            ScheduledCommand::DiscardUpload { upload, owner } => {
+                self.send_upload(upload, owner, UploadCommand::Discard { upload })
+                    .await
+            }
+            ScheduledCommand::WarnInactiveTicket { ticket, close_at } => {
+                self.send_ticket(ticket, SystemTicketCommand::WarnInactivity { close_at })
+                    .await
+            }
+            ScheduledCommand::CloseInactiveTicket { ticket } => {
+                self.send_ticket(ticket, SystemTicketCommand::CloseInactive)
+                    .await
+            }
+            ScheduledCommand::WakeTicket { ticket } => {
+                self.send_ticket(ticket, SystemTicketCommand::WakeUp).await
+            }
+        }
+    }
+
+    async fn send_upload(
+        &self,
+        upload: UploadId,
+        owner: UserId,
+        command: UploadCommand,
+    ) -> Result<(), SendError> {
+        self.upload_sender
+            .send(
+                upload,
+                Authenticated {
+                    user_id: owner,
+                    payload: command,
+                },
+            )
+            .await
+            .map_err(SendError::from)
+    }
+
+    async fn send_ticket(
+        &self,
+        ticket: TicketId,
+        command: SystemTicketCommand,
+    ) -> Result<(), SendError> {
+        self.ticket_sender
+            .send(
+                ticket,
+                LifecycleCommand::Update(TicketCommand::System(command)),
+            )
+            .await
+            .map_err(SendError::from)
+    }
+
+    async fn next_deadline(&self) -> Option<DateTime<Utc>> {
+        self.entries
+            .lock()
+            .await
+            .values()
+            .map(|entry| entry.at)
+            .min()
+    }
+
+    /// Loads the pending commands from the repository and starts sending them in background
+    pub async fn start(self: &Arc<Self>) {
+        let pending = self.repository.load_all().await.expect("Persistence error");
+        info!("Loaded {} pending scheduled commands", pending.len());
+        self.entries
+            .lock()
+            .await
+            .extend(pending.into_iter().map(|entry| (entry.key.clone(), entry)));
+
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            loop {
+                scheduler.run_due().await;
+
+                let sleep = scheduler
+                    .next_deadline()
+                    .await
+                    .map_or(MAX_SLEEP, |deadline| {
+                        (deadline - scheduler.now())
+                            .to_std()
+                            .unwrap_or_default()
+                            .min(MAX_SLEEP)
+                    });
+
+                tokio::select! {
+                    _ = tokio::time::sleep(sleep) => {}
+                    _ = scheduler.wakeup.notified() => {}
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cqrs_es::{Aggregate, Id};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[derive(Default)]
+    struct MemScheduleRepository {
+        entries: Mutex<HashMap<String, ScheduledEntry>>,
+    }
+
+    impl MemScheduleRepository {
+        fn get(&self, key: &str) -> Option<ScheduledEntry> {
+            self.entries.lock().unwrap().get(key).cloned()
+        }
+    }
+
+    #[async_trait]
+    impl ScheduleRepository for MemScheduleRepository {
+        async fn load_all(&self) -> Result<Vec<ScheduledEntry>, PersistenceError> {
+            Ok(self.entries.lock().unwrap().values().cloned().collect())
+        }
+
+        async fn save(&self, entry: &ScheduledEntry) -> Result<(), PersistenceError> {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(entry.key.clone(), entry.clone());
+            Ok(())
+        }
+
+        async fn remove(&self, key: &str) -> Result<(), PersistenceError> {
+            self.entries.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    /// Records the ids of the aggregates it sends commands to, or fails without delivering them
+    #[derive(Default)]
+    struct FakeSender {
+        sent: Mutex<Vec<Id>>,
+        fail: AtomicBool,
+    }
+
+    impl FakeSender {
+        fn sent(&self) -> Vec<Id> {
+            self.sent.lock().unwrap().clone()
+        }
+
+        fn record<E: std::error::Error>(&self, id: Id) -> Result<(), AggregateError<E>> {
+            if self.fail.load(Ordering::SeqCst) {
+                return Err(AggregateError::AggregateConflict);
+            }
+            self.sent.lock().unwrap().push(id);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl SendCommand<Upload> for FakeSender {
+        async fn send(
+            &self,
+            aggregate_id: UploadId,
+            _command: <Upload as Aggregate>::Command,
+        ) -> Result<(), AggregateError<<Upload as Aggregate>::Error>> {
+            self.record(aggregate_id.0)
+        }
+    }
+
+    #[async_trait]
+    impl SendCommand<TicketAggregate> for FakeSender {
+        async fn send(
+            &self,
+            aggregate_id: TicketId,
+            _command: <TicketAggregate as Aggregate>::Command,
+        ) -> Result<(), AggregateError<<TicketAggregate as Aggregate>::Error>> {
+            self.record(aggregate_id.0)
+        }
+    }
+
+    struct Fixture {
+        clock: Arc<FakeClock>,
+        repository: Arc<MemScheduleRepository>,
+        sender: Arc<FakeSender>,
+        scheduler: Scheduler,
+    }
+
+    fn fixture() -> Fixture {
+        let clock = Arc::new(FakeClock::default());
+        let repository = Arc::new(MemScheduleRepository::default());
+        let sender = Arc::new(FakeSender::default());
+        let scheduler = Scheduler::new(
+            clock.clone(),
+            repository.clone(),
+            sender.clone(),
+            sender.clone(),
+        );
+
+        Fixture {
+            clock,
+            repository,
+            sender,
+            scheduler,
+        }
+    }
+
+    fn wake(ticket: TicketId) -> ScheduledCommand {
+        ScheduledCommand::WakeTicket { ticket }
+    }
+
+    #[tokio::test]
+    async fn sends_commands_when_they_are_due() {
+        let f = fixture();
+        let upload = UploadId(Id::generate());
+        let ticket = TicketId(Id::generate());
+
+        f.scheduler
+            .schedule(
+                "drop".to_string(),
+                f.scheduler.now() + Duration::minutes(1),
+                ScheduledCommand::DropUpload {
+                    upload,
+                    owner: UserId::default(),
+                },
+            )
+            .await;
+        f.scheduler
+            .schedule(
+                "wake".to_string(),
+                f.scheduler.now() + Duration::hours(1),
+                wake(ticket),
+            )
+            .await;
+
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![]);
+
+        f.clock.advance(Duration::minutes(2));
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![upload.0]);
+        assert!(f.repository.get("drop").is_none());
+        assert!(f.repository.get("wake").is_some());
+
+        f.clock.advance(Duration::hours(1));
+        f.scheduler.run_due().await;
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![upload.0, ticket.0]);
+        assert!(f.repository.get("wake").is_none());
+    }
+
+    #[tokio::test]
+    async fn scheduling_with_the_same_key_replaces_the_command() {
+        let f = fixture();
+        let first = TicketId(Id::generate());
+        let second = TicketId(Id::generate());
+
+        f.scheduler
+            .schedule(
+                "wake".to_string(),
+                f.scheduler.now() + Duration::hours(1),
+                wake(first),
+            )
+            .await;
+        f.scheduler
+            .schedule(
+                "wake".to_string(),
+                f.scheduler.now() + Duration::minutes(1),
+                wake(second),
+            )
+            .await;
+        assert_eq!(f.repository.get("wake").unwrap().command, wake(second));
+
+        f.clock.advance(Duration::hours(2));
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![second.0]);
+    }
+
+    #[tokio::test]
+    async fn cancelled_commands_are_not_sent() {
+        let f = fixture();
+        let ticket = TicketId(Id::generate());
+
+        f.scheduler
+            .schedule(
+                "wake".to_string(),
+                f.scheduler.now() + Duration::minutes(1),
+                wake(ticket),
+            )
+            .await;
+        f.scheduler.cancel("wake").await;
+        // cancelling twice is fine
+        f.scheduler.cancel("wake").await;
+        assert!(f.repository.get("wake").is_none());
+
+        f.clock.advance(Duration::minutes(2));
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn undelivered_commands_are_retried() {
+        let f = fixture();
+        let ticket = TicketId(Id::generate());
+
+        f.scheduler
+            .schedule("wake".to_string(), f.scheduler.now(), wake(ticket))
+            .await;
+
+        f.sender.fail.store(true, Ordering::SeqCst);
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![]);
+        let postponed = f.repository.get("wake").unwrap();
+        assert!(postponed.at > f.scheduler.now());
+
+        f.sender.fail.store(false, Ordering::SeqCst);
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![]);
+
+        f.clock.advance(retry_delay());
+        f.scheduler.run_due().await;
+        assert_eq!(f.sender.sent(), vec![ticket.0]);
+        assert!(f.repository.get("wake").is_none());
+    }
+}
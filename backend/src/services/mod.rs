@@ -1,3 +1,5 @@
+pub mod personal_data;
+pub mod storage;
 pub mod upload;
 
 pub use upload::UploadService;
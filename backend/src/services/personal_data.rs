@@ -0,0 +1,143 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use cqrs_es::Id;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::RwLock;
+use tracing::warn;
+
+/// Identifies a key used to seal personal data of a single user.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct PersonalDataKey(pub Id);
+
+/// Encrypted personal data, as it is stored in the events.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SealedData {
+    key: PersonalDataKey,
+    #[serde(with = "hex")]
+    nonce: Vec<u8>,
+    #[serde(with = "hex")]
+    ciphertext: Vec<u8>,
+}
+
+/// Personal data embedded in an event.
+///
+/// Only the sealed form is ever persisted. Once the key is destroyed by the [`PersonalDataVault`] the data becomes unrecoverable, even though the events themselves are immutable.
+#[derive(Clone, PartialEq)]
+pub enum PersonalData<T> {
+    Sealed(SealedData),
+    /// Only produced by the [`PersonalDataVault`] when handing the events over to the queries
+    Unsealed(T),
+}
+
+impl<T> PersonalData<T> {
+    /// Returns the data if it was unsealed
+    pub fn unsealed(&self) -> Option<&T> {
+        match self {
+            PersonalData::Sealed(_) => None,
+            PersonalData::Unsealed(value) => Some(value),
+        }
+    }
+}
+
+impl<T> Debug for PersonalData<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersonalData::Sealed(sealed) => write!(f, "Sealed({:?})", sealed.key),
+            PersonalData::Unsealed(_) => write!(f, "Unsealed([REDACTED])"),
+        }
+    }
+}
+
+impl<T> Serialize for PersonalData<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PersonalData::Sealed(sealed) => sealed.serialize(serializer),
+            PersonalData::Unsealed(_) => Err(serde::ser::Error::custom(
+                "BUG: attempt to serialize unsealed personal data",
+            )),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for PersonalData<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SealedData::deserialize(deserializer).map(PersonalData::Sealed)
+    }
+}
+
+/// Holds the keys used to seal personal data of the users.
+///
+/// Destroying a key (when the user is deleted) makes all the data sealed with it unrecoverable.
+pub struct PersonalDataVault {
+    // TODO: the keys should be persisted, but separately from the event store
+    keys: RwLock<HashMap<PersonalDataKey, Key>>,
+}
+
+impl PersonalDataVault {
+    pub fn new() -> Self {
+        Self {
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn create_key(&self) -> PersonalDataKey {
+        let key = PersonalDataKey(Id::generate());
+        self.keys
+            .write()
+            .unwrap()
+            .insert(key, ChaCha20Poly1305::generate_key(&mut OsRng));
+        key
+    }
+
+    pub fn destroy_key(&self, key: PersonalDataKey) {
+        self.keys.write().unwrap().remove(&key);
+    }
+
+    pub fn seal<T: Serialize>(&self, key: PersonalDataKey, value: &T) -> PersonalData<T> {
+        let cipher = ChaCha20Poly1305::new(
+            self.keys
+                .read()
+                .unwrap()
+                .get(&key)
+                .expect("BUG: sealing personal data with a destroyed key"),
+        );
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plaintext = serde_json::to_vec(value).expect("Failed to serialize personal data");
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .expect("Failed to seal personal data");
+
+        PersonalData::Sealed(SealedData {
+            key,
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    /// Unseals the data, leaving it sealed if the key was destroyed.
+    pub fn unseal<T: DeserializeOwned + Clone>(&self, data: &PersonalData<T>) -> PersonalData<T> {
+        let PersonalData::Sealed(sealed) = data else {
+            return data.clone();
+        };
+
+        let keys = self.keys.read().unwrap();
+        let Some(key) = keys.get(&sealed.key) else {
+            warn!("Personal data key {:?} was destroyed", sealed.key);
+            return data.clone();
+        };
+
+        let plaintext = ChaCha20Poly1305::new(key)
+            .decrypt(
+                Nonce::from_slice(&sealed.nonce),
+                sealed.ciphertext.as_slice(),
+            )
+            .expect("Failed to unseal personal data");
+
+        PersonalData::Unsealed(
+            serde_json::from_slice(&plaintext).expect("Failed to deserialize personal data"),
+        )
+    }
+}
@@ -0,0 +1,538 @@
+use crate::error::ApiError;
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use s3::post_policy::PostPolicyExpiration;
+use s3::{Bucket, PostPolicy, PostPolicyField, PostPolicyValue};
+use sha2::Sha256;
+use snafu::{ensure, ResultExt, Snafu};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{ErrorKind, SeekFrom};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::debug;
+use url::Url;
+
+#[derive(Snafu, Debug)]
+#[snafu(visibility(pub(crate)))]
+pub enum StorageError {
+    /// Error in the underlying S3 library
+    S3 { source: s3::error::S3Error },
+    /// Error while accessing the local storage
+    Io { source: std::io::Error },
+    /// The object does not exist
+    ObjectNotFound,
+    /// Invalid object key
+    InvalidKey,
+    /// The request signature is invalid
+    InvalidSignature,
+    /// The request signature has expired
+    SignatureExpired,
+    /// Invalid upload form: {message}
+    InvalidForm { message: String },
+    /// The uploaded file is too large
+    ObjectTooLarge,
+}
+
+impl ApiError for StorageError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            StorageError::S3 { .. } | StorageError::Io { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            StorageError::ObjectNotFound => StatusCode::NOT_FOUND,
+            StorageError::InvalidKey | StorageError::InvalidForm { .. } => StatusCode::BAD_REQUEST,
+            StorageError::InvalidSignature | StorageError::SignatureExpired => {
+                StatusCode::FORBIDDEN
+            }
+            StorageError::ObjectTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+/// A form the client posts the file with, straight to the storage
+#[derive(Debug, Clone)]
+pub struct SignedPost {
+    pub url: String,
+    /// Must be sent before the file
+    pub fields: BTreeMap<String, String>,
+    pub expiration: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub size: Option<u64>,
+    pub content_type: Option<String>,
+}
+
+/// Keeps the uploaded files
+///
+/// The files never go through the API: the clients upload and download them using the signed URLs made by the storage
+#[async_trait]
+pub trait ObjectStorage: std::fmt::Debug + Send + Sync {
+    async fn make_signed_post(
+        &self,
+        key: &Utf8Path,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> Result<SignedPost, StorageError>;
+
+    /// Makes a URL to download the object as an attachment with the given filename
+    async fn make_signed_get_url(
+        &self,
+        key: &Utf8Path,
+        filename: &str,
+        expires_in: Duration,
+    ) -> Result<String, StorageError>;
+
+    async fn head(&self, key: &Utf8Path) -> Result<ObjectInfo, StorageError>;
+
+    async fn get(&self, key: &Utf8Path) -> Result<Vec<u8>, StorageError>;
+
+    /// Reads the bytes of the object from `start` to `end`, both inclusive
+    async fn get_range(
+        &self,
+        key: &Utf8Path,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, StorageError>;
+
+    async fn put(
+        &self,
+        key: &Utf8Path,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<(), StorageError>;
+
+    /// Deletes the object. Does nothing if it does not exist
+    async fn delete(&self, key: &Utf8Path) -> Result<(), StorageError>;
+
+    /// Lists the keys of the objects starting with `prefix`
+    async fn list(&self, prefix: &str) -> Result<Vec<Utf8PathBuf>, StorageError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Storage {
+    bucket: Bucket,
+}
+
+impl S3Storage {
+    pub fn new(bucket: Bucket) -> Self {
+        Self { bucket }
+    }
+}
+
+#[async_trait]
+impl ObjectStorage for S3Storage {
+    async fn make_signed_post(
+        &self,
+        key: &Utf8Path,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> Result<SignedPost, StorageError> {
+        let presigned =
+            PostPolicy::new(PostPolicyExpiration::ExpiresIn(expires_in.as_secs() as u32))
+                .condition(
+                    PostPolicyField::ContentType,
+                    PostPolicyValue::Exact(content_type.to_string().into()),
+                )
+                .context(S3Snafu)?
+                .condition(
+                    PostPolicyField::Key,
+                    PostPolicyValue::Exact(key.as_str().to_string().into()),
+                )
+                .context(S3Snafu)?
+                .sign(self.bucket.clone())
+                .await
+                .context(S3Snafu)?;
+
+        Ok(SignedPost {
+            url: presigned.url,
+            fields: presigned.fields.into_iter().collect(),
+            expiration: DateTime::from_timestamp(presigned.expiration.unix_timestamp(), 0).unwrap(),
+        })
+    }
+
+    async fn make_signed_get_url(
+        &self,
+        key: &Utf8Path,
+        filename: &str,
+        expires_in: Duration,
+    ) -> Result<String, StorageError> {
+        let custom_queries = HashMap::from([(
+            "response-content-disposition".into(),
+            format!("attachment; filename=\"{}\"", filename),
+        )]);
+
+        self.bucket
+            .presign_get(
+                key.as_str(),
+                expires_in.as_secs() as u32,
+                Some(custom_queries),
+            )
+            .await
+            .context(S3Snafu)
+    }
+
+    async fn head(&self, key: &Utf8Path) -> Result<ObjectInfo, StorageError> {
+        let (object, _) = self.bucket.head_object(key).await.context(S3Snafu)?;
+
+        debug!("Object: {:?}", object);
+
+        Ok(ObjectInfo {
+            size: object.content_length.and_then(|l| u64::try_from(l).ok()),
+            content_type: object.content_type,
+        })
+    }
+
+    async fn get(&self, key: &Utf8Path) -> Result<Vec<u8>, StorageError> {
+        let response = self.bucket.get_object(key).await.context(S3Snafu)?;
+        Ok(response.to_vec())
+    }
+
+    async fn get_range(
+        &self,
+        key: &Utf8Path,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, StorageError> {
+        let response = self
+            .bucket
+            .get_object_range(key, start, Some(end))
+            .await
+            .context(S3Snafu)?;
+        Ok(response.to_vec())
+    }
+
+    async fn put(
+        &self,
+        key: &Utf8Path,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<(), StorageError> {
+        self.bucket
+            .put_object_with_content_type(key, content, content_type)
+            .await
+            .context(S3Snafu)?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &Utf8Path) -> Result<(), StorageError> {
+        self.bucket.delete_object(key).await.context(S3Snafu)?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<Utf8PathBuf>, StorageError> {
+        let pages = self
+            .bucket
+            .list(prefix.to_string(), None)
+            .await
+            .context(S3Snafu)?;
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .map(|object| Utf8PathBuf::from(object.key))
+            .collect())
+    }
+}
+
+struct SigningKey([u8; 32]);
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+/// Keeps the objects in a local directory, the backend itself serves the signed URLs
+///
+/// Meant for development and testing. The signing key is generated on start, so the URLs do not survive restarts
+#[derive(Debug)]
+pub struct LocalStorage {
+    root: PathBuf,
+    public_url: Url,
+    signing_key: SigningKey,
+    max_object_size: u64,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf, public_url: Url, max_object_size: u64) -> Self {
+        let mut signing_key = [0; 32];
+        OsRng.fill_bytes(&mut signing_key);
+
+        Self {
+            root,
+            public_url,
+            signing_key: SigningKey(signing_key),
+            max_object_size,
+        }
+    }
+
+    pub fn max_object_size(&self) -> u64 {
+        self.max_object_size
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    /// The content types are kept in a tree mirroring the objects one
+    fn content_types_dir(&self) -> PathBuf {
+        self.root.join("content-types")
+    }
+
+    fn check_key(key: &Utf8Path) -> Result<(), StorageError> {
+        // the keys come from the signed requests, but don't let them escape the root anyway
+        ensure!(
+            key.components()
+                .all(|component| matches!(component, Utf8Component::Normal(_))),
+            InvalidKeySnafu
+        );
+        Ok(())
+    }
+
+    fn object_path(&self, key: &Utf8Path) -> Result<PathBuf, StorageError> {
+        Self::check_key(key)?;
+        Ok(self.objects_dir().join(key))
+    }
+
+    fn content_type_path(&self, key: &Utf8Path) -> Result<PathBuf, StorageError> {
+        Self::check_key(key)?;
+        Ok(self.content_types_dir().join(key))
+    }
+
+    fn mac(&self, parts: &[&str]) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.signing_key.0).expect("BUG: invalid key length");
+        // prefix the parts with their lengths, so that they can't be shifted into each other
+        for part in parts {
+            mac.update(&(part.len() as u64).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        mac
+    }
+
+    fn sign(&self, parts: &[&str]) -> String {
+        hex::encode(self.mac(parts).finalize().into_bytes())
+    }
+
+    fn verify(&self, parts: &[&str], expires: i64, signature: &str) -> Result<(), StorageError> {
+        let signature = hex::decode(signature).map_err(|_| StorageError::InvalidSignature)?;
+        self.mac(parts)
+            .verify_slice(&signature)
+            .map_err(|_| StorageError::InvalidSignature)?;
+        ensure!(expires >= Utc::now().timestamp(), SignatureExpiredSnafu);
+        Ok(())
+    }
+
+    /// Checks the fields of a form made by [`ObjectStorage::make_signed_post`]
+    pub fn verify_post(
+        &self,
+        key: &Utf8Path,
+        content_type: &str,
+        expires: i64,
+        signature: &str,
+    ) -> Result<(), StorageError> {
+        let expires_str = expires.to_string();
+        self.verify(
+            &["POST", key.as_str(), content_type, &expires_str],
+            expires,
+            signature,
+        )
+    }
+
+    /// Checks the query of a URL made by [`ObjectStorage::make_signed_get_url`]
+    pub fn verify_get(
+        &self,
+        key: &Utf8Path,
+        filename: &str,
+        expires: i64,
+        signature: &str,
+    ) -> Result<(), StorageError> {
+        let expires_str = expires.to_string();
+        self.verify(
+            &["GET", key.as_str(), filename, &expires_str],
+            expires,
+            signature,
+        )
+    }
+
+    /// Reads the whole object along with its content type
+    pub async fn read(&self, key: &Utf8Path) -> Result<(Vec<u8>, String), StorageError> {
+        let content = tokio::fs::read(self.object_path(key)?)
+            .await
+            .map_err(not_found_to_missing_object)?;
+        let content_type = tokio::fs::read_to_string(self.content_type_path(key)?)
+            .await
+            .map_err(not_found_to_missing_object)?;
+        Ok((content, content_type))
+    }
+
+    fn expiration(expires_in: Duration) -> DateTime<Utc> {
+        // the signatures have a second resolution
+        DateTime::from_timestamp(Utc::now().timestamp() + expires_in.as_secs() as i64, 0).unwrap()
+    }
+}
+
+fn not_found_to_missing_object(e: std::io::Error) -> StorageError {
+    match e.kind() {
+        ErrorKind::NotFound => StorageError::ObjectNotFound,
+        _ => StorageError::Io { source: e },
+    }
+}
+
+async fn remove_file_if_exists(path: PathBuf) -> Result<(), StorageError> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(StorageError::Io { source: e }),
+        _ => Ok(()),
+    }
+}
+
+async fn write_file(path: PathBuf, content: &[u8]) -> Result<(), StorageError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.context(IoSnafu)?;
+    }
+    tokio::fs::write(path, content).await.context(IoSnafu)
+}
+
+#[async_trait]
+impl ObjectStorage for LocalStorage {
+    async fn make_signed_post(
+        &self,
+        key: &Utf8Path,
+        content_type: &str,
+        expires_in: Duration,
+    ) -> Result<SignedPost, StorageError> {
+        Self::check_key(key)?;
+
+        let expiration = Self::expiration(expires_in);
+        let expires = expiration.timestamp().to_string();
+        let signature = self.sign(&["POST", key.as_str(), content_type, &expires]);
+
+        Ok(SignedPost {
+            url: self.public_url.join("api/storage/upload").unwrap().into(),
+            fields: BTreeMap::from([
+                ("key".to_string(), key.to_string()),
+                ("Content-Type".to_string(), content_type.to_string()),
+                ("expires".to_string(), expires),
+                ("signature".to_string(), signature),
+            ]),
+            expiration,
+        })
+    }
+
+    async fn make_signed_get_url(
+        &self,
+        key: &Utf8Path,
+        filename: &str,
+        expires_in: Duration,
+    ) -> Result<String, StorageError> {
+        Self::check_key(key)?;
+
+        let expires = Self::expiration(expires_in).timestamp().to_string();
+        let signature = self.sign(&["GET", key.as_str(), filename, &expires]);
+
+        let mut url = self.public_url.join("api/storage/object").unwrap();
+        url.query_pairs_mut()
+            .append_pair("key", key.as_str())
+            .append_pair("filename", filename)
+            .append_pair("expires", &expires)
+            .append_pair("signature", &signature);
+        Ok(url.into())
+    }
+
+    async fn head(&self, key: &Utf8Path) -> Result<ObjectInfo, StorageError> {
+        let metadata = tokio::fs::metadata(self.object_path(key)?)
+            .await
+            .map_err(not_found_to_missing_object)?;
+        let content_type = tokio::fs::read_to_string(self.content_type_path(key)?)
+            .await
+            .map_err(not_found_to_missing_object)?;
+
+        Ok(ObjectInfo {
+            size: Some(metadata.len()),
+            content_type: Some(content_type),
+        })
+    }
+
+    async fn get(&self, key: &Utf8Path) -> Result<Vec<u8>, StorageError> {
+        tokio::fs::read(self.object_path(key)?)
+            .await
+            .map_err(not_found_to_missing_object)
+    }
+
+    async fn get_range(
+        &self,
+        key: &Utf8Path,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, StorageError> {
+        let mut file = tokio::fs::File::open(self.object_path(key)?)
+            .await
+            .map_err(not_found_to_missing_object)?;
+        file.seek(SeekFrom::Start(start)).await.context(IoSnafu)?;
+
+        let mut content = Vec::new();
+        file.take(end.saturating_sub(start) + 1)
+            .read_to_end(&mut content)
+            .await
+            .context(IoSnafu)?;
+        Ok(content)
+    }
+
+    async fn put(
+        &self,
+        key: &Utf8Path,
+        content_type: &str,
+        content: &[u8],
+    ) -> Result<(), StorageError> {
+        ensure!(
+            content.len() as u64 <= self.max_object_size,
+            ObjectTooLargeSnafu
+        );
+        write_file(self.content_type_path(key)?, content_type.as_bytes()).await?;
+        write_file(self.object_path(key)?, content).await
+    }
+
+    async fn delete(&self, key: &Utf8Path) -> Result<(), StorageError> {
+        remove_file_if_exists(self.object_path(key)?).await?;
+        remove_file_if_exists(self.content_type_path(key)?).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<Utf8PathBuf>, StorageError> {
+        let objects_dir = self.objects_dir();
+        // only walk the directory the prefix points into
+        let start = match prefix.rsplit_once('/') {
+            Some((dir, _)) => Utf8PathBuf::from(dir),
+            None => Utf8PathBuf::new(),
+        };
+        Self::check_key(&start)?;
+
+        let mut keys = Vec::new();
+        let mut pending = vec![start];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(objects_dir.join(&dir)).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(StorageError::Io { source: e }),
+            };
+            while let Some(entry) = entries.next_entry().await.context(IoSnafu)? {
+                // the keys are always UTF-8, as they are made from strings
+                let Ok(name) = entry.file_name().into_string() else {
+                    continue;
+                };
+                let key = dir.join(name);
+                if entry.file_type().await.context(IoSnafu)?.is_dir() {
+                    pending.push(key);
+                } else if key.as_str().starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        Ok(keys)
+    }
+}
@@ -1,20 +1,30 @@
-use crate::domain::upload::{S3Snafu, UploadError, UploadId};
+use crate::domain::upload::{StorageSnafu, ThumbnailSnafu, UploadError, UploadId, UploadUsageView};
 use crate::domain::user::UserId;
+use crate::services::storage::{ObjectStorage, SignedPost};
 use camino::{Utf8Path, Utf8PathBuf};
-use s3::post_policy::{PostPolicyExpiration, PresignedPost};
-use s3::{Bucket, PostPolicy, PostPolicyField, PostPolicyValue};
+use image::ImageFormat;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 use std::collections::{BTreeSet, HashMap};
-use tracing::{debug, info};
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedMutexGuard, Semaphore};
+use tracing::info;
 use ts_rs::TS;
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct UploadMetadata {
-    filename: String,
-    content_type: String,
-    size: u64,
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+/// A smaller copy of an uploaded image, to be shown instead of downloading the whole file
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UploadThumbnail {
+    pub width: u32,
+    pub height: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -23,6 +33,16 @@ pub struct UploadPolicy {
     allowed_file_extensions: BTreeSet<String>,
     allowed_content_types: BTreeSet<String>,
     max_size: u64,
+    quota: UploadQuota,
+}
+
+/// Limits on the storage used by a single user
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct UploadQuota {
+    /// Total size of all the files, in bytes
+    pub max_total_size: u64,
+    pub max_files: u64,
 }
 
 #[derive(Snafu, Debug, PartialOrd, Ord, PartialEq, Eq)]
@@ -35,20 +55,101 @@ pub enum PolicyViolation {
     ContentTypeNotAllowed,
     /// File too large
     FileTooLarge,
+    /// Storage quota exceeded
+    QuotaExceeded,
+    /// Uploaded file size differs from the declared one
+    SizeMismatch,
+    /// Uploaded file content type differs from the declared one
+    ContentTypeMismatch,
+    /// Uploaded file content does not match its content type
+    UnexpectedContent,
 }
 
+/// How long the client has to upload the file and finalize the upload before it is dropped
+pub const UPLOAD_EXPIRATION: std::time::Duration = std::time::Duration::from_secs(1800);
+
+/// How long the download links are valid for
+const RETRIEVE_URL_EXPIRATION: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// How long a finalized upload is kept if nothing refers to it
+pub const UPLOAD_CLAIM_WINDOW: std::time::Duration = std::time::Duration::from_secs(24 * 3600);
+
 const BAD_FILENAME_CHARS: &[char] = &['/', '\\', '?', '%', '*', ':', '|', '"', '<', '>'];
 
+/// Signatures the files of some content types start with, used to catch the files lying about their type
+///
+/// The formats without a reliable signature (like text) are not checked
+const MAGIC_BYTES: &[(&str, &[u8])] = &[
+    ("image/png", b"\x89PNG\r\n\x1a\n"),
+    ("image/jpeg", b"\xff\xd8\xff"),
+    ("image/gif", b"GIF8"),
+    ("application/pdf", b"%PDF-"),
+    ("application/zip", b"PK\x03\x04"),
+    ("application/gzip", b"\x1f\x8b"),
+];
+
+impl UploadPolicy {
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+}
+
+/// The thumbnails fit into a square with this side
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// Decoding limits for the images the thumbnails are made of, so that a small file can't take all the memory
+const THUMBNAIL_SOURCE_MAX_DIMENSION: u32 = 10000;
+const THUMBNAIL_SOURCE_MAX_ALLOC: u64 = 256 * 1024 * 1024;
+
+/// How many thumbnails are made at once, each of them may take up to [`THUMBNAIL_SOURCE_MAX_ALLOC`]
+const MAX_CONCURRENT_THUMBNAILS: usize = 2;
+
+/// Image formats the thumbnails can be made of
+const THUMBNAIL_FORMATS: &[(&str, ImageFormat)] = &[
+    ("image/png", ImageFormat::Png),
+    ("image/jpeg", ImageFormat::Jpeg),
+    ("image/gif", ImageFormat::Gif),
+];
+
 #[derive(Debug, Clone)]
 pub struct UploadService {
-    pub bucket: Bucket,
+    pub storage: Arc<dyn ObjectStorage>,
     pub policy: UploadPolicy,
+    quota_locks: Arc<Mutex<HashMap<UserId, Arc<tokio::sync::Mutex<()>>>>>,
+    thumbnail_permits: Arc<Semaphore>,
 }
 
 impl UploadService {
+    pub fn new(storage: Arc<dyn ObjectStorage>, policy: UploadPolicy) -> Self {
+        Self {
+            storage,
+            policy,
+            quota_locks: Arc::new(Mutex::new(HashMap::new())),
+            thumbnail_permits: Arc::new(Semaphore::new(MAX_CONCURRENT_THUMBNAILS)),
+        }
+    }
+
+    /// Serializes the uploads initiated by a user: the quota is checked against the usage view,
+    /// which only accounts for an initiation once it is fully processed
+    pub async fn lock_quota(&self, user: UserId) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.quota_locks.lock().unwrap();
+            // forget the locks nobody holds or waits for
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(user).or_default().clone()
+        };
+
+        lock.lock_owned().await
+    }
+
+    pub fn quota(&self) -> &UploadQuota {
+        &self.policy.quota
+    }
+
     pub fn validate_upload_initiation(
         &self,
         meta: &UploadMetadata,
+        usage: &UploadUsageView,
     ) -> Result<(), Vec<PolicyViolation>> {
         let mut violations = BTreeSet::new();
 
@@ -76,6 +177,12 @@ impl UploadService {
         if meta.size > self.policy.max_size {
             violations.insert(PolicyViolation::FileTooLarge);
         }
+        let quota = &self.policy.quota;
+        if usage.files >= quota.max_files
+            || usage.total_size.saturating_add(meta.size) > quota.max_total_size
+        {
+            violations.insert(PolicyViolation::QuotaExceeded);
+        }
 
         if violations.is_empty() {
             Ok(())
@@ -84,15 +191,23 @@ impl UploadService {
         }
     }
 
+    /// All the objects of an upload are stored under this path
+    pub fn make_upload_dir(&self, user: UserId, upload: UploadId) -> Utf8PathBuf {
+        Utf8PathBuf::from(user.0.to_string()).join(upload.0.to_string())
+    }
+
     pub fn make_upload_path(
         &self,
         user: UserId,
         upload: UploadId,
         meta: &UploadMetadata,
     ) -> Utf8PathBuf {
-        Utf8PathBuf::from(user.0.to_string())
-            .join(upload.0.to_string())
-            .join(&meta.filename)
+        self.make_upload_dir(user, upload).join(&meta.filename)
+    }
+
+    /// The thumbnail is kept next to the original file. An uploaded file can't be named like this, as it has no extension
+    pub fn make_thumbnail_path(&self, user: UserId, upload: UploadId) -> Utf8PathBuf {
+        self.make_upload_dir(user, upload).join("thumbnail")
     }
 
     pub async fn make_signed_upload_request(
@@ -100,27 +215,18 @@ impl UploadService {
         user: UserId,
         upload: UploadId,
         meta: UploadMetadata,
-    ) -> Result<PresignedPost, UploadError> {
+    ) -> Result<SignedPost, UploadError> {
         let path = self.make_upload_path(user, upload, &meta);
 
         info!("Creating a pre-signed request to upload to {}", path);
 
-        PostPolicy::new(PostPolicyExpiration::ExpiresIn(1800))
-            .condition(
-                PostPolicyField::ContentType,
-                PostPolicyValue::Exact(meta.content_type.into()),
-            )
-            .context(S3Snafu)?
-            .condition(
-                PostPolicyField::Key,
-                PostPolicyValue::Exact(path.into_string().into()),
-            )
-            .context(S3Snafu)?
-            .sign(self.bucket.clone())
+        self.storage
+            .make_signed_post(&path, &meta.content_type, UPLOAD_EXPIRATION)
             .await
-            .context(S3Snafu)
+            .context(StorageSnafu)
     }
 
+    /// Checks that the uploaded file is the one declared on initiation. The offending file is deleted, so that the client can upload it again
     pub async fn check_upload(
         &self,
         user: UserId,
@@ -131,11 +237,46 @@ impl UploadService {
 
         info!("Checking upload at {}", path);
 
-        let (object, _) = self.bucket.head_object(&path).await.context(S3Snafu)?;
+        let object = self.storage.head(&path).await.context(StorageSnafu)?;
 
-        debug!("Object: {:?}", object);
+        let mut violations = BTreeSet::new();
+        let size = object.size;
+        if size != Some(meta.size) {
+            violations.insert(PolicyViolation::SizeMismatch);
+        }
+        if object.content_type.as_deref() != Some(meta.content_type.as_str()) {
+            violations.insert(PolicyViolation::ContentTypeMismatch);
+        }
+        if let Some((_, signature)) = MAGIC_BYTES
+            .iter()
+            .find(|(content_type, _)| *content_type == meta.content_type)
+        {
+            // an empty range can't be requested, and a short file can't have the signature anyway
+            let matches = if size.unwrap_or(0) >= signature.len() as u64 {
+                let head = self
+                    .storage
+                    .get_range(&path, 0, signature.len() as u64 - 1)
+                    .await
+                    .context(StorageSnafu)?;
+                head.starts_with(signature)
+            } else {
+                false
+            };
+            if !matches {
+                violations.insert(PolicyViolation::UnexpectedContent);
+            }
+        }
 
-        Ok(())
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        info!("Upload at {} violates the policy: {:?}", path, violations);
+        self.storage.delete(&path).await.context(StorageSnafu)?;
+
+        Err(UploadError::PolicyViolated {
+            violations: violations.into_iter().collect(),
+        })
     }
 
     pub async fn make_signed_retrieve_url(
@@ -146,14 +287,123 @@ impl UploadService {
     ) -> Result<String, UploadError> {
         let path = self.make_upload_path(user, upload, meta);
 
-        let custom_queries = HashMap::from([(
-            "response-content-disposition".into(),
-            format!("attachment; filename=\"{}\"", meta.filename),
-        )]);
+        self.storage
+            .make_signed_get_url(&path, &meta.filename, RETRIEVE_URL_EXPIRATION)
+            .await
+            .context(StorageSnafu)
+    }
+
+    pub fn supports_thumbnail(&self, meta: &UploadMetadata) -> bool {
+        THUMBNAIL_FORMATS
+            .iter()
+            .any(|(content_type, _)| *content_type == meta.content_type)
+    }
+
+    /// Makes a PNG thumbnail of an uploaded image and puts it next to the original
+    pub async fn make_thumbnail(
+        &self,
+        user: UserId,
+        upload: UploadId,
+        meta: &UploadMetadata,
+    ) -> Result<UploadThumbnail, UploadError> {
+        let Some(&(_, format)) = THUMBNAIL_FORMATS
+            .iter()
+            .find(|(content_type, _)| *content_type == meta.content_type)
+        else {
+            return Err(UploadError::ThumbnailNotSupported);
+        };
+
+        let path = self.make_upload_path(user, upload, meta);
+        let thumbnail_path = self.make_thumbnail_path(user, upload);
+
+        // the images are only loaded once there is room to decode them
+        let _permit = self
+            .thumbnail_permits
+            .acquire()
+            .await
+            .expect("BUG: thumbnail semaphore closed");
+
+        info!("Making a thumbnail of {} at {}", path, thumbnail_path);
+
+        let content = self.storage.get(&path).await.context(StorageSnafu)?;
+        // decoding and resizing take a while, don't block the other tasks
+        let (thumbnail, encoded) = tokio::task::spawn_blocking(move || {
+            let mut reader = image::io::Reader::with_format(Cursor::new(content), format);
+            let mut limits = image::io::Limits::default();
+            limits.max_image_width = Some(THUMBNAIL_SOURCE_MAX_DIMENSION);
+            limits.max_image_height = Some(THUMBNAIL_SOURCE_MAX_DIMENSION);
+            limits.max_alloc = Some(THUMBNAIL_SOURCE_MAX_ALLOC);
+            reader.limits(limits);
+
+            let image = reader.decode()?.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+            let mut encoded = Vec::new();
+            image.write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)?;
+
+            Ok((
+                UploadThumbnail {
+                    width: image.width(),
+                    height: image.height(),
+                },
+                encoded,
+            ))
+        })
+        .await
+        .expect("Thumbnail task panicked")
+        .context(ThumbnailSnafu)?;
+
+        self.storage
+            .put(&thumbnail_path, "image/png", &encoded)
+            .await
+            .context(StorageSnafu)?;
+
+        Ok(thumbnail)
+    }
 
-        self.bucket
-            .presign_get(path.into_string(), 3600, Some(custom_queries))
+    pub async fn make_signed_thumbnail_url(
+        &self,
+        user: UserId,
+        upload: UploadId,
+        meta: &UploadMetadata,
+    ) -> Result<String, UploadError> {
+        let path = self.make_thumbnail_path(user, upload);
+        let filename = format!(
+            "{}-thumbnail.png",
+            Utf8Path::new(&meta.filename)
+                .file_stem()
+                .unwrap_or(&meta.filename)
+        );
+
+        self.storage
+            .make_signed_get_url(&path, &filename, RETRIEVE_URL_EXPIRATION)
             .await
-            .context(S3Snafu)
+            .context(StorageSnafu)
+    }
+
+    /// Deletes the thumbnail of the upload, keeping the uploaded file itself
+    pub async fn delete_thumbnail(
+        &self,
+        user: UserId,
+        upload: UploadId,
+    ) -> Result<(), UploadError> {
+        let path = self.make_thumbnail_path(user, upload);
+
+        info!("Deleting thumbnail {}", path);
+
+        self.storage.delete(&path).await.context(StorageSnafu)
+    }
+
+    /// Deletes everything stored for the upload, including partially uploaded files
+    pub async fn delete_objects(&self, user: UserId, upload: UploadId) -> Result<(), UploadError> {
+        // the trailing slash prevents matching the uploads whose id starts with this one
+        let prefix = format!("{}/", self.make_upload_dir(user, upload));
+
+        info!("Deleting objects under {}", prefix);
+
+        let keys = self.storage.list(&prefix).await.context(StorageSnafu)?;
+        for key in keys {
+            self.storage.delete(&key).await.context(StorageSnafu)?;
+        }
+
+        Ok(())
     }
 }
@@ -1,12 +1,26 @@
 use crate::auth::CookieAuthority;
-use crate::config::TelegramSecret;
-use crate::domain::group::{Group, GroupView, UserGroupsQuery, UserGroupsView};
+use crate::config::{LocalStorageConfig, S3Config, TelegramSecret, UploadStorage};
+use crate::domain::group::{
+    Group, GroupHierarchyQuery, GroupHierarchyView, GroupInvitationsView, GroupServices, GroupView,
+    UserGroupsQuery, UserGroupsView, UserInvitationsQuery, UserInvitationsView,
+};
 use crate::domain::ticket::{
-    Ticket, TicketListingKind, TicketListingQuery, TicketListingView, TicketServices, TicketView,
+    AgentWorkloadQuery, Ticket, TicketAssigner, TicketInactivityManager, TicketListingKind,
+    TicketListingQuery, TicketListingView, TicketRelationManager, TicketServices, TicketSlaQuery,
+    TicketSlaView, TicketSnoozeManager, TicketView, UploadReferencesQuery, UploadReferencesView,
+};
+use crate::domain::upload::{
+    Upload, UploadCollector, UploadQuery, UploadServices, UploadThumbnailer, UploadUsageQuery,
+    UploadUsageView, UploadView,
+};
+use crate::domain::user::{
+    IdentityQuery, IdentityView, PersonalDataShredder, UnsealingQuery, User, UserServices, UserView,
 };
-use crate::domain::upload::{Upload, UploadQuery, UploadView};
-use crate::domain::user::{IdentityQuery, IdentityView, User, UserServices, UserView};
 use crate::meilisearch_view_repository::MeilisearchViewRepository;
+use crate::process_manager::CommandSender;
+use crate::scheduler::{Clock, FakeClock, Scheduler, SystemClock};
+use crate::services::personal_data::PersonalDataVault;
+use crate::services::storage::{LocalStorage, ObjectStorage, S3Storage};
 use crate::services::upload::UploadService;
 use cqrs_es::lifecycle::{
     LifecycleAggregate, LifecycleAggregateState, LifecycleQuery, LifecycleView, LifecycleViewState,
@@ -37,6 +51,10 @@ pub struct ApplicationState {
     pub cqrs: CqrsState,
     pub search: SearchState,
     pub upload_service: Arc<UploadService>,
+    /// Only set when the uploads are kept in a local directory
+    pub local_storage: Option<Arc<LocalStorage>>,
+    /// Only set when the scheduler is configured to use a fake clock
+    pub fake_clock: Option<Arc<FakeClock>>,
 }
 
 #[derive(Clone)]
@@ -58,10 +76,12 @@ impl SearchState {
                     searchable_attributes: Some(
                         // TODO: search by messages? maybe in a different index?
                         // honestly, we shouldn't be using meilisearch to store our data...
+                        // the timeline must not be searchable as is: matching the internal
+                        // notes would reveal them to anyone who can search
                         ["title"].into_iter().map(ToString::to_string).collect(),
                     ),
                     filterable_attributes: Some(
-                        ["lifecycle_state"]
+                        ["lifecycle_state", "labels.name"]
                             .into_iter()
                             .map(ToString::to_string)
                             .collect(),
@@ -128,12 +148,18 @@ impl SearchState {
 pub struct CqrsState {
     pub group_view_repository: Arc<MyLifecycleViewRepository<GroupView>>,
     pub user_groups_view_repository: Arc<MyViewRepository<UserGroupsView>>,
+    pub group_invitations_view_repository: Arc<MyLifecycleViewRepository<GroupInvitationsView>>,
+    pub user_invitations_view_repository: Arc<MyViewRepository<UserInvitationsView>>,
+    pub group_hierarchy_view_repository: Arc<MyViewRepository<GroupHierarchyView>>,
     pub group_cqrs: Arc<MyCqrsFramework<Group>>,
 
     pub ticket_view_repository: Arc<MyLifecycleViewRepository<TicketView>>,
     pub ticket_owner_listing_view_repository: Arc<MyViewRepository<TicketListingView>>,
     pub ticket_assignee_listing_view_repository: Arc<MyViewRepository<TicketListingView>>,
     pub ticket_destination_listing_view_repository: Arc<MyViewRepository<TicketListingView>>,
+    pub ticket_watched_listing_view_repository: Arc<MyViewRepository<TicketListingView>>,
+    pub ticket_sla_view_repository: Arc<MyViewRepository<TicketSlaView>>,
+    pub upload_references_view_repository: Arc<MyViewRepository<UploadReferencesView>>,
     pub ticket_cqrs: Arc<MyCqrsFramework<Ticket>>,
 
     pub user_view_repository: Arc<MyLifecycleViewRepository<UserView>>,
@@ -141,7 +167,10 @@ pub struct CqrsState {
     pub user_cqrs: Arc<MyCqrsFramework<User>>,
 
     pub upload_view_repository: Arc<MyViewRepository<UploadView>>,
+    pub upload_usage_view_repository: Arc<MyViewRepository<UploadUsageView>>,
     pub upload_cqrs: Arc<MyPlainCqrsFramework<Upload>>,
+
+    pub scheduler: Arc<Scheduler>,
 }
 
 pub trait BattsAggregate: LifecycleAggregate {
@@ -194,6 +223,8 @@ impl BattsView for TicketView {
 struct CqrsBuilder {
     meilisearch: meilisearch_sdk::Client,
     index_names: HashSet<String>,
+    /// The subset of `index_names` that is not wiped on startup
+    persistent_index_names: HashSet<String>,
 }
 
 impl CqrsBuilder {
@@ -202,7 +233,26 @@ impl CqrsBuilder {
         Self {
             meilisearch,
             index_names: HashSet::new(),
+            persistent_index_names: HashSet::new(),
+        }
+    }
+
+    /// Creates an index that is not backing a view repository, but is managed together with them
+    fn index(&mut self, name: &str) -> Index {
+        let index = Index::new(name, self.meilisearch.clone());
+        if !self.index_names.insert(index.uid.clone()) {
+            panic!("An index named `{}` already exists", index.uid)
         }
+
+        index
+    }
+
+    /// Creates an index holding data that cannot be rebuilt from the events, so it is not wiped on startup
+    fn persistent_index(&mut self, name: &str) -> Index {
+        let index = self.index(name);
+        self.persistent_index_names.insert(index.uid.clone());
+
+        index
     }
 
     fn aggregate<A: Aggregate>(&mut self, _name: &str) -> AggregateBuilder<A> {
@@ -217,7 +267,7 @@ impl CqrsBuilder {
 
         // wipe the elasticsearch database
         // TODO: remove this when we are able to handle persistence
-        for index in &self.index_names {
+        for index in self.index_names.difference(&self.persistent_index_names) {
             match self.meilisearch.delete_index(index).await {
                 Ok(task) => {
                     tasks.push(task);
@@ -271,6 +321,20 @@ struct AggregateBuilder<'a, A: Aggregate> {
 }
 
 impl<'a, A: Aggregate> AggregateBuilder<'a, A> {
+    fn query<Q: Query<A> + 'static>(&mut self, query: Q) {
+        self.queries.push(Box::new(query));
+    }
+
+    /// Creates a view repository that is not backed by its own query (it is updated by some other query)
+    fn auxiliary_view_repository<V: View>(&mut self, name: &str) -> Arc<MyViewRepository<V>> {
+        let index = Index::new(name, self.cqrs.meilisearch.clone());
+        if !self.cqrs.index_names.insert(index.uid.clone()) {
+            panic!("An index named `{}` already exists", index.uid)
+        }
+
+        Arc::new(MyViewRepository::new(index))
+    }
+
     fn view_repository_from_index<
         V: View,
         Q: Query<A> + 'static,
@@ -287,7 +351,7 @@ impl<'a, A: Aggregate> AggregateBuilder<'a, A> {
         let view_repository = MyViewRepository::new(index.clone());
         let view_repository = Arc::new(view_repository);
 
-        self.queries.push(Box::new(f(view_repository.clone())));
+        self.query(f(view_repository.clone()));
 
         view_repository
     }
@@ -342,9 +406,7 @@ async fn search_state(config: &crate::config::Config) -> SearchState {
     }
 }
 
-async fn upload_service(config: &crate::config::Upload) -> Arc<UploadService> {
-    let s3 = &config.s3;
-
+async fn s3_storage(s3: &S3Config) -> S3Storage {
     let region = s3::Region::Custom {
         region: "us-east1".to_owned(),
         endpoint: s3.endpoint.as_str().trim_end_matches('/').to_string(),
@@ -391,23 +453,119 @@ async fn upload_service(config: &crate::config::Upload) -> Arc<UploadService> {
         .expect("Failed to create S3 bucket");
     }
 
-    Arc::new(UploadService {
-        bucket,
-        policy: config.policy.clone(),
-    })
+    S3Storage::new(bucket)
+}
+
+async fn local_storage(config: &LocalStorageConfig, max_object_size: u64) -> LocalStorage {
+    // TODO: do not erase the directory when we start handling persistence
+    if tokio::fs::try_exists(&config.root)
+        .await
+        .expect("Failed to check if the storage directory exists")
+    {
+        warn!("Clearing storage directory `{}`", config.root.display());
+        tokio::fs::remove_dir_all(&config.root)
+            .await
+            .expect("Failed to clear the storage directory");
+    }
+    tokio::fs::create_dir_all(&config.root)
+        .await
+        .expect("Failed to create the storage directory");
+
+    LocalStorage::new(
+        config.root.clone(),
+        config.public_url.clone(),
+        max_object_size,
+    )
 }
 
-async fn cqrs_state(search_state: &SearchState, upload_service: Arc<UploadService>) -> CqrsState {
+async fn upload_service(
+    config: &crate::config::Upload,
+) -> (Arc<UploadService>, Option<Arc<LocalStorage>>) {
+    let (storage, local_storage): (Arc<dyn ObjectStorage>, _) = match &config.storage {
+        UploadStorage::S3(s3) => (Arc::new(s3_storage(s3).await), None),
+        UploadStorage::Local(local) => {
+            warn!("Keeping the uploads in a local directory. DO NOT USE IN PRODUCTION!");
+            let storage = Arc::new(local_storage(local, config.policy.max_size()).await);
+            (storage.clone(), Some(storage))
+        }
+    };
+
+    let upload_service = Arc::new(UploadService::new(storage, config.policy.clone()));
+    (upload_service, local_storage)
+}
+
+async fn cqrs_state(
+    search_state: &SearchState,
+    upload_service: Arc<UploadService>,
+    clock: Arc<dyn Clock>,
+) -> CqrsState {
     let mut builder = CqrsBuilder::new(search_state.meilisearch.clone());
 
+    let upload_sender = CommandSender::new();
+    let ticket_sender = CommandSender::new();
+    let scheduler = Arc::new(Scheduler::new(
+        clock.clone(),
+        Arc::new(MeilisearchViewRepository::new(
+            builder.persistent_index("scheduler"),
+        )),
+        Arc::new(upload_sender.clone()),
+        Arc::new(ticket_sender.clone()),
+    ));
+
     let mut groups_builder = builder.aggregate("groups");
 
     let group_view_repository =
         groups_builder.lifecycle_view_repository(search_state.group_index.clone());
     let user_groups_view_repository =
         groups_builder.view_repository("groups-user", UserGroupsQuery::new);
+    let group_invitations_view_repository = groups_builder.lifecycle_view_repository(Index::new(
+        "groups-invitations",
+        search_state.meilisearch.clone(),
+    ));
+    let user_invitations_view_repository =
+        groups_builder.view_repository("groups-user-invitations", UserInvitationsQuery::new);
+    let group_hierarchy_view_repository =
+        groups_builder.view_repository("groups-hierarchy", GroupHierarchyQuery::new);
+
+    let group_cqrs = groups_builder.build(GroupServices {
+        group_view_repository: group_view_repository.clone(),
+        group_hierarchy_view_repository: group_hierarchy_view_repository.clone(),
+    });
 
-    let group_cqrs = groups_builder.build(());
+    // maintained by the tickets, but the uploads need it to tell which ones are still in use
+    let upload_references_index = Index::new(
+        "tickets-upload-references",
+        search_state.meilisearch.clone(),
+    );
+
+    let mut upload_builder = builder.aggregate("uploads");
+
+    let upload_view_repository = upload_builder.view_repository("uploads", UploadQuery::new);
+    // must go after the view: the collector finds the objects to delete from it
+    upload_builder.query(UploadCollector::new(
+        upload_view_repository.clone(),
+        upload_service.clone(),
+        scheduler.clone(),
+    ));
+
+    upload_builder.query(UploadThumbnailer::new(
+        upload_view_repository.clone(),
+        upload_service.clone(),
+        Arc::new(upload_sender.clone()),
+    ));
+    // must go after the view too: the removed uploads no longer have their size in the events
+    let upload_usage_view_repository = upload_builder.view_repository("uploads-usage", |repo| {
+        UploadUsageQuery::new(repo, upload_view_repository.clone())
+    });
+
+    let upload_cqrs = upload_builder.build(UploadServices {
+        upload_service,
+        usage_view_repository: upload_usage_view_repository.clone(),
+        references_view_repository: Arc::new(MyViewRepository::new(
+            upload_references_index.clone(),
+        )),
+    });
+    upload_sender.connect(upload_cqrs.clone());
 
     let mut tickets_builder = builder.aggregate("tickets");
     let ticket_view_repository =
@@ -433,28 +591,85 @@ async fn cqrs_state(search_state: &SearchState, upload_service: Arc<UploadServic
         "tickets-destination-listing",
         make_ticket_listing(TicketListingKind::Destination),
     );
+    let ticket_watched_listing_view_repository = tickets_builder.view_repository(
+        "tickets-watched-listing",
+        make_ticket_listing(TicketListingKind::Watched),
+    );
+
+    let ticket_sla_view_repository = tickets_builder.view_repository("tickets-sla", |repo| {
+        TicketSlaQuery::new(repo, group_view_repository.clone())
+    });
+    let ticket_uploads_state_repository =
+        tickets_builder.auxiliary_view_repository("tickets-uploads-state");
+    let upload_references_view_repository =
+        tickets_builder.view_repository_from_index(upload_references_index, |repo| {
+            UploadReferencesQuery::new(
+                repo,
+                ticket_uploads_state_repository,
+                upload_view_repository.clone(),
+                scheduler.clone(),
+            )
+        });
+    let ticket_workload_state_repository =
+        tickets_builder.auxiliary_view_repository("tickets-workload-state");
+    let agent_workload_view_repository = tickets_builder
+        .view_repository("tickets-agent-workload", |repo| {
+            AgentWorkloadQuery::new(repo, ticket_workload_state_repository)
+        });
+    let group_assignment_view_repository =
+        tickets_builder.auxiliary_view_repository("tickets-group-assignment");
+    let ticket_inactivity_state_repository =
+        tickets_builder.auxiliary_view_repository("tickets-inactivity-state");
+    // must go last: the assignment is a new command, all the queries should have seen the creation by then
+    tickets_builder.query(TicketAssigner::new(
+        group_view_repository.clone(),
+        agent_workload_view_repository.clone(),
+        group_assignment_view_repository,
+        ticket_sender.clone(),
+    ));
+    tickets_builder.query(TicketRelationManager::new(ticket_sender.clone()));
+    tickets_builder.query(TicketInactivityManager::new(
+        group_view_repository.clone(),
+        ticket_inactivity_state_repository,
+        scheduler.clone(),
+    ));
+    tickets_builder.query(TicketSnoozeManager::new(scheduler.clone()));
 
     let ticket_cqrs = tickets_builder.build(TicketServices {
         group_view_repository: group_view_repository.clone(),
+        group_hierarchy_view_repository: group_hierarchy_view_repository.clone(),
+        user_groups_view_repository: user_groups_view_repository.clone(),
+        ticket_view_repository: ticket_view_repository.clone(),
+        upload_view_repository: upload_view_repository.clone(),
+        clock,
     });
+    ticket_sender.connect(ticket_cqrs.clone());
+
+    let personal_data_vault = Arc::new(PersonalDataVault::new());
 
     let mut user_builder = builder.aggregate("users");
 
-    let user_view_repository =
-        user_builder.lifecycle_view_repository(search_state.user_index.clone());
-    let user_identity_view_repository =
-        user_builder.view_repository("users-identity", IdentityQuery::new);
+    // the user views need the personal data, so they get the events unsealed
+    let user_view_repository = user_builder
+        .view_repository_from_index(search_state.user_index.clone(), |repo| {
+            UnsealingQuery::new(personal_data_vault.clone(), LifecycleQuery::new(repo))
+        });
+    let user_identity_keys_view_repository =
+        user_builder.auxiliary_view_repository("users-identity-keys");
+    let user_identity_view_repository = user_builder.view_repository("users-identity", |repo| {
+        UnsealingQuery::new(
+            personal_data_vault.clone(),
+            IdentityQuery::new(repo, user_identity_keys_view_repository),
+        )
+    });
+    // must go last: the other queries need the key to unseal the data
+    user_builder.query(PersonalDataShredder::new(personal_data_vault.clone()));
 
     let user_cqrs = user_builder.build(UserServices {
         user_identity_view_repository: user_identity_view_repository.clone(),
+        personal_data_vault,
     });
 
-    let mut upload_builder = builder.aggregate("uploads");
-
-    let upload_view_repository = upload_builder.view_repository("uploads", UploadQuery::new);
-
-    let upload_cqrs = upload_builder.build(upload_service);
-
     builder.finalize().await;
 
     CqrsState {
@@ -462,10 +677,16 @@ async fn cqrs_state(search_state: &SearchState, upload_service: Arc<UploadServic
         ticket_owner_listing_view_repository,
         ticket_assignee_listing_view_repository,
         ticket_destination_listing_view_repository,
+        ticket_watched_listing_view_repository,
+        ticket_sla_view_repository,
+        upload_references_view_repository,
         ticket_cqrs,
 
         group_view_repository,
         user_groups_view_repository,
+        group_invitations_view_repository,
+        user_invitations_view_repository,
+        group_hierarchy_view_repository,
         group_cqrs,
 
         user_view_repository,
@@ -473,7 +694,10 @@ async fn cqrs_state(search_state: &SearchState, upload_service: Arc<UploadServic
         user_cqrs,
 
         upload_view_repository,
+        upload_usage_view_repository,
         upload_cqrs,
+
+        scheduler,
     }
 }
 
@@ -493,10 +717,23 @@ pub async fn new_application_state(config: &crate::config::Config) -> Applicatio
     );
 
     let search = search_state(config).await;
-    let upload_service = upload_service(&config.upload).await;
-    let cqrs = cqrs_state(&search, upload_service.clone()).await;
+    let (upload_service, local_storage) = upload_service(&config.upload).await;
+    let fake_clock = config
+        .scheduler
+        .fake_clock
+        .then(|| Arc::new(FakeClock::default()));
+    let clock: Arc<dyn Clock> = match &fake_clock {
+        Some(fake_clock) => {
+            warn!("Running with a fake clock. DO NOT USE IN PRODUCTION!");
+            fake_clock.clone()
+        }
+        None => Arc::new(SystemClock),
+    };
+    let cqrs = cqrs_state(&search, upload_service.clone(), clock).await;
 
     search.ensure_settings().await;
+    // the pending commands can only be loaded after the indices are set up
+    cqrs.scheduler.start().await;
 
     ApplicationState {
         cookie_authority: authority,
@@ -504,5 +741,7 @@ pub async fn new_application_state(config: &crate::config::Config) -> Applicatio
         cqrs,
         search,
         upload_service,
+        local_storage,
+        fake_clock,
     }
 }
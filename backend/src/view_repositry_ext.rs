@@ -48,7 +48,7 @@ where
 impl<V, T> ViewRepositoryExt<V> for T
 where
     V: View,
-    T: ViewRepository<V>,
+    T: ViewRepository<V> + ?Sized,
 {
 }
 
@@ -74,6 +74,6 @@ where
 impl<V, T> LifecycleViewRepositoryExt<V> for T
 where
     V: LifecycleView,
-    T: ViewRepository<LifecycleViewState<V>>,
+    T: ViewRepository<LifecycleViewState<V>> + ?Sized,
 {
 }
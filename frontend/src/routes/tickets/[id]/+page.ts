@@ -1,5 +1,5 @@
 import type { PageLoad } from './$types'
-import type {TicketView, ApiError, UserProfileView, UserId, TicketId, GroupId, GroupProfileView} from 'backend'
+import type {TicketView, ApiError, UserProfileView, UserId, TicketId, GroupId, GroupProfileView, UploadId, UploadMetadata} from 'backend'
 import { Api } from 'backend'
 
 /*
@@ -13,6 +13,7 @@ type Succ = {
   editPermissions: Set<string>,
   users: Record<UserId, UserProfileView>,
   groups: Record<GroupId, GroupProfileView>,
+  uploads: Record<UploadId, UploadMetadata>,
   ticketId: TicketId,
   ticket: TicketView,
 }
@@ -28,7 +29,7 @@ export const load: PageLoad<Data> = async ({ fetch, params }) => {
     const result = await api.getTicket(params.id)
 
     if (result.status === "Success") {
-      const { users, groups, payload: ticket } = result.payload
+      const { users, groups, uploads, payload: ticket } = result.payload
 
       const editPermissions = new Set<string>()
       const destination = ticket.destination
@@ -55,7 +56,7 @@ export const load: PageLoad<Data> = async ({ fetch, params }) => {
         editPermissions.add(ticket.assignee)
       }
 
-      return { status: 'Success', users, ticketId: params.id, editPermissions, groups, ticket } as Succ
+      return { status: 'Success', users, ticketId: params.id, editPermissions, groups, uploads, ticket } as Succ
     } else {
       return { ticketId: params.id, status: 'Error', payload: result.payload } as Err
     }